@@ -0,0 +1,91 @@
+// 予約者の公開鍵で暗号化した入室コードの受け渡し
+// 予約者が予約時に公開鍵を登録し、オーナーはその公開鍵で暗号化した入室コードを予約に記録する
+// 復号は予約者の秘密鍵でのみ行えるため、スマートロックの暗証番号をオンチェーンで安全に渡せる
+
+use near_sdk::serde_json::json;
+use near_sdk::PublicKey;
+
+use crate::event::emit_event;
+use crate::*;
+
+// 暗号化した入室コードの最大の長さ
+const MAX_ACCESS_CODE_LENGTH: usize = 1024;
+
+#[near_bindgen]
+impl Contract {
+    // 入室コードの暗号化に使う公開鍵を登録する
+    // 公開鍵を変更すると、以前の公開鍵で暗号化された入室コードとチェックインの案内は削除される
+    // 確定前・確定済みの予約の予約者のみがコールできる
+    pub fn set_guest_public_key(&mut self, booking_id: BookingId, public_key: PublicKey) {
+        let booking = self.internal_booking(booking_id);
+        assert_eq!(
+            env::signer_account_id(),
+            booking.guest_id,
+            "ERR_NOT_BOOKING_GUEST"
+        );
+        assert!(
+            matches!(
+                booking.status,
+                BookingStatus::Pending | BookingStatus::Confirmed | BookingStatus::AwaitingPayment
+            ),
+            "ERR_CANNOT_CHANGE_BOOKING"
+        );
+
+        self.internal_set_guest_public_key(booking_id, public_key);
+    }
+
+    // 予約者の公開鍵で暗号化した入室コードを予約に記録し、スマートロックの連携向けのイベントを発行する
+    // 確定済み・宿泊中の予約で、予約者が公開鍵を登録している場合のみ記録できる
+    // 部屋のオーナーのみがコールできる
+    pub fn post_access_code(&mut self, booking_id: BookingId, encrypted_access_code: String) {
+        let mut booking = self.internal_booking(booking_id);
+        self.rooms_by_id
+            .get(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM")
+            .assert_owner();
+        assert!(
+            matches!(
+                booking.status,
+                BookingStatus::Confirmed | BookingStatus::CheckedIn
+            ),
+            "ERR_BOOKING_NOT_CONFIRMED"
+        );
+        assert!(
+            booking.guest_public_key.is_some(),
+            "ERR_NO_GUEST_PUBLIC_KEY"
+        );
+        assert!(
+            !encrypted_access_code.is_empty()
+                && encrypted_access_code.len() <= MAX_ACCESS_CODE_LENGTH,
+            "ERR_INVALID_ACCESS_CODE"
+        );
+
+        booking.access_code = Some(encrypted_access_code);
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+        emit_event(
+            "access_code_posted",
+            json!({
+                "booking_id": booking_id,
+                "room_id": booking.room_id,
+                "guest_id": booking.guest_id,
+            }),
+        );
+    }
+}
+
+impl Contract {
+    // 予約に予約者の公開鍵を記録し、以前の公開鍵で暗号化された入室コードとチェックインの案内を削除する
+    pub(crate) fn internal_set_guest_public_key(
+        &mut self,
+        booking_id: BookingId,
+        public_key: PublicKey,
+    ) {
+        let mut booking = self.internal_booking(booking_id);
+        booking.guest_public_key = Some(public_key);
+        booking.access_code = None;
+        booking.encrypted_instructions = None;
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+    }
+}
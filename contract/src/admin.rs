@@ -0,0 +1,50 @@
+// コントラクト全体の設定を管理するアカウント
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // 管理者のアカウントIDを取得する
+    pub fn get_admin(&self) -> AccountId {
+        self.admin_id.clone()
+    }
+
+    // 管理者を別のアカウントに変更する
+    // 現在の管理者のみがコールできる
+    pub fn set_admin(&mut self, admin_id: AccountId) {
+        self.assert_admin();
+        self.admin_id = admin_id;
+    }
+}
+
+impl Contract {
+    // 関数をコールしたアカウントが管理者であるかを確認する
+    // 管理者が署名したトランザクションでも、他のコントラクトを経由したコールは認めない
+    pub(crate) fn assert_admin(&self) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin_id,
+            "ERR_NOT_ADMIN"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    use crate::test_utils::*;
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ADMIN")]
+    fn rejects_admin_call_relayed_by_another_contract() {
+        let (mut contract, _) = setup();
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(contract_id())
+            .signer_account_id(contract_id())
+            .predecessor_account_id(guest_id())
+            .build());
+        contract.set_admin(guest_id());
+    }
+}
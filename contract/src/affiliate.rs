@@ -0,0 +1,89 @@
+// アフィリエイトの成果報酬
+// 予約にアフィリエイトのアカウントを記録し、宿泊した分のオーナーへの支払いの手数料から成果報酬を売上として記録する
+
+use near_sdk::serde_json::json;
+
+use crate::event::emit_event;
+use crate::*;
+
+// 成果報酬率の上限（ベーシスポイント）
+const MAX_AFFILIATE_COMMISSION_BPS: u16 = 10_000;
+
+#[near_bindgen]
+impl Contract {
+    // オーナーへの支払いに対する成果報酬率（ベーシスポイント）を変更する
+    // 成果報酬はその支払いの手数料を上限とし、変更後の成果報酬率はこれから作成される予約にのみ適用される
+    // 管理者のみがコールできる
+    pub fn set_affiliate_commission(&mut self, affiliate_commission_bps: u16) {
+        self.assert_admin();
+        assert!(
+            affiliate_commission_bps <= MAX_AFFILIATE_COMMISSION_BPS,
+            "ERR_INVALID_AFFILIATE_COMMISSION"
+        );
+        self.affiliate_commission_bps = affiliate_commission_bps;
+    }
+
+    // 現在の成果報酬率を取得する
+    pub fn get_affiliate_commission(&self) -> u16 {
+        self.affiliate_commission_bps
+    }
+}
+
+impl Contract {
+    // 予約にアフィリエイトのアカウントを記録する
+    // 予約者と部屋のオーナーはアフィリエイトになれない
+    pub(crate) fn internal_set_affiliate(
+        &mut self,
+        booking_id: BookingId,
+        affiliate_id: AccountId,
+    ) {
+        let mut booking = self.internal_booking(booking_id);
+        assert!(
+            affiliate_id != booking.guest_id
+                && affiliate_id != self.rooms_by_id[&booking.room_id].owner_id,
+            "ERR_INVALID_AFFILIATE"
+        );
+        booking.affiliate_id = Some(affiliate_id);
+        booking.affiliate_commission_bps = self.affiliate_commission_bps;
+        self.bookings_by_id.insert(&booking_id, &booking);
+    }
+
+    // 宿泊した分のオーナーへの支払いの手数料から、アフィリエイトの成果報酬を売上として記録する
+    // キャンセル・ノーショーによる支払いには成果報酬は発生しない
+    // トレジャリーに積み立てる残りの手数料を返す
+    pub(crate) fn internal_pay_affiliate(
+        &mut self,
+        booking: &Booking,
+        amount: Balance,
+        fee: Balance,
+    ) -> Balance {
+        let affiliate_id = match &booking.affiliate_id {
+            Some(affiliate_id)
+                if matches!(
+                    booking.status,
+                    BookingStatus::CheckedIn | BookingStatus::CheckedOut
+                ) =>
+            {
+                affiliate_id
+            }
+            _ => return fee,
+        };
+        let commission = (amount * booking.affiliate_commission_bps as Balance
+            / MAX_AFFILIATE_COMMISSION_BPS as Balance)
+            .min(fee);
+        if commission == 0 {
+            return fee;
+        }
+        self.internal_credit_earnings(affiliate_id, &booking.payment_token, commission);
+        emit_event(
+            "affiliate_commission",
+            json!({
+                "affiliate_id": affiliate_id,
+                "booking_id": booking.booking_id,
+                "token_id": booking.payment_token,
+                "amount": U128(commission),
+            }),
+        );
+        fee - commission
+    }
+}
@@ -0,0 +1,44 @@
+// 部屋の設備（Wi-Fi、駐車場など）
+// オーナーが登録した設備をもとに、必要な設備が全て揃っている部屋を検索できる
+
+use crate::*;
+
+// 1つの部屋に登録できる設備の最大数
+const MAX_AMENITIES: usize = 30;
+// 設備の名前の最大の長さ
+const MAX_AMENITY_LENGTH: usize = 32;
+
+#[near_bindgen]
+impl Contract {
+    // 指定された設備を全て備えている、掲載中の部屋を取得する
+    pub fn get_rooms_with_amenities(
+        &self,
+        amenities: Vec<String>,
+        lang: Option<String>,
+    ) -> Vec<AvailableRoom> {
+        self.rooms_by_id
+            .iter()
+            .filter(|(_, room)| {
+                room.listed
+                    && amenities
+                        .iter()
+                        .all(|amenity| room.amenities.contains(amenity))
+            })
+            .map(|(room_id, room)| room.to_available_room(room_id, lang.as_deref()))
+            .collect()
+    }
+}
+
+// 設備の数と名前の長さを確認し、重複を除いて並べ替える
+pub(crate) fn normalize_amenities(mut amenities: Vec<String>) -> Vec<String> {
+    amenities.sort();
+    amenities.dedup();
+    assert!(amenities.len() <= MAX_AMENITIES, "ERR_TOO_MANY_AMENITIES");
+    assert!(
+        amenities
+            .iter()
+            .all(|amenity| !amenity.is_empty() && amenity.chars().count() <= MAX_AMENITY_LENGTH),
+        "ERR_INVALID_AMENITY"
+    );
+    amenities
+}
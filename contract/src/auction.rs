@@ -0,0 +1,485 @@
+// 需要の高い宿泊日のオークション
+// オーナーが宿泊日をオークションにかけ、最も高い額で入札したアカウントの予約として確定する
+// 公開入札では入札額がそのまま記録され、封印入札では入札期間の終了後に入札額を公開する
+
+use near_sdk::json_types::Base58CryptoHash;
+
+use crate::booking::PaymentMethod;
+use crate::date::NANOS_PER_HOUR;
+use crate::*;
+
+// 1つのオークションで受け付ける入札の最大数
+const MAX_BIDS: usize = 20;
+// 公開の締め切りから、オーナー以外もオークションを終了できるようになるまでの時間
+const FINALIZE_GRACE_HOURS: u64 = 24;
+
+// オークションのデータ
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Auction {
+    check_out_date: CheckInDate, // チェックアウト日
+    min_bid: U128,               // 最低入札額（掲出料の合計）
+    sealed: bool,                // 封印入札か
+    bidding_ends_at: u64,        // 入札を締め切る時刻
+    reveal_ends_at: u64, // 封印した入札額の公開を締め切る時刻（公開入札では入札の締め切りと同じ）
+    bids: Vec<Bid>,      // 入札
+}
+
+// オークションへの入札
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Bid {
+    bidder_id: AccountId,               // 入札したアカウントID
+    guests: u16,                        // 宿泊する人数
+    amount: U128,                       // 入札額（封印入札で未公開の場合は0）
+    deposit: U128,                      // 入札時に添付されたNEAR
+    bid_hash: Option<Base58CryptoHash>, // 封印入札の`"{入札額}:{salt}"`のハッシュ
+}
+
+#[near_bindgen]
+impl Contract {
+    // 宿泊日をオークションにかける
+    // オークションの期間中は、通常の予約を受け付けない
+    // 封印入札の場合は、入札の締め切りから`reveal_hours`時間の間に入札額を公開する
+    // 部屋のデータが増えた分のストレージの代金は、預けている代金と添付されたNEARから支払う
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn open_auction(
+        &mut self,
+        room_id: RoomId,
+        check_in_date: CheckInDate,
+        check_out_date: CheckInDate,
+        min_bid: U128,
+        bidding_hours: u16,
+        sealed: bool,
+        reveal_hours: u16,
+    ) {
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        // 入札はNEARで受け付けるため、NEARで掲出料を決める部屋のみオークションにかけられる
+        assert!(
+            !room.usd_pricing && room.payment_token.is_none() && room.pricing_contract_id.is_none(),
+            "ERR_AUCTION_ROOM_PAYS_IN_NEAR"
+        );
+        assert!(bidding_hours > 0, "ERR_INVALID_AUCTION_DURATION");
+        assert!(sealed || reveal_hours == 0, "ERR_REVEAL_FOR_OPEN_AUCTION");
+
+        let nights = date::nights(&check_in_date, &check_out_date);
+        room.assert_stay_length(nights.len());
+        for night in nights.iter() {
+            assert!(room.is_available(night), "ERR_ALREADY_BOOKED");
+        }
+
+        let bidding_ends_at = env::block_timestamp() + bidding_hours as u64 * NANOS_PER_HOUR;
+        let reveal_ends_at = bidding_ends_at + reveal_hours as u64 * NANOS_PER_HOUR;
+        assert!(
+            (reveal_ends_at as i64) < room.local_start_timestamp(&check_in_date),
+            "ERR_AUCTION_ENDS_AFTER_CHECK_IN"
+        );
+
+        room.auctions.insert(
+            check_in_date,
+            Auction {
+                check_out_date,
+                min_bid,
+                sealed,
+                bidding_ends_at,
+                reveal_ends_at,
+                bids: Vec::new(),
+            },
+        );
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // オークションに入札する
+    // 公開入札では`amount`を、封印入札では`sha256("{入札額}:{salt}")`を`bid_hash`に指定する
+    // 入札額・追加料金・保証金の合計以上のNEARを添付する（封印入札では添付したNEARが入札額の上限になる）
+    // 既に入札している場合は、以前の入札を取り消して返金する
+    #[payable]
+    pub fn place_bid(
+        &mut self,
+        room_id: RoomId,
+        check_in_date: CheckInDate,
+        amount: Option<U128>,
+        bid_hash: Option<Base58CryptoHash>,
+        guests: Option<u16>,
+    ) {
+        let bidder_id = env::signer_account_id();
+        let deposit = env::attached_deposit();
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        let initial_size = room.serialized_size();
+        let guests = guests.unwrap_or(1);
+        assert!(guests > 0, "ERR_INVALID_GUESTS");
+        let auction = room
+            .auctions
+            .get(&check_in_date)
+            .expect("ERR_NOT_FOUND_AUCTION");
+        let extras = room.auction_extras(&check_in_date, &auction.check_out_date, guests);
+        let auction = room.auctions.get_mut(&check_in_date).unwrap();
+        assert!(
+            env::block_timestamp() < auction.bidding_ends_at,
+            "ERR_BIDDING_CLOSED"
+        );
+
+        let amount = if auction.sealed {
+            assert!(
+                amount.is_none() && bid_hash.is_some(),
+                "ERR_SEALED_BID_REQUIRES_HASH"
+            );
+            0
+        } else {
+            let amount = amount.expect("ERR_BID_AMOUNT_REQUIRED").0;
+            assert!(bid_hash.is_none(), "ERR_OPEN_BID_HASH");
+            assert!(amount >= auction.min_bid.0, "ERR_BID_TOO_LOW");
+            assert!(amount > auction.highest_amount(), "ERR_BID_TOO_LOW");
+            assert!(deposit >= amount + extras, "ERR_DEPOSIT_IS_INCORRECT");
+            amount
+        };
+        assert!(deposit > extras, "ERR_DEPOSIT_IS_INCORRECT");
+
+        let previous = auction
+            .bids
+            .iter()
+            .position(|bid| bid.bidder_id == bidder_id)
+            .map(|index| auction.bids.remove(index));
+        assert!(auction.bids.len() < MAX_BIDS, "ERR_TOO_MANY_BIDS");
+        auction.bids.push(Bid {
+            bidder_id: bidder_id.clone(),
+            guests,
+            amount: U128(amount),
+            deposit: U128(deposit),
+            bid_hash,
+        });
+        self.internal_track_room_storage(&room_id, initial_size);
+
+        if let Some(previous) = previous {
+            self.internal_send_refund(bidder_id, None, previous.deposit.0);
+        }
+    }
+
+    // 封印入札の入札額を公開する
+    // 入札の締め切りから公開の締め切りまでの間にコールする
+    pub fn reveal_bid(
+        &mut self,
+        room_id: RoomId,
+        check_in_date: CheckInDate,
+        amount: U128,
+        salt: String,
+    ) {
+        let bidder_id = env::signer_account_id();
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        let auction = room
+            .auctions
+            .get(&check_in_date)
+            .expect("ERR_NOT_FOUND_AUCTION");
+        let now = env::block_timestamp();
+        assert!(
+            auction.bidding_ends_at <= now && now < auction.reveal_ends_at,
+            "ERR_NOT_REVEAL_PERIOD"
+        );
+        let bid = auction
+            .bids
+            .iter()
+            .find(|bid| bid.bidder_id == bidder_id)
+            .expect("ERR_NOT_FOUND_BID");
+        let extras = room.auction_extras(&check_in_date, &auction.check_out_date, bid.guests);
+
+        let auction = room.auctions.get_mut(&check_in_date).unwrap();
+        let min_bid = auction.min_bid.0;
+        let bid = auction
+            .bids
+            .iter_mut()
+            .find(|bid| bid.bidder_id == bidder_id)
+            .unwrap();
+        let hash: CryptoHash = env::sha256(format!("{}:{}", amount.0, salt).as_bytes())
+            .try_into()
+            .unwrap();
+        assert_eq!(
+            bid.bid_hash.map(CryptoHash::from),
+            Some(hash),
+            "ERR_BID_HASH_MISMATCH"
+        );
+        assert!(amount.0 >= min_bid, "ERR_BID_TOO_LOW");
+        assert!(
+            bid.deposit.0 >= amount.0 + extras,
+            "ERR_DEPOSIT_IS_INCORRECT"
+        );
+
+        bid.amount = amount;
+        bid.bid_hash = None;
+    }
+
+    // オークションを終了し、最も高い額で入札したアカウントの予約を作成する
+    // 落札されなかった入札と、落札額を超えて添付されたNEARは返金する
+    // 入札後に部屋の設定が変わり落札者の予約を作成できない場合は、落札者にも全額を返金する
+    // 部屋のオーナーは公開の締め切り後に、それ以外のアカウントは締め切りから`FINALIZE_GRACE_HOURS`時間後にコールできる
+    // 作成した予約のIDを返す（入札がない場合と、予約を作成できない場合は`None`）
+    pub fn finalize_auction(
+        &mut self,
+        room_id: RoomId,
+        check_in_date: CheckInDate,
+    ) -> Option<BookingId> {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        let auction = room
+            .auctions
+            .get(&check_in_date)
+            .expect("ERR_NOT_FOUND_AUCTION");
+        let now = env::block_timestamp();
+        assert!(now >= auction.reveal_ends_at, "ERR_AUCTION_NOT_ENDED");
+        // オーナーが終了しない場合でも、入札したアカウントが返金を受けられるようにする
+        if env::signer_account_id() != room.owner_id {
+            assert!(
+                now >= auction.reveal_ends_at + FINALIZE_GRACE_HOURS * NANOS_PER_HOUR,
+                "ERR_NOT_ROOM_OWNER"
+            );
+        }
+        // オークションを終了してから予約を作成し、宿泊日を確保できるようにする
+        let initial_size = room.serialized_size();
+        let mut auction = room.auctions.remove(&check_in_date).unwrap();
+
+        // 同額の入札は先に入札したアカウントを優先する
+        let winner = auction
+            .bids
+            .iter()
+            .enumerate()
+            .filter(|(_, bid)| bid.amount.0 > 0)
+            .max_by_key(|(index, bid)| (bid.amount.0, std::cmp::Reverse(*index)))
+            .map(|(index, _)| index)
+            .map(|index| auction.bids.remove(index));
+        let bookable = winner.as_ref().is_some_and(|winner| {
+            room.can_book_winning_bid(&check_in_date, &auction.check_out_date, winner)
+        });
+        self.internal_track_room_storage(&room_id, initial_size);
+        for bid in auction.bids {
+            self.internal_send_refund(bid.bidder_id, None, bid.deposit.0);
+        }
+
+        let winner = winner?;
+        if !bookable {
+            self.internal_send_refund(winner.bidder_id, None, winner.deposit.0);
+            return None;
+        }
+        let (booking_id, total) = self.internal_create_booking(
+            &winner.bidder_id,
+            PaymentMethod::Auction(winner.amount.0),
+            &room_id,
+            check_in_date,
+            auction.check_out_date,
+            1,
+            Some(winner.guests),
+            None,
+            None,
+            None,
+            None,
+        );
+        if winner.deposit.0 > total {
+            self.internal_send_refund(winner.bidder_id, None, winner.deposit.0 - total);
+        }
+        Some(booking_id)
+    }
+
+    // オークションを取り消し、全ての入札を返金する
+    // 部屋のオーナーのみがコールできる
+    pub fn cancel_auction(&mut self, room_id: RoomId, check_in_date: CheckInDate) {
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        let auction = room
+            .auctions
+            .remove(&check_in_date)
+            .expect("ERR_NOT_FOUND_AUCTION");
+        self.internal_update_room_storage(&room_id, initial_usage);
+
+        for bid in auction.bids {
+            self.internal_send_refund(bid.bidder_id, None, bid.deposit.0);
+        }
+    }
+
+    // オークションの状況を取得する
+    pub fn get_auction(&self, room_id: RoomId, check_in_date: CheckInDate) -> Option<Auction> {
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        room.auctions.get(&check_in_date).cloned()
+    }
+}
+
+impl Room {
+    // 指定された日付がオークションの期間中の宿泊日かを確認する
+    pub(crate) fn is_in_auction(&self, date: &CheckInDate) -> bool {
+        self.auctions
+            .iter()
+            .any(|(check_in_date, auction)| check_in_date <= date && *date < auction.check_out_date)
+    }
+
+    // 落札した場合に入札額に加えて支払う追加料金と保証金の合計を返す
+    fn auction_extras(
+        &self,
+        check_in_date: &CheckInDate,
+        check_out_date: &CheckInDate,
+        guests: u16,
+    ) -> Balance {
+        let nights = date::nights(check_in_date, check_out_date);
+        let (cleaning_fee, extra_guest_fee) = self.extra_fees(nights.len(), 1, guests);
+        cleaning_fee + extra_guest_fee + self.security_deposit.0
+    }
+
+    // 現在の部屋の設定で、落札者の予約を作成できるかを返す
+    // 入札後に部屋の掲載・支払い方法・宿泊の条件・追加料金・保証金が変更されていないか、宿泊日が塞がれていないかを確認する
+    fn can_book_winning_bid(
+        &self,
+        check_in_date: &CheckInDate,
+        check_out_date: &CheckInDate,
+        bid: &Bid,
+    ) -> bool {
+        let nights = date::nights(check_in_date, check_out_date);
+        let extras = self.auction_extras(check_in_date, check_out_date, bid.guests);
+        self.listed
+            && self.payment_token.is_none()
+            && self.pricing_contract_id.is_none()
+            && self.allows_stay_length(nights.len())
+            && self.is_within_max_guests(1, bid.guests)
+            && self.can_reserve_nights(&nights, &bid.bidder_id, 1)
+            && bid.deposit.0 >= bid.amount.0 + extras
+    }
+}
+
+impl Auction {
+    // 公開されている最も高い入札額を返す
+    fn highest_amount(&self) -> Balance {
+        self.bids.iter().map(|bid| bid.amount.0).max().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::ONE_NEAR;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    // 10日後から1泊の公開入札のオークションを開き、予約者が`PRICE`で入札する
+    fn open_and_bid(contract: &mut Contract, room_id: &RoomId) -> CheckInDate {
+        let check_in_date = today().add_days(10);
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.open_auction(
+            room_id.clone(),
+            check_in_date,
+            check_in_date.add_days(1),
+            U128(PRICE),
+            1,
+            false,
+            0,
+        );
+        set_caller(&guest_id(), PRICE);
+        contract.place_bid(
+            room_id.clone(),
+            check_in_date,
+            Some(U128(PRICE)),
+            None,
+            None,
+        );
+        check_in_date
+    }
+
+    fn stranger_id() -> AccountId {
+        "stranger.near".parse().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ROOM_OWNER")]
+    fn stranger_cannot_finalize_before_grace_period() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = open_and_bid(&mut contract, &room_id);
+
+        set_caller_at(&stranger_id(), 0, noon(&today()) + 2 * NANOS_PER_HOUR);
+        contract.finalize_auction(room_id, check_in_date);
+    }
+
+    #[test]
+    fn stranger_finalizes_after_grace_period() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = open_and_bid(&mut contract, &room_id);
+
+        let after_grace = noon(&today()) + (1 + FINALIZE_GRACE_HOURS) * NANOS_PER_HOUR;
+        set_caller_at(&stranger_id(), 0, after_grace);
+        let booking_id = contract.finalize_auction(room_id.clone(), check_in_date);
+        assert!(booking_id.is_some());
+        assert!(contract.get_auction(room_id, check_in_date).is_none());
+    }
+
+    #[test]
+    fn refunds_winner_when_fees_raised_after_bid() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = open_and_bid(&mut contract, &room_id);
+        set_caller(&owner_id(), 0);
+        contract.set_extra_fees(room_id.clone(), U128(PRICE / 10), U128(0), 1);
+
+        set_caller_at(&owner_id(), 0, noon(&today()) + NANOS_PER_HOUR);
+        assert_eq!(
+            contract.finalize_auction(room_id.clone(), check_in_date),
+            None
+        );
+        assert_eq!(transferred_to(&guest_id()), PRICE);
+        assert!(contract.get_auction(room_id, check_in_date).is_none());
+    }
+
+    #[test]
+    fn refunds_winner_when_dates_blocked_after_bid() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = open_and_bid(&mut contract, &room_id);
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.block_dates(room_id.clone(), vec![check_in_date]);
+
+        set_caller_at(&owner_id(), 0, noon(&today()) + NANOS_PER_HOUR);
+        assert_eq!(contract.finalize_auction(room_id, check_in_date), None);
+        assert_eq!(transferred_to(&guest_id()), PRICE);
+    }
+
+    #[test]
+    fn books_highest_bid_and_refunds_outbid_guest() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = open_and_bid(&mut contract, &room_id);
+        set_caller(&stranger_id(), 2 * PRICE);
+        contract.place_bid(
+            room_id.clone(),
+            check_in_date,
+            Some(U128(2 * PRICE)),
+            None,
+            None,
+        );
+
+        set_caller_at(&owner_id(), 0, noon(&today()) + NANOS_PER_HOUR);
+        let booking_id = contract
+            .finalize_auction(room_id.clone(), check_in_date)
+            .unwrap();
+        let booking = contract.internal_booking(booking_id);
+        assert_eq!(booking.guest_id, stranger_id());
+        assert_eq!(booking.amount.0, 2 * PRICE);
+        assert_eq!(transferred_to(&guest_id()), PRICE);
+        assert_eq!(transferred_to(&stranger_id()), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BID_TOO_LOW")]
+    fn rejects_open_bid_not_above_highest() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = open_and_bid(&mut contract, &room_id);
+        set_caller(&stranger_id(), PRICE);
+        contract.place_bid(room_id, check_in_date, Some(U128(PRICE)), None, None);
+    }
+}
@@ -0,0 +1,1099 @@
+use crate::date::NANOS_PER_HOUR;
+use crate::*;
+
+// 繰り返し予約で一度に予約できる最大回数
+const MAX_RECURRING_OCCURRENCES: u16 = 52;
+
+// 予約者がオーナーに伝えるメモの最大文字数
+const MAX_NOTE_LENGTH: usize = 500;
+
+// 複数の部屋をまとめて予約する際の、部屋ごとの予約内容
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoomBookingRequest {
+    room_id: RoomId,
+    check_in_date: CheckInDate,
+    check_out_date: CheckInDate,
+    guests: Option<u16>,
+    note: Option<String>,
+}
+
+// 予約の支払い方法
+pub(crate) enum PaymentMethod {
+    Near,             // 掲出料と同額のNEARを添付する（不足する分はクレジットから支払う）
+    Token(AccountId), // NEP-141のトークンで支払う
+    OracleNear,       // 米ドル建ての掲出料を、オラクルの価格でNEARに換算して支払う
+    ExternalPrice,    // 料金設定コントラクトが決めた掲出料をNEARで支払う
+    Auction(Balance), // オークションの落札額を掲出料としてNEARで支払う
+}
+
+// 予約者の予約履歴を表示する際に使用
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GuestBooking {
+    booking_id: BookingId,
+    room_id: RoomId,
+    name: String,
+    cover_image: String,
+    check_in_date: CheckInDate,
+    check_out_date: CheckInDate,
+    amount: U128,
+    status: BookingStatus,
+}
+
+#[near_bindgen]
+impl Contract {
+    // 部屋を1泊分予約し、予約IDを返す
+    // 予約者は掲出料と同額のNEARを添付してコールする（不足する分はクレジットから支払われる）
+    // 支払われたNEARはチェックアウトまでコントラクトが預かる
+    // プロモーションコードを指定すると、掲出料が割り引かれる
+    // アフィリエイトのアカウントを指定すると、宿泊後にそのアカウントに成果報酬が記録される
+    // 返金不可の料金プランを選ぶと、割安な掲出料で予約できるが、キャンセルしても掲出料は返金されない
+    // 公開鍵を指定すると、オーナーはその公開鍵で暗号化した入室コードを予約に記録できる
+    #[payable]
+    pub fn book_room(
+        &mut self,
+        room_id: RoomId,
+        check_in_date: CheckInDate,
+        note: Option<String>,
+        promo_code: Option<String>,
+        loyalty_points: Option<U128>,
+        affiliate_id: Option<AccountId>,
+        rate_plan: Option<RatePlan>,
+        guest_public_key: Option<PublicKey>,
+    ) -> BookingId {
+        let check_out_date = check_in_date.next();
+        let (booking_id, total) = self.internal_create_booking(
+            &env::signer_account_id(),
+            PaymentMethod::Near,
+            &room_id,
+            check_in_date,
+            check_out_date,
+            1,
+            None,
+            note,
+            promo_code,
+            loyalty_points,
+            rate_plan,
+        );
+        if let Some(affiliate_id) = affiliate_id {
+            self.internal_set_affiliate(booking_id, affiliate_id);
+        }
+        if let Some(guest_public_key) = guest_public_key {
+            self.internal_set_guest_public_key(booking_id, guest_public_key);
+        }
+        self.internal_collect_deposit(&env::signer_account_id(), total);
+        booking_id
+    }
+
+    // チェックイン日からチェックアウト日の前日までの複数泊をまとめて予約し、予約IDを返す
+    // 1泊でも予約済みの日があれば、全ての予約が失敗する
+    #[payable]
+    pub fn book_stay(
+        &mut self,
+        room_id: RoomId,
+        check_in_date: CheckInDate,
+        check_out_date: CheckInDate,
+        guests: Option<u16>,
+        note: Option<String>,
+        promo_code: Option<String>,
+        loyalty_points: Option<U128>,
+        affiliate_id: Option<AccountId>,
+        rate_plan: Option<RatePlan>,
+        guest_public_key: Option<PublicKey>,
+    ) -> BookingId {
+        let (booking_id, total) = self.internal_create_booking(
+            &env::signer_account_id(),
+            PaymentMethod::Near,
+            &room_id,
+            check_in_date,
+            check_out_date,
+            1,
+            guests,
+            note,
+            promo_code,
+            loyalty_points,
+            rate_plan,
+        );
+        if let Some(affiliate_id) = affiliate_id {
+            self.internal_set_affiliate(booking_id, affiliate_id);
+        }
+        if let Some(guest_public_key) = guest_public_key {
+            self.internal_set_guest_public_key(booking_id, guest_public_key);
+        }
+        self.internal_collect_deposit(&env::signer_account_id(), total);
+        booking_id
+    }
+
+    // ベッド単位で予約を受け付ける部屋の、指定された数のベッドをまとめて予約し、予約IDを返す
+    // 掲出料はベッド1台・1泊あたりの金額として計算する
+    #[payable]
+    pub fn book_beds(
+        &mut self,
+        room_id: RoomId,
+        check_in_date: CheckInDate,
+        check_out_date: CheckInDate,
+        beds: u16,
+        guests: Option<u16>,
+        note: Option<String>,
+    ) -> BookingId {
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        assert!(room.bookable_by_bed, "ERR_NOT_BOOKABLE_BY_BED");
+        assert!(beds > 0, "ERR_INVALID_BEDS");
+
+        let (booking_id, total) = self.internal_create_booking(
+            &env::signer_account_id(),
+            PaymentMethod::Near,
+            &room_id,
+            check_in_date,
+            check_out_date,
+            beds,
+            guests,
+            note,
+            None,
+            None,
+            None,
+        );
+        self.internal_collect_deposit(&env::signer_account_id(), total);
+        booking_id
+    }
+
+    // 同じ条件の部屋を指定された数だけまとめて予約し、予約IDを返す
+    // 掲出料は部屋1室・1泊あたりの金額として計算する
+    #[payable]
+    pub fn book_units(
+        &mut self,
+        room_id: RoomId,
+        check_in_date: CheckInDate,
+        check_out_date: CheckInDate,
+        units: u16,
+        guests: Option<u16>,
+        note: Option<String>,
+    ) -> BookingId {
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        assert!(!room.bookable_by_bed, "ERR_BOOKABLE_BY_BED");
+        assert!(units > 0, "ERR_INVALID_UNITS");
+
+        let (booking_id, total) = self.internal_create_booking(
+            &env::signer_account_id(),
+            PaymentMethod::Near,
+            &room_id,
+            check_in_date,
+            check_out_date,
+            units,
+            guests,
+            note,
+            None,
+            None,
+            None,
+        );
+        self.internal_collect_deposit(&env::signer_account_id(), total);
+        booking_id
+    }
+
+    // 一定間隔で繰り返す宿泊日（例: 毎週月曜日を8週間）をまとめて予約し、予約IDの一覧を返す
+    // 全ての宿泊日が予約可能な場合のみ成功し、掲出料は合計をまとめて支払う
+    #[payable]
+    pub fn book_recurring(
+        &mut self,
+        room_id: RoomId,
+        start_date: CheckInDate,
+        interval_days: u16,
+        occurrences: u16,
+        note: Option<String>,
+    ) -> Vec<BookingId> {
+        assert!(interval_days > 0, "ERR_INVALID_INTERVAL");
+        assert!(
+            occurrences > 0 && occurrences <= MAX_RECURRING_OCCURRENCES,
+            "ERR_INVALID_OCCURRENCES"
+        );
+
+        let mut booking_ids = vec![];
+        let mut total: Balance = 0;
+        for night in date::recurring(&start_date, interval_days, occurrences) {
+            let (booking_id, amount) = self.internal_create_booking(
+                &env::signer_account_id(),
+                PaymentMethod::Near,
+                &room_id,
+                night,
+                night.next(),
+                1,
+                None,
+                note.clone(),
+                None,
+                None,
+                None,
+            );
+            booking_ids.push(booking_id);
+            total += amount;
+        }
+        self.internal_collect_deposit(&env::signer_account_id(), total);
+        booking_ids
+    }
+
+    // 複数の部屋をまとめて予約し、予約IDの一覧を返す
+    // 全ての部屋・宿泊日が予約可能な場合のみ成功し、1つでも予約できなければ全体が取り消される
+    // 添付するNEARとクレジットで、全ての予約の掲出料の合計を支払う
+    #[payable]
+    pub fn book_rooms(&mut self, requests: Vec<RoomBookingRequest>) -> Vec<BookingId> {
+        assert!(!requests.is_empty(), "ERR_NO_BOOKING_REQUEST");
+
+        let mut booking_ids = vec![];
+        let mut total: Balance = 0;
+        for request in requests {
+            let (booking_id, amount) = self.internal_create_booking(
+                &env::signer_account_id(),
+                PaymentMethod::Near,
+                &request.room_id,
+                request.check_in_date,
+                request.check_out_date,
+                1,
+                request.guests,
+                request.note,
+                None,
+                None,
+                None,
+            );
+            booking_ids.push(booking_id);
+            total += amount;
+        }
+        self.internal_collect_deposit(&env::signer_account_id(), total);
+        booking_ids
+    }
+
+    // 予約IDから予約のデータを取得する
+    pub fn get_booking(&self, booking_id: BookingId) -> Option<Booking> {
+        self.bookings_by_id.get(&booking_id)
+    }
+
+    // 部屋の予約を予約した順に取得する
+    // 予約者からのメモも含まれるため、オーナーが到着時刻などを確認する際に使用する
+    // `from_index`番目から最大`limit`件を返す
+    pub fn get_bookings_by_room(
+        &self,
+        room_id: RoomId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<Booking> {
+        self.bookings_per_room
+            .get(&room_id)
+            .unwrap_or_default()
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|booking_id| self.bookings_by_id.get(booking_id).unwrap())
+            .collect()
+    }
+
+    // 予約者の予約履歴を予約した順に取得する
+    // `from_index`番目から最大`limit`件を返す
+    pub fn get_bookings_by_guest(
+        &self,
+        guest_id: AccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<GuestBooking> {
+        self.bookings_per_guest
+            .get(&guest_id)
+            .unwrap_or_default()
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|booking_id| {
+                let booking = self.bookings_by_id.get(booking_id).unwrap();
+                // 削除された部屋の予約は、部屋の名前と画像を空にして返す
+                let room = self.rooms_by_id.get(&booking.room_id);
+                GuestBooking {
+                    booking_id: booking.booking_id,
+                    name: room.map(|room| room.name.clone()).unwrap_or_default(),
+                    cover_image: room.map(|room| room.cover_image()).unwrap_or_default(),
+                    room_id: booking.room_id,
+                    check_in_date: booking.check_in_date,
+                    check_out_date: booking.check_out_date,
+                    amount: booking.amount,
+                    status: booking.status,
+                }
+            })
+            .collect()
+    }
+
+    // 予約をキャンセルし、キャンセルポリシーに基づいて掲出料を予約者とオーナーに分配する
+    // 追加料金と保証金は全額を予約者に返金する
+    // キャンセルできるのは予約したアカウントのみ
+    pub fn cancel_booking(&mut self, booking_id: BookingId) -> Promise {
+        let booking = self.internal_booking(booking_id);
+        assert_eq!(
+            env::signer_account_id(),
+            booking.guest_id,
+            "ERR_NOT_BOOKING_GUEST"
+        );
+
+        // チェックイン前の予約のみキャンセルできる
+        assert!(
+            matches!(
+                booking.status,
+                BookingStatus::Pending | BookingStatus::Confirmed
+            ),
+            "ERR_CANNOT_CANCEL_BOOKING"
+        );
+        self.internal_cancel_booking(booking_id)
+    }
+
+    // 予約したチェックイン日を別の日に変更する
+    // 宿泊数はそのままで、支払い済みの掲出料は引き継がれ、新しい日付の掲出料との差額のみ精算する
+    // 無料で変更できる期限を過ぎている場合は、変更手数料も預かっている掲出料から差し引き、すぐにオーナーに支払う
+    // 支払った変更手数料は、予約をキャンセルしても返金されない
+    // 不足する場合は、その分のNEARを添付してコールする
+    #[payable]
+    pub fn change_booking(&mut self, booking_id: BookingId, new_date: CheckInDate) {
+        let mut booking = self.internal_booking(booking_id);
+        let guest_id = env::signer_account_id();
+        assert_eq!(guest_id, booking.guest_id, "ERR_NOT_BOOKING_GUEST");
+
+        // チェックイン前の予約のみ変更できる
+        assert!(
+            matches!(
+                booking.status,
+                BookingStatus::Pending | BookingStatus::Confirmed
+            ),
+            "ERR_CANNOT_CHANGE_BOOKING"
+        );
+        // 分割払いの残額が未払いの予約は、残額を支払うまで変更できない
+        assert_eq!(booking.balance_due.0, 0, "ERR_BALANCE_DUE");
+
+        let room = self
+            .rooms_by_id
+            .get_mut(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        let initial_size = room.serialized_size();
+        // 料金設定コントラクトが指定された部屋は、掲出料を再計算できないため変更できない
+        assert!(
+            room.pricing_contract_id.is_none(),
+            "ERR_EXTERNALLY_PRICED_ROOM"
+        );
+        // 米ドル建ての部屋は、支払い済みのNEARと掲出料の単位が異なり差額を精算できないため変更できない
+        assert!(!room.usd_pricing, "ERR_USD_PRICED_ROOM");
+        let old_nights = date::nights(&booking.check_in_date, &booking.check_out_date);
+        let new_check_out_date = new_date.add_days(old_nights.len() as u64);
+        let new_nights = date::nights(&new_date, &new_check_out_date);
+
+        // 差額と変更手数料を精算する
+        // 変更後も含まれる宿泊日は支払い済みの掲出料（落札額を含む）を按分して引き継ぎ、
+        // 新しく加わる宿泊日のみ現在の掲出料から予約時と同じ割合で割り引いて計算する
+        let paid = booking.amount.0;
+        let kept_nights = new_nights
+            .iter()
+            .filter(|night| old_nights.contains(night))
+            .count() as Balance;
+        let added_nights: Vec<CheckInDate> = new_nights
+            .iter()
+            .filter(|night| !old_nights.contains(night))
+            .copied()
+            .collect();
+        let added_list_price = room.price_for_guest(
+            &booking.guest_id,
+            &added_nights,
+            booking.quantity,
+            &booking.rate_plan,
+        );
+        let discount_bps = match paid + booking.discount.0 {
+            0 => 0,
+            list_price => booking.discount.0 * 10_000 / list_price,
+        };
+        let added_price = added_list_price - added_list_price * discount_bps / 10_000;
+        let old_nights_count = old_nights.len() as Balance;
+        let new_price = paid * kept_nights / old_nights_count + added_price;
+        let new_discount =
+            booking.discount.0 * kept_nights / old_nights_count + (added_list_price - added_price);
+        let fee = room.reschedule_fee_for(&booking.check_in_date);
+        let due = new_price + fee;
+        let deposit = env::attached_deposit();
+        let refund = if due > paid {
+            // トークンで支払われた予約は、NEARで差額を支払えない
+            assert!(booking.payment_token.is_none(), "ERR_PAY_WITH_TOKEN");
+            assert_eq!(deposit, due - paid, "ERR_DEPOSIT_IS_INCORRECT");
+            0
+        } else {
+            assert_eq!(deposit, 0, "ERR_DEPOSIT_IS_INCORRECT");
+            paid - due
+        };
+
+        // 元の宿泊日を解放してから、新しい宿泊日を予約する
+        room.release_nights(&old_nights, booking_id, booking.quantity);
+        room.reserve_nights(&new_nights, &guest_id, booking_id, booking.quantity);
+        for night in old_nights.iter() {
+            if !new_nights.contains(night) {
+                room.offer_to_waitlist(night);
+            }
+        }
+
+        booking.check_in_date = new_date;
+        booking.check_out_date = new_check_out_date;
+        booking.amount = U128(new_price);
+        booking.discount = U128(new_discount);
+        booking.reschedule_fees = U128(booking.reschedule_fees.0 + fee);
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+        self.internal_track_room_storage(&booking.room_id, initial_size);
+
+        self.internal_hold_escrow(booking_id, deposit);
+        if fee > 0 {
+            self.internal_pay_owner(booking_id, fee);
+        }
+        if refund > 0 {
+            self.internal_release_escrow(booking_id, guest_id, refund);
+        }
+    }
+
+    // 承認待ちの予約リクエストを承認する
+    // 承認期間内に部屋のオーナーと、施設のマネージャー・フロントがコールできる
+    pub fn approve_booking(&mut self, booking_id: BookingId) {
+        let mut booking = self.internal_booking(booking_id);
+        self.assert_room_operator(&booking.room_id, FRONT_DESK_ROLES);
+        let room = self
+            .rooms_by_id
+            .get(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        let approval_window = room.approval_window_hours * NANOS_PER_HOUR;
+
+        assert_eq!(
+            booking.status,
+            BookingStatus::Pending,
+            "ERR_BOOKING_NOT_PENDING"
+        );
+        assert!(
+            !booking.is_approval_expired(approval_window),
+            "ERR_APPROVAL_WINDOW_EXPIRED"
+        );
+
+        booking.status = BookingStatus::Confirmed;
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+        self.internal_start_payment_window(booking_id);
+    }
+
+    // 承認待ちの予約リクエストを却下し、預かっていた掲出料・追加料金・保証金を予約者に返金する
+    // 部屋のオーナーと、施設のマネージャー・フロントがコールできる。承認期間を過ぎたリクエストは誰でも却下できる
+    pub fn reject_booking(&mut self, booking_id: BookingId) -> Promise {
+        let mut booking = self.internal_booking(booking_id);
+        let is_operator = self.is_room_operator(&booking.room_id, FRONT_DESK_ROLES);
+        let room = self
+            .rooms_by_id
+            .get(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        let approval_window = room.approval_window_hours * NANOS_PER_HOUR;
+
+        assert_eq!(
+            booking.status,
+            BookingStatus::Pending,
+            "ERR_BOOKING_NOT_PENDING"
+        );
+        assert!(
+            is_operator || booking.is_approval_expired(approval_window),
+            "ERR_NOT_ROOM_OWNER"
+        );
+
+        let refund = self.escrow_by_booking.get(&booking_id).unwrap_or(0);
+        self.internal_close_booking(&mut booking, BookingStatus::Cancelled);
+        self.internal_release_escrow(booking_id, booking.guest_id, refund)
+    }
+
+    // 当日を含む予約にチェックインし、宿泊中の予約として記録する
+    // 清掃が終わっていない部屋にはチェックインできない
+    // 予約者、部屋のオーナー、施設のマネージャー・フロントがコールできる
+    pub fn check_in(&mut self, booking_id: BookingId) {
+        let mut booking = self.internal_booking(booking_id);
+        assert!(
+            env::signer_account_id() == booking.guest_id
+                || self.is_room_operator(&booking.room_id, FRONT_DESK_ROLES),
+            "ERR_NOT_ALLOWED"
+        );
+        let room = self
+            .rooms_by_id
+            .get_mut(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        assert_eq!(
+            booking.status,
+            BookingStatus::Confirmed,
+            "ERR_BOOKING_NOT_CONFIRMED"
+        );
+        assert_eq!(booking.balance_due.0, 0, "ERR_BALANCE_DUE");
+        // 部屋を利用できないとして補償を請求している間は、裁定されるまでチェックインできない
+        assert!(
+            !booking.has_pending_insurance_claim(),
+            "ERR_INSURANCE_CLAIM_PENDING"
+        );
+        assert_eq!(
+            room.housekeeping_status,
+            HousekeepingStatus::Ready,
+            "ERR_ROOM_NOT_READY"
+        );
+
+        // 施設のタイムゾーンで、当日が予約期間に含まれている場合のみチェックインできる
+        let today = room.today();
+        assert!(
+            booking.check_in_date <= today && today < booking.check_out_date,
+            "ERR_NOT_CHECK_IN_DATE"
+        );
+
+        // 長期滞在の場合は、チェックインした時点から掲出料のストリーミング払いを始める
+        if room.streams(&booking) {
+            booking.stream_started_at = env::block_timestamp();
+        }
+        let initial_size = room.serialized_size();
+        room.stays.push(booking_id);
+        self.internal_track_room_storage(&booking.room_id, initial_size);
+        booking.status = BookingStatus::CheckedIn;
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+        self.internal_capture_on_check_in(booking_id);
+    }
+
+    // 宿泊中の予約をチェックアウトする
+    // 預かっていた掲出料はオーナーの売上に記録され、保証金は部屋の設定に応じて予約者に返金される
+    pub fn check_out(&mut self, booking_id: BookingId) {
+        let booking = self.internal_booking(booking_id);
+        assert_eq!(
+            booking.status,
+            BookingStatus::CheckedIn,
+            "ERR_NOT_FOUND_STAY"
+        );
+        let room = &self.rooms_by_id[&booking.room_id];
+        let caller = env::signer_account_id();
+        assert!(
+            caller == booking.guest_id || caller == room.owner_id,
+            "ERR_NOT_ALLOWED"
+        );
+
+        // 超過滞在している場合は、保証金から違約金を差し引く
+        let penalty = room
+            .overstay(&booking)
+            .map_or(0, |overstay| overstay.penalty.0);
+        let (payment, security_deposit) = self.internal_finish_stay(booking_id);
+        let penalty = penalty.min(security_deposit);
+        self.internal_return_security_deposit(booking_id, security_deposit - penalty);
+        self.internal_pay_owner(booking_id, payment + penalty);
+    }
+}
+
+impl Contract {
+    // 連続した宿泊を1つの予約として保存する
+    // 作成した予約IDと、予約時に支払うべき掲出料・追加料金・保証金の合計を返す
+    // 分割払いの部屋では、前払いの額のみを返す
+    // 支払われたNEARやトークンとの照合はコール元で行う
+    pub(crate) fn internal_create_booking(
+        &mut self,
+        guest_id: &AccountId,
+        payment: PaymentMethod,
+        room_id: &RoomId,
+        check_in_date: CheckInDate,
+        check_out_date: CheckInDate,
+        quantity: u16,
+        guests: Option<u16>,
+        note: Option<String>,
+        promo_code: Option<String>,
+        loyalty_points: Option<U128>,
+        rate_plan: Option<RatePlan>,
+    ) -> (BookingId, Balance) {
+        let note = note.unwrap_or_default();
+        assert!(note.chars().count() <= MAX_NOTE_LENGTH, "ERR_NOTE_TOO_LONG");
+
+        let initial_size = self
+            .rooms_by_id
+            .get(room_id)
+            .expect("ERR_NOT_FOUND_ROOM")
+            .serialized_size();
+        let room = self
+            .rooms_by_id
+            .get_mut(room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        assert!(room.listed, "ERR_ROOM_NOT_LISTED");
+        // 分割払いと承認後の支払いは、NEARで支払う予約のみ受け付ける
+        let pays_in_installments = matches!(payment, PaymentMethod::Near);
+        // 料金設定コントラクトが指定された部屋は、その掲出料でのみ予約できる
+        assert_eq!(
+            room.pricing_contract_id.is_some(),
+            matches!(payment, PaymentMethod::ExternalPrice),
+            "ERR_EXTERNALLY_PRICED_ROOM"
+        );
+        let winning_bid = match payment {
+            PaymentMethod::Auction(amount) => Some(amount),
+            _ => None,
+        };
+        let payment_token = match payment {
+            PaymentMethod::Near => {
+                assert!(!room.usd_pricing, "ERR_USD_PRICED_ROOM");
+                None
+            }
+            PaymentMethod::Token(token_id) => Some(token_id),
+            PaymentMethod::OracleNear => {
+                assert!(room.usd_pricing, "ERR_NOT_USD_PRICED_ROOM");
+                None
+            }
+            PaymentMethod::ExternalPrice | PaymentMethod::Auction(_) => None,
+        };
+        assert_eq!(room.payment_token, payment_token, "ERR_WRONG_PAYMENT_TOKEN");
+        let nights = date::nights(&check_in_date, &check_out_date);
+        room.assert_stay_length(nights.len());
+
+        // 即時予約が無効な部屋では、オーナーの承認待ちとして保存する
+        let status = if room.instant_book {
+            BookingStatus::Confirmed
+        } else {
+            BookingStatus::Pending
+        };
+        let rate_plan = rate_plan.unwrap_or(RatePlan::Standard);
+        let amount = winning_bid
+            .unwrap_or_else(|| room.price_for_guest(guest_id, &nights, quantity, &rate_plan));
+        let discount = promo_code.map_or(0, |code| room.redeem_promo_code(&code, guest_id, amount));
+        let amount = amount - discount;
+        // ロイヤルティポイントはNEAR建ての掲出料の割引にのみ使える
+        let points_discount = loyalty_points.map_or(0, |points| {
+            assert!(
+                payment_token.is_none() && !room.usd_pricing,
+                "ERR_LOYALTY_POINTS_REQUIRE_NEAR"
+            );
+            self.loyalty.redeem(guest_id, points.0, amount)
+        });
+        let discount = discount + points_discount;
+        let amount = amount - points_discount;
+        // 人数が指定されない場合は、予約する部屋（ベッド）1つにつき1人として扱う
+        let guests = guests.unwrap_or(quantity);
+        assert!(guests > 0, "ERR_INVALID_GUESTS");
+        room.assert_within_max_guests(quantity, guests);
+        let (cleaning_fee, extra_guest_fee) = room.extra_fees(nights.len(), quantity, guests);
+        let fees = cleaning_fee + extra_guest_fee;
+        let security_deposit = room.security_deposit.0;
+        let tax_region = room.location.city.clone();
+        let room_capture_on_check_in = room.capture_on_check_in;
+        let total = amount + fees + security_deposit;
+        let (balance_due, balance_due_at) = if pays_in_installments {
+            room.deferred_payment(total, &check_in_date)
+                .unwrap_or_else(|| room.installment_plan(total, &check_in_date))
+        } else {
+            (0, 0)
+        };
+
+        let booking_id = self.next_booking_id;
+        self.next_booking_id += 1;
+        room.reserve_nights(&nights, guest_id, booking_id, quantity);
+
+        let now = env::block_timestamp();
+        let booking = Booking {
+            booking_id,
+            room_id: room_id.clone(),
+            guest_id: guest_id.clone(),
+            check_in_date,
+            check_out_date,
+            quantity,
+            guests,
+            payment_token,
+            amount: U128(amount),
+            discount: U128(discount),
+            fees: U128(fees),
+            cleaning_fee: U128(cleaning_fee),
+            extra_guest_fee: U128(extra_guest_fee),
+            reschedule_fees: U128(0),
+            security_deposit: U128(security_deposit),
+            tips: U128(0),
+            balance_due: U128(balance_due),
+            balance_due_at,
+            stream_started_at: 0,
+            streamed: U128(0),
+            captured: U128(0),
+            status,
+            created_at: now,
+            updated_at: now,
+            time_requests: vec![],
+            note,
+            deposit_release_at: 0,
+            damage_claim: None,
+            platform_fee_bps: self.platform_fee_bps,
+            tax_bps: self.tax_bps_for(&tax_region),
+            tax_region,
+            affiliate_id: None,
+            affiliate_commission_bps: 0,
+            insurance_claim: None,
+            rate_plan,
+            capture_on_check_in: room_capture_on_check_in,
+            guest_public_key: None,
+            access_code: None,
+            encrypted_instructions: None,
+        };
+        self.bookings_by_id.insert(&booking_id, &booking);
+        if balance_due > 0 {
+            self.unpaid_bookings.push(booking_id);
+        }
+        self.internal_hold_escrow(booking_id, total - balance_due);
+        push_booking_id(&mut self.bookings_per_guest, guest_id, booking_id);
+        push_booking_id(&mut self.bookings_per_room, room_id, booking_id);
+        self.internal_track_room_storage(room_id, initial_size);
+
+        (booking_id, total - balance_due)
+    }
+
+    // 予約をキャンセルし、キャンセルポリシーに基づいて掲出料を予約者とオーナーに分配する
+    // 分割払いの残額が未払いの場合は、その分を差し引いて分配する
+    pub(crate) fn internal_cancel_booking(&mut self, booking_id: BookingId) -> Promise {
+        let mut booking = self.internal_booking(booking_id);
+        let room = &self.rooms_by_id[&booking.room_id];
+        let price_refund = room.cancellation_refund(&booking, booking.amount.0);
+        let (payment, refund) = booking.deduct_balance_due(
+            booking.amount.0 - price_refund,
+            price_refund + booking.fees.0 + booking.security_deposit.0,
+        );
+
+        self.internal_close_booking(&mut booking, BookingStatus::Cancelled);
+        if payment > 0 {
+            self.internal_pay_owner(booking_id, payment);
+        }
+        self.internal_release_escrow(booking_id, booking.guest_id, refund)
+    }
+
+    // 予約IDから予約のデータを取得する
+    pub(crate) fn internal_booking(&self, booking_id: BookingId) -> Booking {
+        self.bookings_by_id
+            .get(&booking_id)
+            .expect("ERR_NOT_FOUND_BOOKING")
+    }
+
+    // 予約をキャンセル済み・宿泊終了にし、宿泊日を解放する
+    // キャンセル・ノーショーの場合は、解放された今日以降の宿泊日をキャンセル待ちの先頭のアカウントに仮押さえする
+    pub(crate) fn internal_close_booking(&mut self, booking: &mut Booking, status: BookingStatus) {
+        let room = self
+            .rooms_by_id
+            .get_mut(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        let initial_size = room.serialized_size();
+        let nights = date::nights(&booking.check_in_date, &booking.check_out_date);
+        room.release_nights(&nights, booking.booking_id, booking.quantity);
+        if status != BookingStatus::CheckedOut {
+            let today = room.today();
+            for night in nights.iter().filter(|night| **night >= today) {
+                room.offer_to_waitlist(night);
+            }
+        }
+        self.internal_track_room_storage(&booking.room_id, initial_size);
+
+        if booking.balance_due.0 > 0 {
+            self.unpaid_bookings.retain(|id| *id != booking.booking_id);
+        }
+        booking.status = status;
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking.booking_id, booking);
+    }
+
+    // 宿泊中の予約を宿泊終了にする
+    // オーナーに支払う掲出料・追加料金（ストリーミング払いやチェックイン時に支払い済みの分を除く）と、予約者に返す保証金を返す
+    pub(crate) fn internal_finish_stay(&mut self, booking_id: BookingId) -> (Balance, Balance) {
+        let mut booking = self.internal_booking(booking_id);
+        self.internal_close_booking(&mut booking, BookingStatus::CheckedOut);
+        if booking.payment_token.is_none() && !self.rooms_by_id[&booking.room_id].usd_pricing {
+            self.loyalty
+                .earn(&booking.guest_id, booking_id, booking.amount.0);
+            self.internal_pay_cashback(&booking);
+        }
+        self.internal_reward_referral(&booking.guest_id);
+
+        let room = self.rooms_by_id.get_mut(&booking.room_id).unwrap();
+        let initial_size = room.serialized_size();
+        room.stays.retain(|stay| *stay != booking_id);
+        self.internal_track_room_storage(&booking.room_id, initial_size);
+        (
+            booking.amount.0 + booking.fees.0 - booking.paid_to_owner(),
+            booking.security_deposit.0,
+        )
+    }
+}
+
+impl Booking {
+    // 予約リクエストの承認期間が過ぎているかを確認する
+    fn is_approval_expired(&self, approval_window: u64) -> bool {
+        env::block_timestamp() > self.created_at + approval_window
+    }
+}
+
+impl Room {
+    // 1つの宿泊日に受け付けられる予約の数を返す
+    // ベッド単位で予約を受け付ける部屋では全ての部屋のベッドの合計、それ以外は部屋の数になる
+    pub(crate) fn capacity(&self) -> u16 {
+        if self.bookable_by_bed {
+            self.units.saturating_mul(self.beds as u16)
+        } else {
+            self.units
+        }
+    }
+
+    // 指定された日付の空きの数を返す
+    // 有効な仮押さえの数だけ空きから除く。`guest_id`が指定された場合、そのアカウントの仮押さえは除かない
+    pub(crate) fn vacancies(&self, date: &CheckInDate, guest_id: Option<&AccountId>) -> u16 {
+        let held = self.active_holds(date, guest_id);
+        self.capacity().saturating_sub(self.booked(date) + held)
+    }
+
+    // 指定された日付が予約可能かを確認する
+    // 掲載していない部屋は、どの日付も予約できない
+    pub(crate) fn is_available(&self, date: &CheckInDate) -> bool {
+        self.listed
+            && !self.is_blocked(date)
+            && !self.is_in_auction(date)
+            && self.vacancies(date, None) > 0
+    }
+
+    // 指定された日付に有効な予約があるかを確認する
+    pub(crate) fn is_booked(&self, date: &CheckInDate) -> bool {
+        self.booked(date) > 0
+    }
+
+    // 指定された日付が満室かを確認する
+    pub(crate) fn is_fully_booked(&self, date: &CheckInDate) -> bool {
+        self.booked(date) >= self.capacity()
+    }
+
+    // 指定された日付の予約済みの数を返す
+    fn booked(&self, date: &CheckInDate) -> u16 {
+        self.booked_count.get(date).copied().unwrap_or(0)
+    }
+
+    // 空き状況の確認と宿泊日の確保を一度に行う
+    // 1泊でも予約できない日があればパニックし、トランザクション全体が取り消される
+    // （添付されたNEARも自動的に返金される）
+    // 宿泊日を確保する処理は必ずこのメソッドを経由すること
+    pub(crate) fn reserve_nights(
+        &mut self,
+        nights: &[CheckInDate],
+        guest_id: &AccountId,
+        booking_id: BookingId,
+        quantity: u16,
+    ) {
+        // 全ての宿泊日を確認してから保存することで、一部の日だけ予約されることを防ぐ
+        let today = self.today();
+        for night in nights.iter() {
+            night.assert_not_past(&today);
+            self.assert_within_booking_window(night);
+            self.assert_before_same_day_cutoff(night);
+            assert!(
+                self.booked(night) + quantity <= self.capacity(),
+                "ERR_ALREADY_BOOKED"
+            );
+            assert!(!self.is_blocked(night), "ERR_DATE_BLOCKED");
+            assert!(!self.is_in_auction(night), "ERR_DATE_IN_AUCTION");
+            assert!(
+                self.vacancies(night, Some(guest_id)) >= quantity,
+                "ERR_ROOM_ON_HOLD"
+            );
+        }
+
+        // 宿泊日を確保し、不要になった自分の仮押さえを削除する
+        for night in nights.iter() {
+            self.release_hold(night, guest_id);
+            self.booked_info.entry(*night).or_default().push(booking_id);
+            *self.booked_count.entry(*night).or_default() += quantity;
+        }
+    }
+
+    // `reserve_nights`で宿泊日を確保できるかを返す
+    pub(crate) fn can_reserve_nights(
+        &self,
+        nights: &[CheckInDate],
+        guest_id: &AccountId,
+        quantity: u16,
+    ) -> bool {
+        let today = self.today();
+        nights.iter().all(|night| {
+            night >= &today
+                && self.is_within_booking_window(night)
+                && self.is_before_same_day_cutoff(night)
+                && self.booked(night) + quantity <= self.capacity()
+                && !self.is_blocked(night)
+                && !self.is_in_auction(night)
+                && self.vacancies(night, Some(guest_id)) >= quantity
+        })
+    }
+
+    // 確保していた宿泊日を解放する
+    pub(crate) fn release_nights(
+        &mut self,
+        nights: &[CheckInDate],
+        booking_id: BookingId,
+        quantity: u16,
+    ) {
+        for night in nights.iter() {
+            if let Some(booking_ids) = self.booked_info.get_mut(night) {
+                booking_ids.retain(|id| *id != booking_id);
+                if booking_ids.is_empty() {
+                    self.booked_info.remove(night);
+                }
+            }
+            let booked = self.booked(night).saturating_sub(quantity);
+            if booked == 0 {
+                self.booked_count.remove(night);
+            } else {
+                self.booked_count.insert(*night, booked);
+            }
+        }
+    }
+}
+
+// アカウントや部屋ごとの予約IDの一覧に、新しい予約IDを追加する
+pub(crate) fn push_booking_id<K: BorshSerialize + BorshDeserialize>(
+    index: &mut LookupMap<K, Vec<BookingId>>,
+    key: &K,
+    booking_id: BookingId,
+) {
+    let mut booking_ids = index.get(key).unwrap_or_default();
+    booking_ids.push(booking_id);
+    index.insert(key, &booking_ids);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn keeps_late_reschedule_fee_after_cancel() {
+        let (mut contract, room_id) = setup();
+        let fee = PRICE / 10;
+        contract.set_reschedule_policy(room_id.clone(), 30 * 24, U128(fee));
+        let booking_id = book(&mut contract, &room_id, today().add_days(10), 1, PRICE);
+
+        set_caller(&guest_id(), fee);
+        contract.change_booking(booking_id, today().add_days(11));
+        assert_eq!(earnings_of(&contract, &owner_id()), fee);
+        assert_eq!(contract.get_escrow(booking_id).0, PRICE);
+
+        // 掲出料は全額返金されるが、支払い済みの変更手数料は返金されない
+        contract.cancel_booking(booking_id);
+        assert_eq!(earnings_of(&contract, &owner_id()), fee);
+        assert_eq!(contract.get_escrow(booking_id).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_USD_PRICED_ROOM")]
+    fn rejects_change_of_usd_priced_booking() {
+        let (mut contract, room_id) = setup();
+        let booking_id = book(&mut contract, &room_id, today().add_days(10), 1, PRICE);
+        contract.rooms_by_id.get_mut(&room_id).unwrap().usd_pricing = true;
+
+        set_caller(&guest_id(), 0);
+        contract.change_booking(booking_id, today().add_days(11));
+    }
+
+    #[test]
+    fn keeps_discount_when_changing_booking() {
+        let (mut contract, room_id) = setup();
+        let booking_id = book(&mut contract, &room_id, today().add_days(10), 2, 2 * PRICE);
+        // プロモーションコードで半額になった予約とする
+        let mut booking = contract.internal_booking(booking_id);
+        booking.amount = U128(PRICE);
+        booking.discount = U128(PRICE);
+        contract.bookings_by_id.insert(&booking_id, &booking);
+
+        set_caller(&guest_id(), 0);
+        contract.change_booking(booking_id, today().add_days(11));
+        let booking = contract.internal_booking(booking_id);
+        assert_eq!(booking.amount.0, PRICE);
+        assert_eq!(booking.discount.0, PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ALREADY_BOOKED")]
+    fn rejects_second_booking_of_same_night() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = today().add_days(10);
+        book(&mut contract, &room_id, check_in_date, 1, PRICE);
+        book_as(
+            &mut contract,
+            &other_guest_id(),
+            &room_id,
+            check_in_date,
+            1,
+            PRICE,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ALREADY_BOOKED")]
+    fn rejects_booking_overlapping_last_night() {
+        let (mut contract, room_id) = setup();
+        book(&mut contract, &room_id, today().add_days(10), 3, 3 * PRICE);
+        book_as(
+            &mut contract,
+            &other_guest_id(),
+            &room_id,
+            today().add_days(12),
+            2,
+            2 * PRICE,
+        );
+    }
+
+    #[test]
+    fn books_stay_starting_on_previous_check_out_date() {
+        let (mut contract, room_id) = setup();
+        book(&mut contract, &room_id, today().add_days(10), 2, 2 * PRICE);
+        let booking_id = book_as(
+            &mut contract,
+            &other_guest_id(),
+            &room_id,
+            today().add_days(12),
+            2,
+            2 * PRICE,
+        );
+        let booking = contract.internal_booking(booking_id);
+        assert_eq!(booking.guest_id, other_guest_id());
+        assert_eq!(booking.status, BookingStatus::Confirmed);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ALREADY_BOOKED")]
+    fn books_multi_unit_room_up_to_capacity() {
+        let (mut contract, room_id) = setup();
+        contract.rooms_by_id.get_mut(&room_id).unwrap().units = 2;
+        let check_in_date = today().add_days(10);
+        book(&mut contract, &room_id, check_in_date, 1, PRICE);
+        book_as(
+            &mut contract,
+            &other_guest_id(),
+            &room_id,
+            check_in_date,
+            1,
+            PRICE,
+        );
+        assert_eq!(contract.rooms_by_id[&room_id].booked(&check_in_date), 2);
+
+        book_as(
+            &mut contract,
+            &"third.near".parse().unwrap(),
+            &room_id,
+            check_in_date,
+            1,
+            PRICE,
+        );
+    }
+
+    #[test]
+    fn rebooks_night_released_by_cancellation() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = today().add_days(10);
+        let booking_id = book(&mut contract, &room_id, check_in_date, 1, PRICE);
+        contract.cancel_booking(booking_id);
+
+        book_as(
+            &mut contract,
+            &other_guest_id(),
+            &room_id,
+            check_in_date,
+            1,
+            PRICE,
+        );
+        assert_eq!(contract.rooms_by_id[&room_id].booked(&check_in_date), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DEPOSIT_IS_INCORRECT")]
+    fn rejects_booking_without_enough_deposit() {
+        let (mut contract, room_id) = setup();
+        book(&mut contract, &room_id, today().add_days(10), 2, PRICE);
+    }
+}
@@ -0,0 +1,155 @@
+// キャンセルポリシーに基づいて返金額を計算する
+
+use crate::date::NANOS_PER_HOUR;
+use crate::*;
+
+// チェックインの何時間前までにキャンセルすると、掲出料の何%が返金されるか
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RefundTier {
+    hours_before_check_in: u32, // チェックインの何時間前まで
+    refund_percent: u8,         // 返金される掲出料の割合（%）
+}
+
+// カスタムポリシーに設定できる段階の最大数
+const MAX_REFUND_TIERS: usize = 10;
+
+// 部屋ごとのキャンセルポリシー
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum CancellationPolicy {
+    Flexible,                          // 24時間前までは全額返金
+    Moderate,                          // 7日前までは全額、48時間前までは半額を返金
+    Strict,                            // 14日前までは半額を返金
+    Custom { tiers: Vec<RefundTier> }, // オーナーが設定した段階で返金
+}
+
+impl CancellationPolicy {
+    // ポリシーに対応する返金の段階を返す
+    fn tiers(&self) -> Vec<RefundTier> {
+        match self {
+            CancellationPolicy::Flexible => vec![RefundTier::new(24, 100)],
+            CancellationPolicy::Moderate => {
+                vec![RefundTier::new(7 * 24, 100), RefundTier::new(48, 50)]
+            }
+            CancellationPolicy::Strict => vec![RefundTier::new(14 * 24, 50)],
+            CancellationPolicy::Custom { tiers } => tiers.clone(),
+        }
+    }
+}
+
+impl RefundTier {
+    fn new(hours_before_check_in: u32, refund_percent: u8) -> Self {
+        Self {
+            hours_before_check_in,
+            refund_percent,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // キャンセルポリシーを変更する
+    // 変更後のポリシーは、既存の予約のキャンセルにも適用される
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn set_cancellation_policy(
+        &mut self,
+        room_id: RoomId,
+        cancellation_policy: CancellationPolicy,
+    ) {
+        assert_valid_cancellation_policy(&cancellation_policy);
+
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.cancellation_policy = cancellation_policy;
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+}
+
+// チェックインまでの時間に応じて、返金される掲出料の割合を返す
+// どの段階にも当てはまらない場合は返金しない
+fn refund_percent(tiers: &[RefundTier], hours_before_check_in: i64) -> u8 {
+    tiers
+        .iter()
+        .filter(|tier| hours_before_check_in >= tier.hours_before_check_in as i64)
+        .map(|tier| tier.refund_percent)
+        .max()
+        .unwrap_or(0)
+}
+
+impl Room {
+    // 現時点でキャンセルした場合に、掲出料のうち予約者に返金される額を返す
+    // 残りはオーナーに支払われる。返金不可の料金プランの予約は返金しない
+    pub(crate) fn cancellation_refund(&self, booking: &Booking, amount: Balance) -> Balance {
+        if booking.rate_plan == RatePlan::NonRefundable {
+            return 0;
+        }
+        let now = env::block_timestamp() as i64;
+        let hours_before_check_in = (self.local_start_timestamp(&booking.check_in_date) - now)
+            .div_euclid(NANOS_PER_HOUR as i64);
+        let percent = refund_percent(&self.cancellation_policy.tiers(), hours_before_check_in);
+        amount * percent as Balance / 100
+    }
+}
+
+// カスタムポリシーの段階の数と返金の割合が正しいかを確認する
+pub(crate) fn assert_valid_cancellation_policy(cancellation_policy: &CancellationPolicy) {
+    if let CancellationPolicy::Custom { tiers } = cancellation_policy {
+        assert!(tiers.len() <= MAX_REFUND_TIERS, "ERR_TOO_MANY_REFUND_TIERS");
+        assert!(
+            tiers.iter().all(|tier| tier.refund_percent <= 100),
+            "ERR_INVALID_REFUND_PERCENT"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::ONE_NEAR;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn refunds_half_between_tiers_of_moderate_policy() {
+        let (mut contract, room_id) = setup();
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.set_cancellation_policy(room_id.clone(), CancellationPolicy::Moderate);
+
+        // チェックインの3日前にキャンセルする
+        let booking_id = book(&mut contract, &room_id, today().add_days(3), 1, PRICE);
+        contract.cancel_booking(booking_id);
+        assert_eq!(transferred_to(&guest_id()), PRICE / 2);
+        assert_eq!(earnings_of(&contract, &owner_id()), PRICE / 2);
+    }
+
+    #[test]
+    fn pays_owner_for_late_cancellation_of_flexible_booking() {
+        let (mut contract, room_id) = setup();
+
+        // チェックインの12時間前にキャンセルする
+        let booking_id = book(&mut contract, &room_id, today().add_days(1), 1, PRICE);
+        contract.cancel_booking(booking_id);
+        assert_eq!(transferred_to(&guest_id()), 0);
+        assert_eq!(earnings_of(&contract, &owner_id()), PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_REFUND_PERCENT")]
+    fn rejects_custom_policy_refunding_over_full_amount() {
+        let (mut contract, room_id) = setup();
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.set_cancellation_policy(
+            room_id,
+            CancellationPolicy::Custom {
+                tiers: vec![RefundTier::new(24, 101)],
+            },
+        );
+    }
+}
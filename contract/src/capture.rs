@@ -0,0 +1,92 @@
+// チェックイン時の支払いの確定
+// 予約時に預かった掲出料と追加料金を、チェックアウト時ではなくチェックインした時点でオーナーの売上に記録する
+// チェックイン前にオーナーがキャンセルした場合は、預かり金の全額がすぐに予約者に返金される
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // 預かった掲出料と追加料金を、チェックインした時点でオーナーの売上に記録するかを変更する
+    // 変更後の設定は、これから作成される予約にのみ適用される
+    // 部屋のオーナーのみがコールできる
+    pub fn set_capture_on_check_in(&mut self, room_id: RoomId, capture_on_check_in: bool) {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.capture_on_check_in = capture_on_check_in;
+    }
+}
+
+impl Contract {
+    // チェックインした予約の掲出料と追加料金を、オーナーの売上に記録する
+    // ストリーミング払いの予約は、経過した時間に応じて支払うため確定しない
+    pub(crate) fn internal_capture_on_check_in(&mut self, booking_id: BookingId) {
+        let mut booking = self.internal_booking(booking_id);
+        if !booking.capture_on_check_in {
+            return;
+        }
+        if booking.stream_started_at > 0 {
+            booking.capture_on_check_in = false;
+            self.bookings_by_id.insert(&booking_id, &booking);
+            return;
+        }
+        let payment = booking.amount.0 + booking.fees.0;
+        booking.captured = U128(payment);
+        self.bookings_by_id.insert(&booking_id, &booking);
+        self.internal_pay_owner(booking_id, payment);
+    }
+
+    // チェックイン時に支払いを確定した予約に、チェックイン後に追加された料金をすぐにオーナーの売上に記録する
+    // それ以外の予約の追加料金は、チェックアウト時に掲出料と合わせて記録する
+    pub(crate) fn internal_capture_added_fee(&mut self, booking_id: BookingId, fee: Balance) {
+        let mut booking = self.internal_booking(booking_id);
+        if !booking.capture_on_check_in || booking.status != BookingStatus::CheckedIn {
+            return;
+        }
+        booking.captured = U128(booking.captured.0 + fee);
+        self.bookings_by_id.insert(&booking_id, &booking);
+        self.internal_pay_owner(booking_id, fee);
+    }
+}
+
+impl Booking {
+    // チェックアウトまでに既にオーナーに支払った掲出料と追加料金を返す
+    pub(crate) fn paid_to_owner(&self) -> Balance {
+        self.captured.0 + self.streamed.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::time_request::TimeRequestKind;
+
+    #[test]
+    fn pays_late_check_out_fee_added_after_capture() {
+        let (mut contract, room_id) = setup();
+        contract.set_capture_on_check_in(room_id.clone(), true);
+        let booking_id = book(&mut contract, &room_id, today(), 1, PRICE);
+        contract.check_in(booking_id);
+        assert_eq!(earnings_of(&contract, &owner_id()), PRICE);
+
+        let fee = PRICE / 2;
+        contract.request_time_change(booking_id, TimeRequestKind::LateCheckOut, 15);
+        set_caller(&owner_id(), 0);
+        contract.approve_time_request(booking_id, TimeRequestKind::LateCheckOut, U128(fee));
+        set_caller(&guest_id(), fee);
+        contract.pay_time_request(booking_id, TimeRequestKind::LateCheckOut);
+        assert_eq!(earnings_of(&contract, &owner_id()), PRICE + fee);
+
+        contract.check_out(booking_id);
+        assert_eq!(earnings_of(&contract, &owner_id()), PRICE + fee);
+        assert_eq!(contract.get_escrow(booking_id).0, 0);
+
+        // 預かり金が残っていないため、部屋を削除できる
+        set_caller(&owner_id(), 0);
+        contract.delete_room(room_id);
+    }
+}
@@ -0,0 +1,97 @@
+// キャッシュバックキャンペーン
+// キャンペーンの期間中に宿泊を終えた予約者に、NEARで支払った掲出料の一定割合をクレジットとして還元する
+
+use near_sdk::serde_json::json;
+
+use crate::event::emit_event;
+use crate::*;
+
+// キャッシュバックキャンペーンの設定と予算
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CashbackCampaign {
+    percent: u8,    // 掲出料の何%を還元するか
+    budget: U128,   // 還元できる残りの予算（NEAR）
+    starts_at: u64, // キャンペーンの開始日時
+    ends_at: u64,   // キャンペーンの終了日時
+}
+
+#[near_bindgen]
+impl Contract {
+    // キャッシュバックキャンペーンを開始する
+    // 添付したNEARがキャンペーンの予算になり、予算を使い切るとそれ以降は還元されない
+    // 管理者のみがコールできる
+    #[payable]
+    pub fn start_cashback_campaign(&mut self, percent: u8, starts_at: u64, ends_at: u64) {
+        self.assert_admin();
+        assert!(
+            percent > 0 && percent <= 100,
+            "ERR_INVALID_CASHBACK_PERCENT"
+        );
+        assert!(
+            starts_at < ends_at && ends_at > env::block_timestamp(),
+            "ERR_INVALID_CAMPAIGN_PERIOD"
+        );
+        assert!(
+            self.cashback_campaign.is_none(),
+            "ERR_CAMPAIGN_ALREADY_RUNNING"
+        );
+        let budget = env::attached_deposit();
+        assert!(budget > 0, "ERR_NO_CAMPAIGN_BUDGET");
+
+        self.cashback_campaign = Some(CashbackCampaign {
+            percent,
+            budget: U128(budget),
+            starts_at,
+            ends_at,
+        });
+    }
+
+    // キャッシュバックキャンペーンを終了し、残りの予算を管理者に返金する
+    // 管理者のみがコールできる
+    pub fn end_cashback_campaign(&mut self) -> U128 {
+        self.assert_admin();
+        let campaign = self
+            .cashback_campaign
+            .take()
+            .expect("ERR_NO_CASHBACK_CAMPAIGN");
+
+        if campaign.budget.0 > 0 {
+            Promise::new(self.admin_id.clone()).transfer(campaign.budget.0);
+        }
+        campaign.budget
+    }
+
+    // 現在のキャッシュバックキャンペーンを取得する
+    pub fn get_cashback_campaign(&self) -> Option<CashbackCampaign> {
+        self.cashback_campaign.clone()
+    }
+}
+
+impl Contract {
+    // 宿泊を終えた予約者に、キャンペーンの予算の範囲内で掲出料の一定割合をクレジットとして還元する
+    pub(crate) fn internal_pay_cashback(&mut self, booking: &Booking) {
+        let now = env::block_timestamp();
+        let campaign = match self.cashback_campaign.as_mut() {
+            Some(campaign) if campaign.starts_at <= now && now < campaign.ends_at => campaign,
+            _ => return,
+        };
+        let cashback =
+            (booking.amount.0 * campaign.percent as Balance / 100).min(campaign.budget.0);
+        if cashback == 0 {
+            return;
+        }
+        campaign.budget = U128(campaign.budget.0 - cashback);
+
+        let credits = self.internal_add_credits(&booking.guest_id, cashback);
+        emit_event(
+            "cashback",
+            json!({
+                "account_id": booking.guest_id,
+                "booking_id": booking.booking_id,
+                "amount": U128(cashback),
+                "credits": U128(credits),
+            }),
+        );
+    }
+}
@@ -0,0 +1,52 @@
+// 確定済みの予約者だけが読めるチェックインの案内
+// コントラクトのストレージやトランザクションの引数・結果は誰でも読めるため、入口の暗証番号などの平文をオンチェーンに保存してはならない
+// オーナーは予約者の公開鍵で暗号化した案内を予約ごとに記録し、予約者だけが自分の秘密鍵で復号できるようにする
+
+use crate::*;
+
+// 暗号化したチェックインの案内の最大の長さ
+const MAX_INSTRUCTIONS_LENGTH: usize = 4096;
+
+#[near_bindgen]
+impl Contract {
+    // 予約者の公開鍵で暗号化したチェックインの案内を予約に記録する
+    // 確定済み・宿泊中の予約で、予約者が公開鍵を登録している場合のみ記録できる
+    // 部屋のオーナーのみがコールできる
+    pub fn post_check_in_instructions(
+        &mut self,
+        booking_id: BookingId,
+        encrypted_instructions: String,
+    ) {
+        let mut booking = self.internal_booking(booking_id);
+        self.rooms_by_id
+            .get(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM")
+            .assert_owner();
+        assert!(
+            matches!(
+                booking.status,
+                BookingStatus::Confirmed | BookingStatus::CheckedIn
+            ),
+            "ERR_BOOKING_NOT_CONFIRMED"
+        );
+        assert!(
+            booking.guest_public_key.is_some(),
+            "ERR_NO_GUEST_PUBLIC_KEY"
+        );
+        assert!(
+            !encrypted_instructions.is_empty()
+                && encrypted_instructions.len() <= MAX_INSTRUCTIONS_LENGTH,
+            "ERR_INVALID_INSTRUCTIONS"
+        );
+
+        booking.encrypted_instructions = Some(encrypted_instructions);
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+    }
+
+    // 予約者の公開鍵で暗号化されたチェックインの案内を取得する
+    // 復号は予約者の秘密鍵でのみ行えるため、誰が取得してもよい
+    pub fn get_check_in_instructions(&self, booking_id: BookingId) -> Option<String> {
+        self.internal_booking(booking_id).encrypted_instructions
+    }
+}
@@ -0,0 +1,84 @@
+// 予約に使えるクレジット（ギフトカード・前払い）
+// 購入したクレジットは予約の支払いに充てられ、添付したNEARが不足する分をクレジットから支払う
+
+use near_sdk::json_types::Base58CryptoHash;
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // 添付したNEARと同額のクレジットを購入し、指定したアカウントに付与する
+    // `receiver_id`が指定されない場合は、コールしたアカウントに付与する
+    #[payable]
+    pub fn buy_credits(&mut self, receiver_id: Option<AccountId>) -> U128 {
+        let amount = env::attached_deposit();
+        assert!(amount > 0, "ERR_NO_DEPOSIT_ATTACHED");
+        let receiver_id = receiver_id.unwrap_or_else(env::signer_account_id);
+        U128(self.internal_add_credits(&receiver_id, amount))
+    }
+
+    // 添付したNEARと同額のギフトコードを発行する
+    // `code_hash`はギフトコードのSHA-256ハッシュで、コードを知っているアカウントが引き換えられる
+    #[payable]
+    pub fn buy_gift_code(&mut self, code_hash: Base58CryptoHash) {
+        let amount = env::attached_deposit();
+        assert!(amount > 0, "ERR_NO_DEPOSIT_ATTACHED");
+        let code_hash: CryptoHash = code_hash.into();
+        assert!(
+            self.gift_codes.insert(&code_hash, &amount).is_none(),
+            "ERR_GIFT_CODE_EXISTS"
+        );
+    }
+
+    // ギフトコードを引き換えて、コールしたアカウントにクレジットを付与する
+    pub fn redeem_gift_code(&mut self, code: String) -> U128 {
+        let code_hash: CryptoHash = env::sha256(code.as_bytes()).try_into().unwrap();
+        let amount = self
+            .gift_codes
+            .remove(&code_hash)
+            .expect("ERR_INVALID_GIFT_CODE");
+        U128(self.internal_add_credits(&env::signer_account_id(), amount))
+    }
+
+    // ギフトコードが引き換えられる額を取得する
+    pub fn get_gift_code(&self, code_hash: Base58CryptoHash) -> Option<U128> {
+        let code_hash: CryptoHash = code_hash.into();
+        self.gift_codes.get(&code_hash).map(U128)
+    }
+
+    // アカウントが持っているクレジットの残高を取得する
+    pub fn get_credits(&self, account_id: AccountId) -> U128 {
+        U128(self.credits.get(&account_id).unwrap_or(0))
+    }
+}
+
+impl Contract {
+    // 添付されたNEARとクレジットで、NEARで支払う予約の合計額を支払う
+    // 添付されたNEARが不足する分はクレジットから差し引き、クレジットでも足りない場合はパニックする
+    pub(crate) fn internal_collect_deposit(&mut self, guest_id: &AccountId, total: Balance) {
+        let deposit = env::attached_deposit();
+        assert!(deposit <= total, "ERR_DEPOSIT_IS_INCORRECT");
+        let shortfall = total - deposit;
+        if shortfall == 0 {
+            return;
+        }
+        let credits = self.credits.get(guest_id).unwrap_or(0);
+        assert!(credits >= shortfall, "ERR_DEPOSIT_IS_INCORRECT");
+        if credits == shortfall {
+            self.credits.remove(guest_id);
+        } else {
+            self.credits.insert(guest_id, &(credits - shortfall));
+        }
+    }
+
+    // クレジットを付与し、付与後の残高を返す
+    pub(crate) fn internal_add_credits(
+        &mut self,
+        account_id: &AccountId,
+        amount: Balance,
+    ) -> Balance {
+        let credits = self.credits.get(account_id).unwrap_or(0) + amount;
+        self.credits.insert(account_id, &credits);
+        credits
+    }
+}
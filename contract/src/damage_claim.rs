@@ -0,0 +1,161 @@
+// チェックアウト後の破損などに対する保証金の請求
+
+use crate::date::NANOS_PER_DAY;
+use crate::*;
+
+// 請求に対して予約者が応答できる期間（3日）
+// 期間内に異議がなければ、オーナーは請求額を受け取れる
+const CLAIM_RESPONSE_DURATION: u64 = 3 * NANOS_PER_DAY;
+
+// 請求の進行状況
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ClaimStatus {
+    Filed,    // 予約者の応答待ち
+    Disputed, // 予約者が異議を申し立て、裁定待ち
+    Settled,  // 精算済み
+}
+
+// オーナーから予約者への、保証金に対する損害の請求
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DamageClaim {
+    amount: U128,         // 請求額
+    evidence_uri: String, // 損害の証拠（画像などのURL）
+    status: ClaimStatus,  // 請求の進行状況
+    filed_at: u64,        // 請求した日時
+    awarded: U128,        // 精算時にオーナーに支払われた額
+}
+
+#[near_bindgen]
+impl Contract {
+    // チェックアウト後、保証金の返金日までに損害を請求する
+    // 請求額は預かっている保証金を上限とする
+    // 部屋のオーナーのみがコールできる
+    pub fn file_damage_claim(&mut self, booking_id: BookingId, amount: U128, evidence_uri: String) {
+        let mut booking = self.internal_booking(booking_id);
+        self.rooms_by_id
+            .get(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM")
+            .assert_owner();
+
+        assert!(
+            booking.deposit_release_at > env::block_timestamp(),
+            "ERR_CLAIM_WINDOW_CLOSED"
+        );
+        assert!(booking.damage_claim.is_none(), "ERR_CLAIM_ALREADY_FILED");
+        assert_valid_media_uri(&evidence_uri);
+        assert!(
+            amount.0 > 0 && amount.0 <= booking.security_deposit.0,
+            "ERR_INVALID_CLAIM_AMOUNT"
+        );
+
+        booking.damage_claim = Some(DamageClaim {
+            amount,
+            evidence_uri,
+            status: ClaimStatus::Filed,
+            filed_at: env::block_timestamp(),
+            awarded: U128(0),
+        });
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+    }
+
+    // 請求を受け入れ、請求額をオーナーの売上に記録し、残りの保証金を予約者に送金する
+    // 予約者がコールできる。応答期間を過ぎた請求はオーナーもコールできる
+    pub fn accept_claim(&mut self, booking_id: BookingId) -> U128 {
+        let booking = self.internal_booking(booking_id);
+        let claim = booking.damage_claim.as_ref().expect("ERR_NOT_FOUND_CLAIM");
+        assert_eq!(claim.status, ClaimStatus::Filed, "ERR_CLAIM_NOT_FILED");
+
+        let caller = env::signer_account_id();
+        let owner_id = self.rooms_by_id[&booking.room_id].owner_id.clone();
+        let is_expired = env::block_timestamp() > claim.filed_at + CLAIM_RESPONSE_DURATION;
+        assert!(
+            caller == booking.guest_id || (caller == owner_id && is_expired),
+            "ERR_NOT_ALLOWED"
+        );
+
+        let amount = claim.amount.0;
+        self.internal_settle_claim(booking_id, amount);
+        U128(amount)
+    }
+
+    // 請求に異議を申し立てる
+    // 精算は管理者による裁定を待つ
+    // 予約者のみが応答期間内にコールできる
+    pub fn dispute_claim(&mut self, booking_id: BookingId) {
+        let mut booking = self.internal_booking(booking_id);
+        assert_eq!(
+            env::signer_account_id(),
+            booking.guest_id,
+            "ERR_NOT_BOOKING_GUEST"
+        );
+        let claim = booking.damage_claim.as_mut().expect("ERR_NOT_FOUND_CLAIM");
+        assert_eq!(claim.status, ClaimStatus::Filed, "ERR_CLAIM_NOT_FILED");
+        assert!(
+            env::block_timestamp() <= claim.filed_at + CLAIM_RESPONSE_DURATION,
+            "ERR_CLAIM_RESPONSE_WINDOW_CLOSED"
+        );
+
+        claim.status = ClaimStatus::Disputed;
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+    }
+
+    // 異議が申し立てられた請求を裁定し、オーナーに支払う額を決定する
+    // 残りの保証金は予約者に返金される
+    // 管理者のみがコールできる
+    pub fn resolve_claim(&mut self, booking_id: BookingId, awarded: U128) {
+        self.assert_admin();
+        let booking = self.internal_booking(booking_id);
+        let claim = booking.damage_claim.as_ref().expect("ERR_NOT_FOUND_CLAIM");
+        assert_eq!(
+            claim.status,
+            ClaimStatus::Disputed,
+            "ERR_CLAIM_NOT_DISPUTED"
+        );
+        assert!(awarded.0 <= claim.amount.0, "ERR_INVALID_CLAIM_AMOUNT");
+
+        self.internal_settle_claim(booking_id, awarded.0);
+    }
+}
+
+impl Contract {
+    // 請求を精算し、指定された額をオーナーの売上に記録し、残りの保証金を予約者に送金する
+    fn internal_settle_claim(&mut self, booking_id: BookingId, awarded: Balance) {
+        let mut booking = self.internal_booking(booking_id);
+        let claim = booking.damage_claim.as_mut().unwrap();
+        claim.status = ClaimStatus::Settled;
+        claim.awarded = U128(awarded);
+        booking.security_deposit = U128(booking.security_deposit.0 - awarded);
+        self.bookings_by_id.insert(&booking_id, &booking);
+
+        // 損害の補償には手数料をかけずに、オーナーの売上として記録する
+        let owner_id = self.rooms_by_id[&booking.room_id].owner_id.clone();
+        self.internal_debit_escrow(booking_id, awarded);
+        self.internal_credit_earnings(&owner_id, &booking.payment_token, awarded);
+        self.internal_release_security_deposit(booking_id);
+    }
+}
+
+impl Booking {
+    // 精算されていない請求があるかを確認する
+    pub(crate) fn has_open_claim(&self) -> bool {
+        matches!(&self.damage_claim, Some(claim) if claim.status != ClaimStatus::Settled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ADMIN")]
+    fn only_admin_resolves_claims() {
+        let (mut contract, room_id) = setup();
+        let booking_id = book(&mut contract, &room_id, today().add_days(10), 1, PRICE);
+        contract.resolve_claim(booking_id, U128(0));
+    }
+}
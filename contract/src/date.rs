@@ -0,0 +1,160 @@
+// 日付を扱うユーティリティ
+// 日付は1970-01-01（UTC）からの経過日数として保存し、
+// JSONでは`YYYY-MM-DD`形式の文字列として受け渡す
+
+use std::fmt;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::env;
+use near_sdk::serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+pub(crate) const NANOS_PER_SECOND: u64 = 1_000_000_000;
+pub(crate) const NANOS_PER_MINUTE: u64 = 60 * NANOS_PER_SECOND;
+pub(crate) const NANOS_PER_HOUR: u64 = 60 * NANOS_PER_MINUTE;
+pub(crate) const NANOS_PER_DAY: u64 = 24 * NANOS_PER_HOUR;
+
+// 1970-01-01からの経過日数で表した日付
+#[derive(
+    BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub struct Date(u64);
+
+impl Date {
+    // `YYYY-MM-DD`形式の文字列を日付に変換する
+    // 形式が異なる文字列や、2月30日のような存在しない日付はエラーになる
+    pub(crate) fn parse(date: &str) -> Result<Date, &'static str> {
+        let bytes = date.as_bytes();
+        let is_iso_format = bytes.len() == 10
+            && bytes.iter().enumerate().all(|(i, byte)| match i {
+                4 | 7 => *byte == b'-',
+                _ => byte.is_ascii_digit(),
+            });
+        if !is_iso_format {
+            return Err("ERR_INVALID_DATE_FORMAT: expected YYYY-MM-DD");
+        }
+
+        let year: i64 = date[0..4].parse().unwrap();
+        let month: i64 = date[5..7].parse().unwrap();
+        let day: i64 = date[8..10].parse().unwrap();
+        if year < 1970 || !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month)
+        {
+            return Err("ERR_INVALID_DATE: date does not exist");
+        }
+
+        Ok(Date(days_from_civil(year, month, day) as u64))
+    }
+
+    // 過去の日付でないかを確認する
+    pub(crate) fn assert_not_past(&self, today: &Date) {
+        assert!(
+            self >= today,
+            "ERR_DATE_IN_PAST: {} is before {}",
+            self,
+            today
+        );
+    }
+
+    // 翌日の日付を返す
+    pub(crate) fn next(&self) -> Date {
+        Date(self.0 + 1)
+    }
+
+    // 指定された日数後の日付を返す
+    pub(crate) fn add_days(&self, days: u64) -> Date {
+        Date(self.0 + days)
+    }
+
+    // 日付の開始時刻（UTCの0時）をブロックのタイムスタンプと同じ単位で返す
+    pub(crate) fn start_timestamp(&self) -> u64 {
+        self.0 * NANOS_PER_DAY
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day) = civil_from_days(self.0 as i64);
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let date = <String as Deserialize>::deserialize(deserializer)?;
+        Date::parse(&date).map_err(de::Error::custom)
+    }
+}
+
+// 指定された年月の日数を返す
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// 年月日を1970-01-01からの経過日数に変換する
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    // 3月始まりの暦に置き換えて計算する
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+// 1970-01-01からの経過日数を年月日に変換する
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = (if days >= 0 { days } else { days - 146096 }) / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// チェックイン日からチェックアウト日の前日までの宿泊日を列挙する
+pub(crate) fn nights(check_in_date: &Date, check_out_date: &Date) -> Vec<Date> {
+    assert!(check_in_date < check_out_date, "ERR_INVALID_DATE_RANGE");
+    (check_in_date.0..check_out_date.0).map(Date).collect()
+}
+
+// 全てのタイムゾーンのうち、最も遅れているタイムゾーン（UTC-12:00）での当日の日付を返す
+// これより前の日付は、どのタイムゾーンでも過去の日付になる
+pub(crate) fn earliest_today() -> Date {
+    local_now(-12 * 60).0
+}
+
+// 指定されたタイムゾーン（UTCからの差分・分）での現在の日付と時を返す
+pub(crate) fn local_now(timezone_offset_minutes: i16) -> (Date, u8) {
+    let offset = timezone_offset_minutes as i64 * NANOS_PER_MINUTE as i64;
+    let local = env::block_timestamp() as i64 + offset;
+    let date = Date(local.div_euclid(NANOS_PER_DAY as i64) as u64);
+    let hour = (local.rem_euclid(NANOS_PER_DAY as i64) / NANOS_PER_HOUR as i64) as u8;
+    (date, hour)
+}
+
+// 開始日から一定間隔で繰り返す日付を指定された回数分列挙する
+pub(crate) fn recurring(start_date: &Date, interval_days: u16, occurrences: u16) -> Vec<Date> {
+    (0..occurrences as u64)
+        .map(|i| Date(start_date.0 + i * interval_days as u64))
+        .collect()
+}
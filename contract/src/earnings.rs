@@ -0,0 +1,194 @@
+// オーナーの売上の台帳
+// オーナーへの支払いは直接送金せずに台帳に記録し、オーナーが任意のタイミングで引き出す
+
+use near_sdk::serde_json::json;
+use near_sdk::{Gas, PromiseResult};
+
+use crate::date::NANOS_PER_DAY;
+use crate::event::emit_event;
+use crate::ft_payment::transfer_to;
+use crate::*;
+
+// 引き出しの結果を確認するコールバックに割り当てるガス
+const GAS_FOR_ON_EARNINGS_WITHDRAWN: Gas = Gas(10_000_000_000_000);
+
+// 自動送金の設定
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AutoPayout {
+    threshold: U128,     // 送金する売上の下限（各通貨の最小単位）
+    interval_days: u16,  // 送金の間隔（日数）
+    next_payout_at: u64, // 次に送金できるようになる時刻
+}
+
+#[near_bindgen]
+impl Contract {
+    // オーナーの引き出し可能な売上を取得する
+    // `token_id`が`None`の場合はNEARの額を返す
+    pub fn get_earnings(&self, owner_id: AccountId, token_id: Option<AccountId>) -> U128 {
+        U128(self.internal_earnings(&owner_id, &token_id))
+    }
+
+    // 売上から指定された額を引き出す
+    // 送金に失敗した場合は、引き出した額が台帳に戻される
+    pub fn withdraw_earnings(&mut self, amount: U128, token_id: Option<AccountId>) -> Promise {
+        let owner_id = env::signer_account_id();
+        let earnings = self.internal_earnings(&owner_id, &token_id);
+        assert!(
+            amount.0 > 0 && amount.0 <= earnings,
+            "ERR_INSUFFICIENT_EARNINGS"
+        );
+        self.internal_payout_earnings(owner_id, token_id, amount.0, false)
+    }
+
+    // 売上を定期的に自動で送金するように設定する
+    // 送金の間隔が経過するごとに、下限を超えている通貨の売上の全額がオーナーに送金される
+    pub fn set_auto_payout(&mut self, threshold: U128, interval_days: u16) {
+        assert!(interval_days > 0, "ERR_INVALID_PAYOUT_INTERVAL");
+        let owner_id = env::signer_account_id();
+        let next_payout_at = env::block_timestamp() + interval_days as u64 * NANOS_PER_DAY;
+        self.auto_payouts.insert(
+            owner_id,
+            AutoPayout {
+                threshold,
+                interval_days,
+                next_payout_at,
+            },
+        );
+    }
+
+    // 売上の自動送金を停止する
+    pub fn disable_auto_payout(&mut self) {
+        self.auto_payouts
+            .remove(&env::signer_account_id())
+            .expect("ERR_AUTO_PAYOUT_NOT_ENABLED");
+    }
+
+    // オーナーの自動送金の設定を取得する
+    pub fn get_auto_payout(&self, owner_id: AccountId) -> Option<AutoPayout> {
+        self.auto_payouts.get(&owner_id).cloned()
+    }
+
+    // 引き出しの送金結果を確認し、失敗していた場合は台帳に戻す
+    #[private]
+    pub fn on_earnings_withdrawn(
+        &mut self,
+        owner_id: AccountId,
+        token_id: Option<AccountId>,
+        amount: U128,
+    ) -> bool {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return true;
+        }
+        self.internal_credit_earnings(&owner_id, &token_id, amount.0);
+        emit_event(
+            "payout_failed",
+            json!({ "owner_id": owner_id, "token_id": token_id, "amount": amount }),
+        );
+        false
+    }
+}
+
+impl Contract {
+    // 送金の間隔が経過したオーナーの売上を自動で送金し、処理したオーナーの数を返す
+    pub(crate) fn internal_process_auto_payouts(&mut self, limit: usize) -> u32 {
+        let now = env::block_timestamp();
+        let due: Vec<AccountId> = self
+            .auto_payouts
+            .iter()
+            .filter(|(_, auto_payout)| now >= auto_payout.next_payout_at)
+            .map(|(owner_id, _)| owner_id.clone())
+            .take(limit)
+            .collect();
+
+        for owner_id in due.iter() {
+            let auto_payout = self.auto_payouts.get_mut(owner_id).unwrap();
+            auto_payout.next_payout_at = now + auto_payout.interval_days as u64 * NANOS_PER_DAY;
+            let threshold = auto_payout.threshold.0;
+
+            let payouts: Vec<(Option<AccountId>, Balance)> = self
+                .earnings_by_owner
+                .get(owner_id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|(_, amount)| *amount >= threshold)
+                .collect();
+            for (token_id, amount) in payouts {
+                self.internal_payout_earnings(owner_id.clone(), token_id, amount, true);
+            }
+        }
+        due.len() as u32
+    }
+
+    // 売上を台帳から差し引いてオーナーに送金し、送金の結果をコールバックで確認する
+    fn internal_payout_earnings(
+        &mut self,
+        owner_id: AccountId,
+        token_id: Option<AccountId>,
+        amount: Balance,
+        auto: bool,
+    ) -> Promise {
+        let earnings = self.internal_earnings(&owner_id, &token_id);
+        self.internal_set_earnings(&owner_id, &token_id, earnings - amount);
+        emit_event(
+            "payout",
+            json!({
+                "owner_id": owner_id,
+                "token_id": token_id,
+                "amount": U128(amount),
+                "auto": auto,
+            }),
+        );
+
+        transfer_to(owner_id.clone(), &token_id, amount).then(
+            Promise::new(env::current_account_id()).function_call(
+                "on_earnings_withdrawn".to_string(),
+                json!({ "owner_id": owner_id, "token_id": token_id, "amount": U128(amount) })
+                    .to_string()
+                    .into_bytes(),
+                0,
+                GAS_FOR_ON_EARNINGS_WITHDRAWN,
+            ),
+        )
+    }
+
+    // オーナーの売上を台帳に加算する
+    pub(crate) fn internal_credit_earnings(
+        &mut self,
+        owner_id: &AccountId,
+        token_id: &Option<AccountId>,
+        amount: Balance,
+    ) {
+        if amount == 0 {
+            return;
+        }
+        let earnings = self.internal_earnings(owner_id, token_id);
+        self.internal_set_earnings(owner_id, token_id, earnings + amount);
+    }
+
+    fn internal_earnings(&self, owner_id: &AccountId, token_id: &Option<AccountId>) -> Balance {
+        self.earnings_by_owner
+            .get(owner_id)
+            .and_then(|earnings| earnings.get(token_id).copied())
+            .unwrap_or(0)
+    }
+
+    fn internal_set_earnings(
+        &mut self,
+        owner_id: &AccountId,
+        token_id: &Option<AccountId>,
+        amount: Balance,
+    ) {
+        let mut earnings = self.earnings_by_owner.get(owner_id).unwrap_or_default();
+        if amount == 0 {
+            earnings.remove(token_id);
+        } else {
+            earnings.insert(token_id.clone(), amount);
+        }
+        if earnings.is_empty() {
+            self.earnings_by_owner.remove(owner_id);
+        } else {
+            self.earnings_by_owner.insert(owner_id, &earnings);
+        }
+    }
+}
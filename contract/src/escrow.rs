@@ -0,0 +1,51 @@
+// 予約ごとにコントラクトが預かっているNEARの台帳
+// 予約者から受け取ったNEARやトークンは全てこの台帳に記録し、オーナーへの支払いや返金は台帳から払い出す
+// 預かり金の単位は、予約の支払いに使われたNEARまたはトークンになる
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // 予約に対してコントラクトが預かっているNEARの額を取得する
+    pub fn get_escrow(&self, booking_id: BookingId) -> U128 {
+        U128(self.escrow_by_booking.get(&booking_id).unwrap_or(0))
+    }
+}
+
+impl Contract {
+    // 予約者から受け取ったNEARを予約の預かり金として記録する
+    pub(crate) fn internal_hold_escrow(&mut self, booking_id: BookingId, amount: Balance) {
+        if amount == 0 {
+            return;
+        }
+        let held = self.escrow_by_booking.get(&booking_id).unwrap_or(0);
+        self.escrow_by_booking.insert(&booking_id, &(held + amount));
+    }
+
+    // 予約の預かり金から指定された額を差し引く
+    // 預かり金を超える額は差し引けない
+    pub(crate) fn internal_debit_escrow(&mut self, booking_id: BookingId, amount: Balance) {
+        let held = self.escrow_by_booking.get(&booking_id).unwrap_or(0);
+        assert!(held >= amount, "ERR_INSUFFICIENT_ESCROW");
+        if held == amount {
+            self.escrow_by_booking.remove(&booking_id);
+        } else {
+            self.escrow_by_booking.insert(&booking_id, &(held - amount));
+        }
+    }
+
+    // 予約の預かり金から指定された額を払い出し、受取人に送金する
+    // トークンで支払われた予約の場合は、同じトークンで送金する
+    // 送金に失敗した場合は、受取人が後から再請求できる
+    // 預かり金を超える額は払い出せない
+    pub(crate) fn internal_release_escrow(
+        &mut self,
+        booking_id: BookingId,
+        receiver_id: AccountId,
+        amount: Balance,
+    ) -> Promise {
+        self.internal_debit_escrow(booking_id, amount);
+        let token_id = self.internal_booking(booking_id).payment_token;
+        self.internal_send_refund(receiver_id, token_id, amount)
+    }
+}
@@ -0,0 +1,20 @@
+// NEP-297形式のイベントをログに出力する
+// インデクサーやフロントエンドがコントラクトの状態の変化を追跡できるようにする
+
+use near_sdk::serde_json::{json, Value};
+
+use crate::*;
+
+const EVENT_STANDARD: &str = "near-booking";
+const EVENT_VERSION: &str = "1.0.0";
+
+// イベント名とデータを受け取り、`EVENT_JSON:`を先頭に付けてログに出力する
+pub(crate) fn emit_event(event: &str, data: Value) {
+    let event = json!({
+        "standard": EVENT_STANDARD,
+        "version": EVENT_VERSION,
+        "event": event,
+        "data": [data],
+    });
+    env::log_str(&format!("EVENT_JSON:{}", event));
+}
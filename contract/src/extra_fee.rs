@@ -0,0 +1,81 @@
+// 掲出料とは別に予約時に加算される料金（清掃料金・追加人数料金）
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // 清掃料金と追加人数料金を変更する
+    // 清掃料金は1回の予約ごと、追加人数料金は含まれる人数を超える1人・1泊ごとに加算される
+    // 変更後の料金は、これから作成される予約にのみ適用される
+    // 部屋のオーナーのみがコールできる
+    pub fn set_extra_fees(
+        &mut self,
+        room_id: RoomId,
+        cleaning_fee: U128,
+        extra_guest_fee: U128,
+        included_guests: u16,
+    ) {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        assert_valid_guest_limits(included_guests, room.max_guests);
+
+        room.cleaning_fee = cleaning_fee;
+        room.extra_guest_fee = extra_guest_fee;
+        room.included_guests = included_guests;
+    }
+
+    // 部屋（ベッド）1つあたりの最大人数を変更する
+    // 0の場合は人数を制限しない
+    // 部屋のオーナーのみがコールできる
+    pub fn set_max_guests(&mut self, room_id: RoomId, max_guests: u16) {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        assert_valid_guest_limits(room.included_guests, max_guests);
+
+        room.max_guests = max_guests;
+    }
+}
+
+impl Room {
+    // 予約の清掃料金と追加人数料金を返す
+    // 料金に含まれる人数は、予約する部屋（ベッド）1つあたりの人数として数える
+    pub(crate) fn extra_fees(
+        &self,
+        nights: usize,
+        quantity: u16,
+        guests: u16,
+    ) -> (Balance, Balance) {
+        let included_guests = self.included_guests as u32 * quantity as u32;
+        let extra_guests = (guests as u32).saturating_sub(included_guests);
+        let extra_guest_fee = self.extra_guest_fee.0 * extra_guests as Balance * nights as Balance;
+        (self.cleaning_fee.0, extra_guest_fee)
+    }
+
+    // 予約の人数が、予約する部屋（ベッド）の数に応じた最大人数を超えていないかを確認する
+    pub(crate) fn assert_within_max_guests(&self, quantity: u16, guests: u16) {
+        assert!(
+            self.is_within_max_guests(quantity, guests),
+            "ERR_TOO_MANY_GUESTS"
+        );
+    }
+
+    // 人数が、予約する部屋（ベッド）の数に応じた最大人数を超えていないかを返す
+    pub(crate) fn is_within_max_guests(&self, quantity: u16, guests: u16) -> bool {
+        self.max_guests == 0 || guests as u32 <= self.max_guests as u32 * quantity as u32
+    }
+}
+
+// 料金に含まれる人数が1人以上で、最大人数を超えていないかを確認する
+pub(crate) fn assert_valid_guest_limits(included_guests: u16, max_guests: u16) {
+    assert!(included_guests > 0, "ERR_INVALID_INCLUDED_GUESTS");
+    assert!(
+        max_guests == 0 || included_guests <= max_guests,
+        "ERR_INVALID_MAX_GUESTS"
+    );
+}
@@ -0,0 +1,209 @@
+// NEP-141のトークンによる支払い
+// 予約者は`ft_transfer_call`でトークンを送金し、`msg`に予約内容をJSONで指定する
+
+use near_sdk::serde_json::{self, json};
+use near_sdk::{Gas, PromiseOrValue};
+
+use crate::booking::PaymentMethod;
+use crate::*;
+
+// `ft_transfer`のコールに割り当てるガス
+const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
+
+// `ft_transfer_call`の`msg`に指定する予約内容
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenBookingRequest {
+    room_id: RoomId,
+    check_in_date: CheckInDate,
+    check_out_date: CheckInDate,
+    quantity: Option<u16>,
+    guests: Option<u16>,
+    note: Option<String>,
+}
+
+#[near_bindgen]
+impl Contract {
+    // 支払いに使えるトークンを追加する
+    // 管理者のみがコールできる
+    pub fn add_payment_token(&mut self, token_id: AccountId) {
+        self.assert_admin();
+        self.payment_tokens.insert(token_id);
+    }
+
+    // 支払いに使えるトークンを削除する
+    // 削除したトークンでは新しい予約を受け付けない
+    // 管理者のみがコールできる
+    pub fn remove_payment_token(&mut self, token_id: AccountId) {
+        self.assert_admin();
+        self.payment_tokens.remove(&token_id);
+    }
+
+    // 支払いに使えるトークンの一覧を取得する
+    pub fn get_payment_tokens(&self) -> Vec<AccountId> {
+        self.payment_tokens.iter().cloned().collect()
+    }
+
+    // 部屋の掲出料を支払うトークンを変更する
+    // `None`を指定するとNEARで支払うようになる
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn set_payment_token(&mut self, room_id: RoomId, payment_token: Option<AccountId>) {
+        self.assert_payment_token(&payment_token);
+
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        assert!(
+            !room.usd_pricing || payment_token.is_none(),
+            "ERR_USD_PRICED_ROOM_PAYS_IN_NEAR"
+        );
+        assert!(
+            room.pricing_contract_id.is_none() || payment_token.is_none(),
+            "ERR_EXTERNALLY_PRICED_ROOM_PAYS_IN_NEAR"
+        );
+
+        room.payment_token = payment_token;
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // トークンのコントラクトから`ft_transfer_call`の際にコールされる
+    // 送金されたトークンで部屋を予約し、使われなかった額をトークンのコントラクトに返す
+    pub fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let request: TokenBookingRequest =
+            serde_json::from_str(&msg).expect("ERR_INVALID_BOOKING_MESSAGE");
+        let token_id = env::predecessor_account_id();
+        self.assert_payment_token(&Some(token_id.clone()));
+
+        let quantity = request.quantity.unwrap_or(1);
+        assert!(quantity > 0, "ERR_INVALID_QUANTITY");
+        let (_, total) = self.internal_create_booking(
+            &sender_id,
+            PaymentMethod::Token(token_id),
+            &request.room_id,
+            request.check_in_date,
+            request.check_out_date,
+            quantity,
+            request.guests,
+            request.note,
+            None,
+            None,
+            None,
+        );
+        assert!(amount.0 >= total, "ERR_INSUFFICIENT_PAYMENT");
+
+        PromiseOrValue::Value(U128(amount.0 - total))
+    }
+}
+
+impl Contract {
+    // 支払いに使えるトークンであるかを確認する
+    pub(crate) fn assert_payment_token(&self, payment_token: &Option<AccountId>) {
+        if let Some(token_id) = payment_token {
+            assert!(
+                self.payment_tokens.contains(token_id),
+                "ERR_TOKEN_NOT_ACCEPTED"
+            );
+        }
+    }
+}
+
+// NEARまたはトークンを受取人に送金する
+// `token_id`が`None`の場合はNEARを送金する
+pub(crate) fn transfer_to(
+    receiver_id: AccountId,
+    token_id: &Option<AccountId>,
+    amount: Balance,
+) -> Promise {
+    match token_id {
+        Some(token_id) => ft_transfer(token_id.clone(), receiver_id, amount),
+        None => Promise::new(receiver_id).transfer(amount),
+    }
+}
+
+// トークンを受取人に送金する
+pub(crate) fn ft_transfer(token_id: AccountId, receiver_id: AccountId, amount: Balance) -> Promise {
+    Promise::new(token_id).function_call(
+        "ft_transfer".to_string(),
+        json!({
+            "receiver_id": receiver_id,
+            "amount": U128(amount),
+        })
+        .to_string()
+        .into_bytes(),
+        1,
+        GAS_FOR_FT_TRANSFER,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::ONE_NEAR;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    fn token_id() -> AccountId {
+        "usdc.near".parse().unwrap()
+    }
+
+    // 部屋の掲出料をトークンで支払うように設定する
+    fn accept_token(contract: &mut Contract, room_id: &RoomId) {
+        set_caller(&contract_id(), 0);
+        contract.add_payment_token(token_id());
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.set_payment_token(room_id.clone(), Some(token_id()));
+    }
+
+    // トークンのコントラクトから`ft_on_transfer`をコールし、10日後から2泊を予約する
+    fn transfer_call(contract: &mut Contract, room_id: &RoomId, amount: Balance) -> Balance {
+        set_caller(&token_id(), 0);
+        let check_in_date = today().add_days(10);
+        let msg = json!({
+            "room_id": room_id,
+            "check_in_date": check_in_date,
+            "check_out_date": check_in_date.add_days(2),
+        })
+        .to_string();
+        match contract.ft_on_transfer(guest_id(), U128(amount), msg) {
+            PromiseOrValue::Value(unused) => unused.0,
+            PromiseOrValue::Promise(_) => panic!("unexpected promise"),
+        }
+    }
+
+    #[test]
+    fn books_with_token_and_returns_unused_amount() {
+        let (mut contract, room_id) = setup();
+        accept_token(&mut contract, &room_id);
+        let booking_id = contract.next_booking_id;
+
+        assert_eq!(transfer_call(&mut contract, &room_id, 3 * PRICE), PRICE);
+        let booking = contract.internal_booking(booking_id);
+        assert_eq!(booking.guest_id, guest_id());
+        assert_eq!(booking.payment_token, Some(token_id()));
+        assert_eq!(booking.amount.0, 2 * PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_PAYMENT")]
+    fn rejects_token_transfer_below_total() {
+        let (mut contract, room_id) = setup();
+        accept_token(&mut contract, &room_id);
+        transfer_call(&mut contract, &room_id, PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TOKEN_NOT_ACCEPTED")]
+    fn rejects_transfer_of_unaccepted_token() {
+        let (mut contract, room_id) = setup();
+        transfer_call(&mut contract, &room_id, 2 * PRICE);
+    }
+}
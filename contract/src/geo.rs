@@ -0,0 +1,189 @@
+// 施設の緯度・経度と、近くの部屋の検索
+// 緯度・経度は10^-6度単位の整数で保存し、ジオハッシュ（5文字、約5km四方）の区画ごとに部屋のIDの索引を作る
+// 検索時は指定された範囲に重なる区画の索引だけを調べ、全ての部屋を調べずに近くの部屋を取得する
+
+use crate::*;
+
+// 1度あたりの緯度・経度の単位の数
+const MICRODEGREES: i64 = 1_000_000;
+// 緯度1度あたりの距離（メートル）
+const METERS_PER_DEGREE: f64 = 111_320.0;
+// ジオハッシュの区画の数（5文字の場合、経度13ビット・緯度12ビット）
+const LNG_CELLS: i64 = 1 << 13;
+const LAT_CELLS: i64 = 1 << 12;
+// ジオハッシュで使用する文字
+const GEOHASH_ALPHABET: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+// 検索できる範囲の半径の上限（メートル）
+const MAX_RADIUS_M: u32 = 20_000;
+// 1回の検索で調べる区画の最大数
+const MAX_SEARCH_CELLS: i64 = 400;
+
+#[near_bindgen]
+impl Contract {
+    // 指定された地点から半径`radius_m`メートル以内にある、掲載中の部屋を近い順に取得する
+    // 緯度・経度は10^-6度単位で指定する
+    // 緯度・経度が登録されていない部屋は含まれない
+    // 高緯度では経度方向の区画が狭くなるため、調べる区画の数が`MAX_SEARCH_CELLS`に収まる範囲まで半径を縮めて検索する
+    // （20kmの半径は緯度約76度まで、10kmの半径は緯度約85度まで縮めずに検索できる）
+    pub fn get_rooms_near(
+        &self,
+        lat: i32,
+        lng: i32,
+        radius_m: u32,
+        lang: Option<String>,
+    ) -> Vec<AvailableRoom> {
+        assert_valid_coordinates(lat, lng);
+        assert!(radius_m <= MAX_RADIUS_M, "ERR_RADIUS_TOO_LARGE");
+
+        // 範囲に重なる区画を、中心の区画からの緯度・経度方向の区画の数で求める
+        let (lat_cell, lng_cell) = geohash_cell(lat, lng);
+        let cell_degrees = 180.0 / LAT_CELLS as f64;
+        let lat_degrees = radius_m as f64 / METERS_PER_DEGREE;
+        let lng_degrees = lat_degrees / to_radians(lat).cos().abs().max(1e-6);
+        let lat_steps = (lat_degrees / cell_degrees).ceil() as i64;
+        let max_lng_steps = (MAX_SEARCH_CELLS / (2 * lat_steps + 1) - 1) / 2;
+        let lng_steps = (lng_degrees / cell_degrees).ceil() as i64;
+        let radius_m = if lng_steps > max_lng_steps {
+            // 調べる経度方向の区画の数を抑え、その区画に収まる範囲を半径とする
+            let lng_meters = max_lng_steps as f64 * cell_degrees * METERS_PER_DEGREE;
+            (lng_meters * to_radians(lat).cos().abs()) as u32
+        } else {
+            radius_m
+        };
+        let lng_steps = lng_steps.min(max_lng_steps);
+
+        let mut rooms: Vec<(u64, &RoomId, &Room)> = vec![];
+        for lat_cell in (lat_cell - lat_steps).max(0)..=(lat_cell + lat_steps).min(LAT_CELLS - 1) {
+            for lng_cell in lng_cell - lng_steps..=lng_cell + lng_steps {
+                // 経度180度をまたぐ区画は反対側の区画として扱う
+                let key = geohash(lat_cell, lng_cell.rem_euclid(LNG_CELLS));
+                let room_ids = match self.rooms_by_geohash.get(&key) {
+                    Some(room_ids) => room_ids,
+                    None => continue,
+                };
+                for room_id in room_ids.iter() {
+                    let (room_id, room) = self.rooms_by_id.get_key_value(&room_id).unwrap();
+                    let location = &room.location;
+                    let distance =
+                        distance_m(lat, lng, location.lat.unwrap(), location.lng.unwrap());
+                    if room.listed && distance <= radius_m as u64 {
+                        rooms.push((distance, room_id, room));
+                    }
+                }
+            }
+        }
+        rooms.sort_by_key(|(distance, _, _)| *distance);
+        rooms
+            .into_iter()
+            .map(|(_, room_id, room)| room.to_available_room(room_id, lang.as_deref()))
+            .collect()
+    }
+}
+
+impl Location {
+    // ジオハッシュの索引のキーを返す
+    // 緯度・経度が登録されていない所在地は索引に含めない
+    pub(crate) fn geohash_keys(&self) -> Vec<String> {
+        match (self.lat, self.lng) {
+            (Some(lat), Some(lng)) => {
+                let (lat_cell, lng_cell) = geohash_cell(lat, lng);
+                vec![geohash(lat_cell, lng_cell)]
+            }
+            _ => vec![],
+        }
+    }
+}
+
+// 緯度・経度が両方とも指定されているか、両方とも省略されているかを確認する
+pub(crate) fn assert_valid_location_coordinates(lat: Option<i32>, lng: Option<i32>) {
+    match (lat, lng) {
+        (Some(lat), Some(lng)) => assert_valid_coordinates(lat, lng),
+        (None, None) => {}
+        _ => panic!("ERR_INVALID_COORDINATES"),
+    }
+}
+
+// 緯度が±90度、経度が±180度の範囲内であるかを確認する
+fn assert_valid_coordinates(lat: i32, lng: i32) {
+    assert!(
+        (lat as i64).abs() <= 90 * MICRODEGREES && (lng as i64).abs() <= 180 * MICRODEGREES,
+        "ERR_INVALID_COORDINATES"
+    );
+}
+
+// 緯度・経度を含む区画の番号（緯度方向, 経度方向）を返す
+fn geohash_cell(lat: i32, lng: i32) -> (i64, i64) {
+    let lat_cell = (lat as i64 + 90 * MICRODEGREES) * LAT_CELLS / (180 * MICRODEGREES);
+    let lng_cell = (lng as i64 + 180 * MICRODEGREES) * LNG_CELLS / (360 * MICRODEGREES);
+    (lat_cell.min(LAT_CELLS - 1), lng_cell.min(LNG_CELLS - 1))
+}
+
+// 区画の番号を、経度と緯度のビットを交互に並べたジオハッシュの文字列にする
+fn geohash(lat_cell: i64, lng_cell: i64) -> String {
+    let mut bits: u32 = 0;
+    for i in 0..25 {
+        let bit = if i % 2 == 0 {
+            lng_cell >> (12 - i / 2)
+        } else {
+            lat_cell >> (11 - i / 2)
+        };
+        bits = (bits << 1) | (bit & 1) as u32;
+    }
+    (0..5)
+        .map(|i| GEOHASH_ALPHABET[(bits >> (20 - 5 * i) & 0x1f) as usize] as char)
+        .collect()
+}
+
+// 2地点間のおおよその距離（メートル）を返す
+// 検索する範囲は狭いため、緯度による経度1度あたりの距離の違いのみを補正する
+fn distance_m(lat: i32, lng: i32, other_lat: i32, other_lng: i32) -> u64 {
+    let lat_diff = (lat as i64 - other_lat as i64) as f64;
+    let lng_diff = ((lng as i64 - other_lng as i64 + 180 * MICRODEGREES)
+        .rem_euclid(360 * MICRODEGREES)
+        - 180 * MICRODEGREES) as f64;
+    let mean_lat = ((lat as i64 + other_lat as i64) / 2) as i32;
+    let x = lng_diff * to_radians(mean_lat).cos();
+    ((x * x + lat_diff * lat_diff).sqrt() / MICRODEGREES as f64 * METERS_PER_DEGREE) as u64
+}
+
+// 10^-6度単位の角度をラジアンにする
+fn to_radians(microdegrees: i32) -> f64 {
+    (microdegrees as f64 / MICRODEGREES as f64).to_radians()
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::ONE_NEAR;
+
+    use super::*;
+    use crate::room_update::UpdateRoomArgs;
+    use crate::test_utils::*;
+
+    // 緯度・経度を10^-6度単位で指定して、部屋の所在地を変更する
+    fn set_coordinates(contract: &mut Contract, room_id: &RoomId, lat: i32, lng: i32) {
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.update_room(
+            room_id.clone(),
+            UpdateRoomArgs {
+                location: Some(Location {
+                    country: "NO".to_string(),
+                    city: "Longyearbyen".to_string(),
+                    lat: Some(lat),
+                    lng: Some(lng),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+    }
+
+    #[test]
+    fn searches_near_rooms_at_high_latitude() {
+        let (mut contract, room_id) = setup();
+        set_coordinates(&mut contract, &room_id, 78_223_000, 15_646_000);
+
+        // 約1km離れた地点から半径20kmで検索する
+        let rooms = contract.get_rooms_near(78_232_000, 15_646_000, 20_000, None);
+        assert_eq!(rooms.len(), 1);
+    }
+}
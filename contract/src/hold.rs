@@ -0,0 +1,270 @@
+use crate::*;
+
+// 仮押さえの有効期間（15分）
+const HOLD_DURATION: u64 = 15 * 60 * 1_000_000_000;
+// 有効期限が切れた後、同じアカウントが同じ宿泊日を再び仮押さえできるようになるまでの時間
+const HOLD_COOLDOWN: u64 = HOLD_DURATION;
+// 仮押さえに必要な預かり金（0.1NEAR）
+const HOLD_DEPOSIT: Balance = 100_000_000_000_000_000_000_000;
+// 1つのアカウントが1つの部屋で同時に仮押さえできる宿泊日の最大数
+const MAX_HOLDS_PER_ACCOUNT: usize = 7;
+
+// 支払いが完了するまでの間、宿泊日を仮押さえするデータ
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Hold {
+    guest_id: AccountId, // 仮押さえしたアカウントID
+    expires_at: u64,     // 有効期限（ブロックのタイムスタンプ）
+    deposit: U128,       // 預かり金（キャンセル待ちから仮押さえした場合は0）
+}
+
+#[near_bindgen]
+impl Contract {
+    // 宿泊日を一定時間仮押さえする
+    // `HOLD_DEPOSIT`以上のNEARを添付し、超えた分は返金する
+    // 預かり金は、仮押さえした宿泊日を予約した場合と、有効期限が切れた仮押さえが削除された場合に返金する
+    // 有効期限が切れた後は、`claim_expired_hold`で予約者が預かり金の返金を受けることもできる
+    // 複数の部屋（ベッド）がある場合は、空きの数まで別々のアカウントが仮押さえできる
+    // 仮押さえ中の宿泊日を延長することはできず、有効期限が切れた後も`HOLD_COOLDOWN`の間は同じ宿泊日を仮押さえできない
+    #[payable]
+    pub fn hold_room(&mut self, room_id: RoomId, check_in_date: CheckInDate) -> u64 {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        let guest_id = env::signer_account_id();
+        let deposit = env::attached_deposit();
+        assert!(deposit >= HOLD_DEPOSIT, "ERR_DEPOSIT_IS_INCORRECT");
+
+        assert!(room.listed, "ERR_ROOM_NOT_LISTED");
+        check_in_date.assert_not_past(&room.today());
+        room.assert_within_booking_window(&check_in_date);
+        room.assert_before_same_day_cutoff(&check_in_date);
+        assert!(
+            !room.is_blocked(&check_in_date) && !room.is_fully_booked(&check_in_date),
+            "ERR_ALREADY_BOOKED"
+        );
+        assert!(!room.is_in_auction(&check_in_date), "ERR_DATE_IN_AUCTION");
+        assert!(
+            !room.has_recent_hold(&check_in_date, &guest_id),
+            "ERR_ALREADY_HELD"
+        );
+        assert!(room.vacancies(&check_in_date, None) > 0, "ERR_ROOM_ON_HOLD");
+        assert!(
+            room.active_holds_by(&guest_id) < MAX_HOLDS_PER_ACCOUNT,
+            "ERR_TOO_MANY_HOLDS"
+        );
+
+        let initial_size = room.serialized_size();
+        let expires_at = room.place_hold(check_in_date, guest_id, HOLD_DURATION, HOLD_DEPOSIT);
+        self.internal_track_room_storage(&room_id, initial_size);
+        if deposit > HOLD_DEPOSIT {
+            Promise::new(env::predecessor_account_id()).transfer(deposit - HOLD_DEPOSIT);
+        }
+        expires_at
+    }
+
+    // 有効期限が切れた仮押さえの預かり金を返金する
+    // 仮押さえの記録は`HOLD_COOLDOWN`が過ぎるまで残り、その間は同じ宿泊日を再び仮押さえできない
+    // 仮押さえしたアカウントのみがコールできる
+    pub fn claim_expired_hold(&mut self, room_id: RoomId, check_in_date: CheckInDate) -> U128 {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        let guest_id = env::signer_account_id();
+        let hold = room
+            .holds
+            .get_mut(&check_in_date)
+            .and_then(|holds| holds.iter_mut().find(|hold| hold.guest_id == guest_id))
+            .expect("ERR_NOT_FOUND_HOLD");
+        assert!(!hold.is_active(), "ERR_HOLD_NOT_EXPIRED");
+        let deposit = hold.deposit;
+        assert!(deposit.0 > 0, "ERR_NO_HOLD_DEPOSIT");
+
+        hold.deposit = U128(0);
+        Promise::new(guest_id).transfer(deposit.0);
+        deposit
+    }
+
+    // 宿泊日の有効な仮押さえを取得する
+    // 有効期限が切れた仮押さえは含まれない
+    pub fn get_holds(&self, room_id: RoomId, check_in_date: CheckInDate) -> Vec<Hold> {
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        room.holds
+            .get(&check_in_date)
+            .map(|holds| {
+                holds
+                    .iter()
+                    .filter(|hold| hold.is_active())
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Room {
+    // 指定された期間、宿泊日を仮押さえし、有効期限を返す
+    // 部屋の全ての宿泊日から、再び仮押さえできるようになった期限切れの仮押さえを削除する
+    pub(crate) fn place_hold(
+        &mut self,
+        date: CheckInDate,
+        guest_id: AccountId,
+        duration: u64,
+        deposit: Balance,
+    ) -> u64 {
+        let now = env::block_timestamp();
+        self.remove_holds(|_, hold| hold.expires_at + HOLD_COOLDOWN <= now);
+
+        // キャンセル待ちから仮押さえする場合は、同じアカウントの以前の仮押さえを置き換える
+        self.remove_holds(|held_date, hold| held_date == &date && hold.guest_id == guest_id);
+        let expires_at = now + duration;
+        self.holds
+            .entry(date)
+            .or_default()
+            .push(Hold::new(guest_id, expires_at, deposit));
+        expires_at
+    }
+
+    // 指定されたアカウントの宿泊日の仮押さえを削除し、預かり金を返金する
+    pub(crate) fn release_hold(&mut self, date: &CheckInDate, guest_id: &AccountId) {
+        self.remove_holds(|held_date, hold| held_date == date && &hold.guest_id == guest_id);
+    }
+
+    // 全ての仮押さえを削除し、預かり金を返金する
+    pub(crate) fn clear_holds(&mut self) {
+        self.remove_holds(|_, _| true);
+    }
+
+    // 指定された日付の有効な仮押さえの数を返す
+    // `guest_id`が指定された場合、そのアカウントの仮押さえは数えない
+    pub(crate) fn active_holds(&self, date: &CheckInDate, guest_id: Option<&AccountId>) -> u16 {
+        self.holds.get(date).map_or(0, |holds| {
+            holds
+                .iter()
+                .filter(|hold| hold.is_active() && Some(&hold.guest_id) != guest_id)
+                .count() as u16
+        })
+    }
+
+    // 指定されたアカウントがこの部屋で仮押さえしている、有効な宿泊日の数を返す
+    fn active_holds_by(&self, guest_id: &AccountId) -> usize {
+        self.holds
+            .values()
+            .flatten()
+            .filter(|hold| hold.is_active() && &hold.guest_id == guest_id)
+            .count()
+    }
+
+    // 指定されたアカウントが、宿泊日を仮押さえ中か、有効期限が切れてから`HOLD_COOLDOWN`が経っていないかを確認する
+    fn has_recent_hold(&self, date: &CheckInDate, guest_id: &AccountId) -> bool {
+        let now = env::block_timestamp();
+        self.holds.get(date).is_some_and(|holds| {
+            holds
+                .iter()
+                .any(|hold| &hold.guest_id == guest_id && hold.expires_at + HOLD_COOLDOWN > now)
+        })
+    }
+
+    // 条件に一致する仮押さえを削除し、預かり金を返金する
+    fn remove_holds(&mut self, remove: impl Fn(&CheckInDate, &Hold) -> bool) {
+        for (date, holds) in self.holds.iter_mut() {
+            holds.retain(|hold| {
+                if !remove(date, hold) {
+                    return true;
+                }
+                if hold.deposit.0 > 0 {
+                    Promise::new(hold.guest_id.clone()).transfer(hold.deposit.0);
+                }
+                false
+            });
+        }
+        self.holds.retain(|_, holds| !holds.is_empty());
+    }
+}
+
+impl Hold {
+    pub(crate) fn new(guest_id: AccountId, expires_at: u64, deposit: Balance) -> Self {
+        Self {
+            guest_id,
+            expires_at,
+            deposit: U128(deposit),
+        }
+    }
+
+    // 有効期限が切れていないかを確認する
+    fn is_active(&self) -> bool {
+        self.expires_at > env::block_timestamp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    #[should_panic(expected = "ERR_DEPOSIT_IS_INCORRECT")]
+    fn requires_hold_deposit() {
+        let (mut contract, room_id) = setup();
+        set_caller(&guest_id(), 0);
+        contract.hold_room(room_id, today().add_days(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ALREADY_HELD")]
+    fn rejects_hold_again_right_after_expiry() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = today().add_days(10);
+        set_caller(&guest_id(), HOLD_DEPOSIT);
+        contract.hold_room(room_id.clone(), check_in_date);
+
+        set_caller_at(&guest_id(), HOLD_DEPOSIT, noon(&today()) + HOLD_DURATION);
+        contract.hold_room(room_id, check_in_date);
+    }
+
+    #[test]
+    fn refunds_hold_deposit_when_holder_books() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = today().add_days(10);
+        set_caller(&guest_id(), HOLD_DEPOSIT);
+        contract.hold_room(room_id.clone(), check_in_date);
+        assert_eq!(contract.get_holds(room_id.clone(), check_in_date).len(), 1);
+
+        book(&mut contract, &room_id, check_in_date, 1, PRICE);
+        assert_eq!(transferred_to(&guest_id()), HOLD_DEPOSIT);
+        assert!(contract.get_holds(room_id, check_in_date).is_empty());
+    }
+
+    #[test]
+    fn refunds_expired_hold_deposit_once() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = today().add_days(10);
+        set_caller(&guest_id(), HOLD_DEPOSIT);
+        contract.hold_room(room_id.clone(), check_in_date);
+
+        set_caller_at(&guest_id(), 0, noon(&today()) + HOLD_DURATION);
+        contract.claim_expired_hold(room_id.clone(), check_in_date);
+        assert_eq!(transferred_to(&guest_id()), HOLD_DEPOSIT);
+
+        // 既に返金済みの仮押さえは、期限切れの削除時にも返金しない
+        set_caller_at(
+            &guest_id(),
+            HOLD_DEPOSIT,
+            noon(&today()) + HOLD_DURATION + HOLD_COOLDOWN,
+        );
+        contract.hold_room(room_id, check_in_date);
+        assert_eq!(transferred_to(&guest_id()), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_HOLD_NOT_EXPIRED")]
+    fn rejects_refund_of_active_hold() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = today().add_days(10);
+        set_caller(&guest_id(), HOLD_DEPOSIT);
+        contract.hold_room(room_id.clone(), check_in_date);
+        contract.claim_expired_hold(room_id, check_in_date);
+    }
+}
@@ -0,0 +1,47 @@
+// ハウスルールとチェックイン・チェックアウトの時刻
+// 部屋の利用上の決まりをオンチェーンに保存し、予約前に確認できるようにする
+
+use crate::*;
+
+// ハウスルールの最大の文字数
+const MAX_HOUSE_RULES_LENGTH: usize = 2000;
+
+#[near_bindgen]
+impl Contract {
+    // ハウスルールとチェックイン・チェックアウトの時刻（現地時間）を変更する
+    // チェックアウトの時刻は、超過滞在の猶予時間を数え始める時刻になる
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn set_house_rules(
+        &mut self,
+        room_id: RoomId,
+        house_rules: String,
+        check_in_hour: u8,
+        check_out_hour: u8,
+    ) {
+        assert_valid_house_rules(&house_rules, check_in_hour, check_out_hour);
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.house_rules = house_rules;
+        room.check_in_hour = check_in_hour;
+        room.check_out_hour = check_out_hour;
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+}
+
+// ハウスルールの文字数と、チェックイン・チェックアウトの時刻が0〜23時の範囲であるかを確認する
+pub(crate) fn assert_valid_house_rules(house_rules: &str, check_in_hour: u8, check_out_hour: u8) {
+    assert!(
+        house_rules.chars().count() <= MAX_HOUSE_RULES_LENGTH,
+        "ERR_HOUSE_RULES_TOO_LONG"
+    );
+    assert!(
+        check_in_hour < 24 && check_out_hour < 24,
+        "ERR_INVALID_HOUR"
+    );
+}
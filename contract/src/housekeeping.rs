@@ -0,0 +1,46 @@
+// 部屋の清掃状況
+// オーナーとスタッフが清掃の進み具合を記録し、清掃が終わっていない部屋へのチェックインを防ぐ
+
+use near_sdk::serde_json::json;
+
+use crate::event::emit_event;
+use crate::*;
+
+// 部屋の清掃状況
+#[derive(
+    Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone, Copy,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum HousekeepingStatus {
+    Dirty,    // 清掃が必要
+    Cleaning, // 清掃中
+    Ready,    // 清掃済みでチェックインできる
+}
+
+#[near_bindgen]
+impl Contract {
+    // 部屋の清掃状況を変更し、運営チーム向けのイベントを発行する
+    // 部屋のオーナーと、施設のマネージャー・フロントがコールできる
+    pub fn set_housekeeping_status(&mut self, room_id: RoomId, status: HousekeepingStatus) {
+        self.assert_room_operator(&room_id, FRONT_DESK_ROLES);
+        let room = self.rooms_by_id.get_mut(&room_id).unwrap();
+
+        room.housekeeping_status = status;
+        emit_event(
+            "housekeeping_status_changed",
+            json!({
+                "room_id": room_id,
+                "status": status,
+                "updated_by": env::signer_account_id(),
+            }),
+        );
+    }
+
+    // 部屋の清掃状況を取得する
+    pub fn get_housekeeping_status(&self, room_id: RoomId) -> HousekeepingStatus {
+        self.rooms_by_id
+            .get(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM")
+            .housekeeping_status
+    }
+}
@@ -0,0 +1,143 @@
+// 高額な宿泊の分割払い
+// 予約時に一部を前払いし、残額を期限までに支払う。期限までに支払われない予約はキャンセルされる
+
+use crate::date::NANOS_PER_DAY;
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // 分割払いの前払いの割合と、残額の支払い期限（チェックインの何日前か）を変更する
+    // 前払いの割合が0の場合は分割払いを受け付けない
+    // 変更後の設定は、これから作成される予約にのみ適用される
+    // 部屋のオーナーのみがコールできる
+    pub fn set_installment_policy(
+        &mut self,
+        room_id: RoomId,
+        deposit_percent: u8,
+        due_days_before_check_in: u16,
+    ) {
+        assert!(deposit_percent < 100, "ERR_INVALID_DEPOSIT_PERCENT");
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.installment_deposit_percent = deposit_percent;
+        room.installment_due_days = due_days_before_check_in;
+    }
+
+    // 分割払いの予約の残額を支払う
+    // 予約者は残額と同額のNEARを添付してコールする
+    #[payable]
+    pub fn pay_balance(&mut self, booking_id: BookingId) {
+        let mut booking = self.internal_booking(booking_id);
+        assert_eq!(
+            env::signer_account_id(),
+            booking.guest_id,
+            "ERR_NOT_BOOKING_GUEST"
+        );
+        assert!(
+            matches!(
+                booking.status,
+                BookingStatus::Pending | BookingStatus::Confirmed
+            ),
+            "ERR_CANNOT_PAY_BALANCE"
+        );
+        assert!(booking.balance_due.0 > 0, "ERR_NO_BALANCE_DUE");
+        assert!(
+            env::block_timestamp() < booking.balance_due_at,
+            "ERR_BALANCE_OVERDUE"
+        );
+        assert_eq!(
+            env::attached_deposit(),
+            booking.balance_due.0,
+            "ERR_DEPOSIT_IS_INCORRECT"
+        );
+
+        self.internal_hold_escrow(booking_id, booking.balance_due.0);
+        self.unpaid_bookings.retain(|id| *id != booking_id);
+        booking.balance_due = U128(0);
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+    }
+
+    // 残額が未払いの予約の一覧を取得する
+    pub fn get_unpaid_bookings(&self) -> Vec<BookingId> {
+        self.unpaid_bookings.clone()
+    }
+}
+
+impl Room {
+    // 予約の合計額のうち、後から支払う残額とその支払い期限を返す
+    // 分割払いを受け付けない部屋や、既に支払い期限を過ぎている場合は全額を前払いする
+    pub(crate) fn installment_plan(
+        &self,
+        total: Balance,
+        check_in_date: &CheckInDate,
+    ) -> (Balance, u64) {
+        if self.installment_deposit_percent == 0 {
+            return (0, 0);
+        }
+        let due_at = self.local_start_timestamp(check_in_date)
+            - (self.installment_due_days as u64 * NANOS_PER_DAY) as i64;
+        if env::block_timestamp() as i64 >= due_at {
+            return (0, 0);
+        }
+        let upfront = total * self.installment_deposit_percent as Balance / 100;
+        (total - upfront, due_at as u64)
+    }
+}
+
+impl Booking {
+    // 未払いの残額を予約者への返金から差し引き、足りない分はオーナーへの支払いから差し引く
+    // 差し引いた後のオーナーへの支払いと予約者への返金を返す
+    pub(crate) fn deduct_balance_due(
+        &self,
+        payment: Balance,
+        refund: Balance,
+    ) -> (Balance, Balance) {
+        let due = self.balance_due.0;
+        let from_refund = due.min(refund);
+        (
+            payment.saturating_sub(due - from_refund),
+            refund - from_refund,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    // 前払いが30%の部屋を、30日後から1泊予約する
+    fn book_with_installments(contract: &mut Contract, room_id: &RoomId) -> BookingId {
+        set_caller(&owner_id(), 0);
+        contract.set_installment_policy(room_id.clone(), 30, 7);
+        book(contract, room_id, today().add_days(30), 1, PRICE * 3 / 10)
+    }
+
+    #[test]
+    fn pays_balance_of_installment_booking() {
+        let (mut contract, room_id) = setup();
+        let booking_id = book_with_installments(&mut contract, &room_id);
+        assert_eq!(
+            contract.internal_booking(booking_id).balance_due.0,
+            PRICE * 7 / 10
+        );
+
+        set_caller(&guest_id(), PRICE * 7 / 10);
+        contract.pay_balance(booking_id);
+        assert_eq!(contract.internal_booking(booking_id).balance_due.0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DEPOSIT_IS_INCORRECT")]
+    fn rejects_partial_balance_payment() {
+        let (mut contract, room_id) = setup();
+        let booking_id = book_with_installments(&mut contract, &room_id);
+        set_caller(&guest_id(), PRICE / 10);
+        contract.pay_balance(booking_id);
+    }
+}
@@ -0,0 +1,230 @@
+// プラットフォームの保険基金
+// 手数料の一部を保険基金に積み立て、チェックイン時に部屋を利用できなかった予約者を補償する
+
+use near_sdk::serde_json::json;
+
+use crate::damage_claim::ClaimStatus;
+use crate::event::emit_event;
+use crate::*;
+
+// 保険基金に積み立てる割合の上限（ベーシスポイント）
+const MAX_INSURANCE_SHARE_BPS: u16 = 10_000;
+
+// 予約者からの、オーナーの不履行（チェックイン時に部屋を利用できない）に対する補償の請求
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct InsuranceClaim {
+    amount: U128,         // 請求額
+    evidence_uri: String, // 不履行の証拠（画像などのURL）
+    status: ClaimStatus,  // 請求の進行状況
+    filed_at: u64,        // 請求した日時
+    paid: U128,           // 精算時に保険基金から支払われた額
+}
+
+#[near_bindgen]
+impl Contract {
+    // 手数料のうち保険基金に積み立てる割合（ベーシスポイント）を変更する
+    // 管理者のみがコールできる
+    pub fn set_insurance_share(&mut self, insurance_share_bps: u16) {
+        self.assert_admin();
+        assert!(
+            insurance_share_bps <= MAX_INSURANCE_SHARE_BPS,
+            "ERR_INVALID_INSURANCE_SHARE"
+        );
+        self.insurance_share_bps = insurance_share_bps;
+    }
+
+    // 手数料のうち保険基金に積み立てる割合を取得する
+    pub fn get_insurance_share(&self) -> u16 {
+        self.insurance_share_bps
+    }
+
+    // 保険基金に積み立てられている額を取得する
+    // `token_id`が`None`の場合はNEARの額を返す
+    pub fn get_insurance_pool(&self, token_id: Option<AccountId>) -> U128 {
+        U128(self.insurance_pool.get(&token_id).copied().unwrap_or(0))
+    }
+
+    // チェックイン日に部屋を利用できなかったことを報告し、保険基金に補償を請求する
+    // チェックイン日からチェックアウト日までの間に、確定済みの予約の予約者のみがコールできる
+    pub fn file_insurance_claim(
+        &mut self,
+        booking_id: BookingId,
+        amount: U128,
+        evidence_uri: String,
+    ) {
+        let mut booking = self.internal_booking(booking_id);
+        assert_eq!(
+            env::signer_account_id(),
+            booking.guest_id,
+            "ERR_NOT_BOOKING_GUEST"
+        );
+        assert_eq!(
+            booking.status,
+            BookingStatus::Confirmed,
+            "ERR_BOOKING_NOT_CONFIRMED"
+        );
+        let today = self.rooms_by_id[&booking.room_id].today();
+        assert!(
+            booking.check_in_date <= today && today < booking.check_out_date,
+            "ERR_NOT_CHECK_IN_PERIOD"
+        );
+        assert!(booking.insurance_claim.is_none(), "ERR_CLAIM_ALREADY_FILED");
+        assert_valid_media_uri(&evidence_uri);
+
+        booking.insurance_claim = Some(InsuranceClaim {
+            amount,
+            evidence_uri,
+            status: ClaimStatus::Filed,
+            filed_at: env::block_timestamp(),
+            paid: U128(0),
+        });
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+    }
+
+    // 補償の請求を裁定する
+    // 不履行と認める場合は、予約をキャンセルして預かり金の全額を返金し、保険基金から`paid`を予約者に支払う
+    // オーナーが担保を預けている場合は、その一部も没収して予約者に支払う
+    // 認めない場合は`verified`を`false`にし、予約はそのまま続く
+    // 不履行と認められるのは、確定済み（チェックイン前）の予約のみ
+    // 管理者のみがコールできる
+    pub fn resolve_insurance_claim(&mut self, booking_id: BookingId, verified: bool, paid: U128) {
+        self.assert_admin();
+        let mut booking = self.internal_booking(booking_id);
+        let claim = booking
+            .insurance_claim
+            .as_mut()
+            .expect("ERR_NOT_FOUND_CLAIM");
+        assert_eq!(claim.status, ClaimStatus::Filed, "ERR_CLAIM_NOT_FILED");
+        assert!(verified || paid.0 == 0, "ERR_INVALID_CLAIM_AMOUNT");
+        assert!(paid.0 <= claim.amount.0, "ERR_INVALID_CLAIM_AMOUNT");
+        assert!(
+            !verified || booking.status == BookingStatus::Confirmed,
+            "ERR_BOOKING_NOT_CONFIRMED"
+        );
+        claim.status = ClaimStatus::Settled;
+        claim.paid = paid;
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+        if !verified {
+            return;
+        }
+
+        let pool = self
+            .insurance_pool
+            .get(&booking.payment_token)
+            .copied()
+            .unwrap_or(0);
+        assert!(paid.0 <= pool, "ERR_INSUFFICIENT_INSURANCE_POOL");
+        if paid.0 == pool {
+            self.insurance_pool.remove(&booking.payment_token);
+        } else {
+            self.insurance_pool
+                .insert(booking.payment_token.clone(), pool - paid.0);
+        }
+
+        let refund = self.escrow_by_booking.get(&booking_id).unwrap_or(0);
+        self.internal_close_booking(&mut booking, BookingStatus::Cancelled);
+        // 宿泊中のまま残ると、宿泊終了の処理で支払い済みの預かり金をオーナーに支払おうとしてしまう
+        let room = self.rooms_by_id.get_mut(&booking.room_id).unwrap();
+        let initial_size = room.serialized_size();
+        room.stays.retain(|stay| *stay != booking_id);
+        self.internal_track_room_storage(&booking.room_id, initial_size);
+        self.internal_release_escrow(booking_id, booking.guest_id.clone(), refund);
+        self.internal_slash_stake(&booking);
+        if paid.0 > 0 {
+            self.internal_send_refund(
+                booking.guest_id.clone(),
+                booking.payment_token.clone(),
+                paid.0,
+            );
+        }
+        emit_event(
+            "insurance_payout",
+            json!({
+                "booking_id": booking_id,
+                "guest_id": booking.guest_id,
+                "token_id": booking.payment_token,
+                "refund": U128(refund),
+                "amount": paid,
+            }),
+        );
+    }
+}
+
+impl Booking {
+    // 裁定されていない補償の請求があるかを確認する
+    pub(crate) fn has_pending_insurance_claim(&self) -> bool {
+        self.insurance_claim
+            .as_ref()
+            .is_some_and(|claim| claim.status == ClaimStatus::Filed)
+    }
+}
+
+impl Contract {
+    // 手数料のうち保険基金に積み立てる分を積み立て、トレジャリーに積み立てる残りの手数料を返す
+    pub(crate) fn internal_fund_insurance(
+        &mut self,
+        token_id: &Option<AccountId>,
+        fee: Balance,
+    ) -> Balance {
+        let share = fee * self.insurance_share_bps as Balance / MAX_INSURANCE_SHARE_BPS as Balance;
+        if share > 0 {
+            *self.insurance_pool.entry(token_id.clone()).or_default() += share;
+        }
+        fee - share
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    // 今日から2泊を予約し、チェックイン日に補償を請求する
+    fn file_claim(contract: &mut Contract, room_id: &RoomId) -> BookingId {
+        let booking_id = book(contract, room_id, today(), 2, 2 * PRICE);
+        set_caller(&guest_id(), 0);
+        contract.file_insurance_claim(
+            booking_id,
+            U128(PRICE),
+            "https://example.com/evidence.png".to_string(),
+        );
+        booking_id
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INSURANCE_CLAIM_PENDING")]
+    fn rejects_check_in_while_claim_is_pending() {
+        let (mut contract, room_id) = setup();
+        let booking_id = file_claim(&mut contract, &room_id);
+        set_caller(&guest_id(), 0);
+        contract.check_in(booking_id);
+    }
+
+    #[test]
+    fn verified_claim_cancels_booking_and_refunds_escrow() {
+        let (mut contract, room_id) = setup();
+        let booking_id = file_claim(&mut contract, &room_id);
+
+        set_caller(&contract_id(), 0);
+        contract.resolve_insurance_claim(booking_id, true, U128(0));
+        let booking = contract.internal_booking(booking_id);
+        assert_eq!(booking.status, BookingStatus::Cancelled);
+        assert!(contract.rooms_by_id[&room_id].stays.is_empty());
+        assert_eq!(transferred_to(&guest_id()), 2 * PRICE);
+    }
+
+    #[test]
+    fn rejected_claim_lets_guest_check_in() {
+        let (mut contract, room_id) = setup();
+        let booking_id = file_claim(&mut contract, &room_id);
+
+        set_caller(&contract_id(), 0);
+        contract.resolve_insurance_claim(booking_id, false, U128(0));
+        set_caller(&guest_id(), 0);
+        contract.check_in(booking_id);
+        assert_eq!(contract.rooms_by_id[&room_id].stays, vec![booking_id]);
+    }
+}
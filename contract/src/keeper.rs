@@ -0,0 +1,104 @@
+// 誰でもコールできる定期実行用のメソッド
+// Croncatなどのタスク実行サービスから定期的にコールすることを想定している
+
+use crate::*;
+
+// 1回のコールで処理する宿泊の最大数
+const MAX_STAYS_PER_CALL: usize = 50;
+
+// 1回のコールで自動送金を処理するオーナーの最大数
+const MAX_PAYOUTS_PER_CALL: usize = 10;
+
+#[near_bindgen]
+impl Contract {
+    // チェックアウト日を過ぎても宿泊中のままの予約を自動的にチェックアウトする
+    // 預かっていた掲出料をオーナーの売上に記録し、保証金を予約者に送金し、処理した予約の数を返す
+    // 超過滞在の違約金を設定している部屋は、オーナーが違約金を請求できるように対象外とする
+    pub fn expire_stays(&mut self) -> u32 {
+        let expired_stays: Vec<Booking> = self
+            .rooms_by_id
+            .values()
+            .filter(|room| room.overstay_penalty_per_day.0 == 0)
+            .flat_map(|room| {
+                let today = room.today();
+                room.stays
+                    .iter()
+                    .map(|booking_id| self.internal_booking(*booking_id))
+                    .filter(move |booking| today >= booking.check_out_date)
+            })
+            .take(MAX_STAYS_PER_CALL)
+            .collect();
+
+        for booking in expired_stays.iter() {
+            let (payment, security_deposit) = self.internal_finish_stay(booking.booking_id);
+            self.internal_return_security_deposit(booking.booking_id, security_deposit);
+            self.internal_pay_owner(booking.booking_id, payment);
+        }
+        expired_stays.len() as u32
+    }
+
+    // 返金日を過ぎた保証金を予約者に返金し、処理した予約の数を返す
+    // 精算されていない損害の請求がある予約は対象外とする
+    pub fn release_security_deposits(&mut self) -> u32 {
+        let now = env::block_timestamp();
+        let releasable: Vec<BookingId> = self
+            .held_security_deposits
+            .iter()
+            .filter(|booking_id| {
+                let booking = self.internal_booking(**booking_id);
+                now >= booking.deposit_release_at && !booking.has_open_claim()
+            })
+            .take(MAX_STAYS_PER_CALL)
+            .cloned()
+            .collect();
+
+        for booking_id in releasable.iter() {
+            self.internal_release_security_deposit(*booking_id);
+        }
+        releasable.len() as u32
+    }
+
+    // 分割払いの残額や承認後に支払う予約リクエストが支払い期限までに支払われなかった予約を、キャンセルポリシーに基づいてキャンセルする
+    // 処理した予約の数を返す
+    pub fn cancel_unpaid_bookings(&mut self) -> u32 {
+        let now = env::block_timestamp();
+        let overdue: Vec<BookingId> = self
+            .unpaid_bookings
+            .iter()
+            .filter(|booking_id| now >= self.internal_booking(**booking_id).balance_due_at)
+            .take(MAX_STAYS_PER_CALL)
+            .cloned()
+            .collect();
+
+        for booking_id in overdue.iter() {
+            self.internal_cancel_booking(*booking_id);
+        }
+        overdue.len() as u32
+    }
+
+    // 自動送金を設定しているオーナーのうち、送金の間隔が経過したオーナーの売上を送金する
+    // 処理したオーナーの数を返す
+    pub fn process_auto_payouts(&mut self) -> u32 {
+        self.internal_process_auto_payouts(MAX_PAYOUTS_PER_CALL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    #[test]
+    fn checks_out_stays_past_check_out_date() {
+        let (mut contract, room_id) = setup();
+        let booking_id = book(&mut contract, &room_id, today(), 1, PRICE);
+        contract.check_in(booking_id);
+
+        // チェックアウト日の前は対象外
+        assert_eq!(contract.expire_stays(), 0);
+
+        set_caller_at(&other_guest_id(), 0, noon(&today().add_days(1)));
+        assert_eq!(contract.expire_stays(), 1);
+        assert!(contract.rooms_by_id[&room_id].stays.is_empty());
+        assert_eq!(earnings_of(&contract, &owner_id()), PRICE);
+    }
+}
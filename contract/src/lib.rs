@@ -6,16 +6,132 @@
  *
  */
 
+// 部屋の登録では設定項目をそのまま引数として受け取る
+#![allow(clippy::too_many_arguments)]
+
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::LookupMap;
 use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{env, near_bindgen, AccountId, Promise};
+use near_sdk::{env, near_bindgen, AccountId, Balance, CryptoHash, Promise, PublicKey};
+
+use std::collections::{HashMap, HashSet};
+
+mod access_code;
+mod admin;
+mod affiliate;
+mod amenity;
+mod auction;
+mod booking;
+mod cancellation;
+mod capture;
+mod check_in_instructions;
+mod cashback;
+mod damage_claim;
+mod credit;
+mod date;
+mod earnings;
+mod escrow;
+mod event;
+mod extra_fee;
+mod ft_payment;
+mod geo;
+mod hold;
+mod house_rules;
+mod housekeeping;
+mod installment;
+mod insurance;
+mod keeper;
+mod localization;
+mod location;
+mod loyalty;
+mod maintenance;
+mod media;
+mod migrate;
+mod no_show;
+mod oracle;
+mod overstay;
+mod payment_deadline;
+mod payout_split;
+mod price_history;
+mod pricing;
+mod pricing_hook;
+mod property;
+mod rate_plan;
+mod promo_code;
+mod referral;
+mod refund;
+mod room;
+mod room_attributes;
+mod room_batch;
+mod room_clone;
+mod room_delete;
+mod room_id;
+mod room_index;
+mod room_limit;
+mod room_share;
+mod room_transfer;
+mod room_type;
+mod room_update;
+mod security_deposit;
+mod staff;
+mod staking;
+mod storage;
+mod stream;
+mod tag;
+mod tax;
+#[cfg(test)]
+mod test_utils;
+mod time_request;
+mod tip;
+mod treasury;
+mod waitlist;
 
-use std::collections::HashMap;
+use amenity::normalize_amenities;
+use auction::Auction;
+use cancellation::{assert_valid_cancellation_policy, CancellationPolicy};
+use cashback::CashbackCampaign;
+use damage_claim::DamageClaim;
+use earnings::AutoPayout;
+use extra_fee::assert_valid_guest_limits;
+use date::Date;
+use hold::Hold;
+use house_rules::assert_valid_house_rules;
+use housekeeping::HousekeepingStatus;
+use insurance::InsuranceClaim;
+use localization::Translation;
+use location::{normalize_location, Location};
+use loyalty::LoyaltyProgram;
+use maintenance::Maintenance;
+use media::{assert_valid_images, assert_valid_media_uri};
+use payout_split::PayoutSplit;
+use price_history::PriceChange;
+use pricing::PriceRule;
+use promo_code::PromoCode;
+use property::Property;
+use rate_plan::RatePlan;
+use staff::{StaffRole, EDIT_ROOM_ROLES, FRONT_DESK_ROLES};
+use staking::HostStake;
+use storage::StorageAccount;
+use tax::TaxRate;
+use time_request::TimeRequest;
+use room::{
+    assert_valid_booking_window, assert_valid_cutoff_hour, assert_valid_stay_limits,
+    assert_valid_timezone,
+};
+use room_attributes::RoomAttributes;
+use room_batch::RoomInput;
+use room_id::slugify;
+use room_index::RoomIndex;
+use room_type::RoomType;
 
 type RoomId = String;
-type CheckInDate = String;
+type CheckInDate = Date;
+type BookingId = u64;
+type PropertyId = u64;
+
+// 予約カレンダーとして一度に取得できる最大日数
+const MAX_CALENDAR_DAYS: usize = 366;
 
 #[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
@@ -23,32 +139,220 @@ pub enum UsageStatus {
     Available,                           // 空き
      Stay { check_in_date: CheckInDate }, // 掲出中
 }
+
+// 予約ごとの進行状況
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum BookingStatus {
+    Pending,    // オーナーの承認待ち
+    Confirmed,  // 予約確定
+    CheckedIn,  // 宿泊中
+    CheckedOut, // 宿泊終了
+    Cancelled,  // キャンセル済み
+    NoShow,     // チェックインされずに終了
+    AwaitingPayment, // オラクルの価格による支払いの確認待ち
+}
+
+// 実際にブロックチェーン上に保存される予約のデータ
+// 1つの予約はチェックイン日からチェックアウト日の前日までの連続した宿泊を表す
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Booking {
+    booking_id: BookingId,         // 予約ID
+    room_id: RoomId,               // 予約した部屋のID
+    guest_id: AccountId,           // 予約者のアカウントID
+    check_in_date: CheckInDate,    // チェックイン日
+    check_out_date: CheckInDate,   // チェックアウト日
+    quantity: u16,                 // 予約した部屋の数（ベッド単位の部屋ではベッドの数）
+    guests: u16,                   // 宿泊する人数
+    payment_token: Option<AccountId>, // 支払いに使われたトークン（NEARの場合は`None`）
+    amount: U128,                  // 支払われた掲出料の合計
+    discount: U128,                // プロモーションコード・ロイヤルティポイントで割り引かれた額
+    fees: U128,                    // 掲出料以外に支払われた追加料金の合計
+    cleaning_fee: U128,            // 追加料金のうち清掃料金
+    extra_guest_fee: U128,         // 追加料金のうち追加人数料金
+    reschedule_fees: U128,         // 予約の変更時にオーナーに支払った変更手数料の合計（キャンセル時も返金しない）
+    security_deposit: U128,        // 預かっている保証金
+    tips: U128,                    // チェックアウト後にオーナーに送られたチップの合計（NEAR）
+    balance_due: U128,             // 分割払いの未払いの残額
+    balance_due_at: u64,           // 分割払いの残額の支払い期限（未払いの残額がない場合は0）
+    stream_started_at: u64,        // ストリーミング払いを始めた日時（ストリーミング払いでない場合は0）
+    streamed: U128,                // ストリーミング払いで既にオーナーに支払った掲出料
+    captured: U128,                // チェックイン時以降に既にオーナーに支払った掲出料と追加料金
+    status: BookingStatus,         // 予約の進行状況
+    created_at: u64,               // 予約した日時（ブロックのタイムスタンプ）
+    updated_at: u64,               // 最後に進行状況が変わった日時
+    time_requests: Vec<TimeRequest>, // アーリーチェックイン・レイトチェックアウトのリクエスト
+    note: String,                  // 予約者からオーナーへのメモ（到着時刻や要望など）
+    deposit_release_at: u64,       // チェックアウト後に保証金を返金する日時（預かっていない場合は0）
+    damage_claim: Option<DamageClaim>, // オーナーからの損害の請求
+    platform_fee_bps: u16,         // 予約時の手数料率（ベーシスポイント）
+    tax_region: String,            // 税率を適用する地域（予約時の部屋の場所）
+    tax_bps: u16,                  // 予約時の地域の税率（ベーシスポイント）
+    affiliate_id: Option<AccountId>, // 予約を紹介したアフィリエイトのアカウントID
+    affiliate_commission_bps: u16, // 予約時のアフィリエイトの成果報酬率（ベーシスポイント）
+    insurance_claim: Option<InsuranceClaim>, // 予約者からのオーナーの不履行に対する補償の請求
+    rate_plan: RatePlan,           // 予約時に選ばれた料金プラン
+    capture_on_check_in: bool,     // チェックインした時点で掲出料と追加料金をオーナーに支払うか
+    guest_public_key: Option<PublicKey>, // 入室コードの暗号化に使う予約者の公開鍵
+    access_code: Option<String>,   // 予約者の公開鍵で暗号化した入室コード
+    encrypted_instructions: Option<String>, // 予約者の公開鍵で暗号化したチェックインの案内
+}
  
  // ショップオーナーが登録した一覧を表示する際に使用
 #[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct RegisteredRoom {
+    room_id: RoomId,
     name: String,
-    image: String,
+    slug: String,
+    property_id: Option<PropertyId>,
+    housekeeping_status: HousekeepingStatus,
+    maintenance: Option<Maintenance>,
+    images: Vec<String>,
+    cover_image_index: u8,
     beds: u8,
+    bookable_by_bed: bool,
+    units: u16,
     description: String,
-    location: String,
+    location: Location,
     price: U128,
+    payment_token: Option<AccountId>,
+    usd_pricing: bool,
     status: UsageStatus,
+    instant_book: bool,
+    min_nights: u16,
+    max_nights: u16,
+    booking_window_days: u16,
+    min_lead_time_hours: u16,
+    same_day_cutoff_hour: u8,
+    timezone_offset_minutes: i16,
+    security_deposit: U128,
+    deposit_release_days: u16,
+    overstay_grace_hours: u16,
+    overstay_penalty_per_day: U128,
+    free_reschedule_hours: u16,
+    reschedule_fee: U128,
+    cancellation_policy: CancellationPolicy,
+    cleaning_fee: U128,
+    extra_guest_fee: U128,
+    included_guests: u16,
+    max_guests: u16,
+    house_rules: String,
+    check_in_hour: u8,
+    check_out_hour: u8,
+    attributes: RoomAttributes,
+    room_type: RoomType,
+    amenities: Vec<String>,
+    tags: Vec<String>,
+    installment_deposit_percent: u8,
+    installment_due_days: u16,
+    streaming_min_nights: u16,
+    payout_splits: Vec<PayoutSplit>,
+    listed: bool,
  }
+
+// 予約可能な部屋の一覧を表示する際に使用
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AvailableRoom {
+    room_id: RoomId,
+    owner_id: AccountId,
+    name: String,
+    slug: String,
+    images: Vec<String>,
+    cover_image_index: u8,
+    beds: u8,
+    description: String,
+    location: Location,
+    price: U128,
+    payment_token: Option<AccountId>,
+    usd_pricing: bool,
+    max_guests: u16,
+    attributes: RoomAttributes,
+    room_type: RoomType,
+    amenities: Vec<String>,
+    tags: Vec<String>,
+}
  
  // 実際にブロックチェーン上に保存されるショップのデータ
  #[derive(BorshDeserialize, BorshSerialize)]
  pub struct Room {
     name: String,        // ショップの名前
     owner_id: AccountId, // オーナーのアカウントID
-    image: String,       // ショップの画像（URL）
+    images: Vec<String>, // ショップの画像（URL）
+    cover_image_index: u8, // 一覧に表示するカバー画像の`images`での番号
     beds: u8,            // 掲出場所（ベッド）の数
+    bookable_by_bed: bool, // ベッド単位で予約を受け付けるか（ホステル形式）
+    units: u16,          // 同じ条件で貸し出す部屋の数
     description: String, // 部屋の説明
-    location: String,    // 施設の場所
+    location: Location,  // 施設の所在地
     price: U128,         // 掲出料
-    status: UsageStatus, // 利用状況
-    booked_info: HashMap<CheckInDate, AccountId>, // 予約データ[掲出日, 掲出者のアカウントID]
+    price_rules: Vec<PriceRule>, // 期間ごとの料金ルール
+    date_prices: HashMap<CheckInDate, U128>, // 日付ごとの掲出料[掲出日, 掲出料]
+    weekly_discount_percent: u8, // 7泊以上の宿泊の割引率（%）
+    monthly_discount_percent: u8, // 28泊以上の宿泊の割引率（%）
+    last_minute_days: u16, // 今日から何日以内の日付を直前割引の対象にするか
+    last_minute_discount_percent: u8, // 直前割引の割引率（%、0の場合は直前割引なし）
+    early_bird_days: u16, // チェックインの何日前より前の予約を早期予約割引の対象にするか
+    early_bird_discount_percent: u8, // 早期予約割引の割引率（%、0の場合は早期予約割引なし）
+    pricing_contract_id: Option<AccountId>, // 掲出料を決める外部の料金設定コントラクト
+    surge_window_days: u16, // 値上げを判定する稼働率の期間（今日からの日数）
+    surge_threshold_percent: u8, // 値上げする稼働率の下限（%）
+    surge_percent: u16,  // 稼働率が下限を超えた場合の値上げ率（%、0の場合は値上げなし）
+    payment_token: Option<AccountId>, // 掲出料を支払うトークンのコントラクト（NEARの場合は`None`）
+    usd_pricing: bool,   // 掲出料・追加料金・保証金を米ドル（小数点以下6桁）で設定しているか
+    instant_book: bool,  // 承認なしで予約を確定するか
+    approval_window_hours: u64, // 予約リクエストを承認できる期間（時間）
+    min_nights: u16,     // 1回の予約の最小宿泊数
+    max_nights: u16,     // 1回の予約の最大宿泊数
+    booking_window_days: u16, // 何日先まで予約を受け付けるか
+    min_lead_time_hours: u16, // チェックインの何時間前まで予約を受け付けるか
+    same_day_cutoff_hour: u8, // 当日予約を受け付ける時刻（現地時間、この時以降は不可）
+    timezone_offset_minutes: i16, // 施設のタイムゾーン（UTCからの差分・分）
+    security_deposit: U128, // 予約時に預かる保証金
+    deposit_release_days: u16, // チェックアウトから保証金を返金するまでの日数
+    overstay_grace_hours: u16, // チェックアウト日から超過滞在とみなすまでの猶予時間
+    overstay_penalty_per_day: U128, // 超過滞在1日あたりの違約金
+    free_reschedule_hours: u16, // チェックインの何時間前まで無料で予約を変更できるか
+    reschedule_fee: U128, // 無料で変更できる期限を過ぎた後の変更手数料
+    cancellation_policy: CancellationPolicy, // キャンセル時の返金ルール
+    cleaning_fee: U128,  // 1回の予約ごとの清掃料金
+    extra_guest_fee: U128, // 含まれる人数を超える1人・1泊あたりの追加人数料金
+    included_guests: u16, // 掲出料に含まれる、部屋（ベッド）1つあたりの人数
+    max_guests: u16, // 部屋（ベッド）1つあたりの最大人数（0の場合は制限なし）
+    house_rules: String, // ハウスルール
+    check_in_hour: u8, // チェックインの時刻（現地時間）
+    check_out_hour: u8, // チェックアウトの時刻（現地時間）
+    attributes: RoomAttributes, // ペット・喫煙・バリアフリーなどの属性
+    room_type: RoomType, // 部屋の種類
+    amenities: Vec<String>, // 部屋の設備（重複のない、並べ替えた一覧）
+    translations: HashMap<String, Translation>, // 名前と説明の翻訳[言語コード, 翻訳]
+    tags: Vec<String>, // テーマ別の一覧に使用するタグ（小文字にそろえた、重複のない一覧）
+    installment_deposit_percent: u8, // 分割払いで予約時に前払いする割合（%、0の場合は分割払いなし）
+    installment_due_days: u16, // 分割払いの残額をチェックインの何日前までに支払うか
+    streaming_min_nights: u16, // 掲出料をストリーミング払いにする最小宿泊数（0の場合はストリーミング払いなし）
+    payout_splits: Vec<PayoutSplit>, // 売上の受取人と割合（空の場合はオーナーが全額を受け取る）
+    booked_info: HashMap<CheckInDate, Vec<BookingId>>, // 有効な予約の宿泊日[掲出日, 予約IDの一覧]
+    booked_count: HashMap<CheckInDate, u16>, // 宿泊日ごとの予約済みの数[掲出日, 予約済みの数]
+    stays: Vec<BookingId>, // 宿泊中の予約ID
+    holds: HashMap<CheckInDate, Vec<Hold>>, // 仮押さえ[掲出日, 仮押さえのデータ]
+    waitlists: HashMap<CheckInDate, Vec<AccountId>>, // キャンセル待ち[掲出日, 登録順のアカウントID]
+    blocked_dates: HashSet<CheckInDate>, // オーナーが予約を停止している日付
+    promo_codes: HashMap<CryptoHash, PromoCode>, // プロモーションコード[コードのハッシュ, 割引の内容]
+    auctions: HashMap<CheckInDate, Auction>, // オークション[チェックイン日, オークションのデータ]
+    private_rates: HashMap<AccountId, U128>, // 特定のアカウントだけに適用する1泊あたりの掲出料[アカウントID, 掲出料]
+    non_refundable_discount_percent: u8, // 返金不可の料金プランの標準料金からの割引率（%、0の場合は提供しない）
+    payment_window_hours: u16, // 予約リクエストの承認後に支払うまでの期限（時間、0の場合はリクエスト時に支払う）
+    capture_on_check_in: bool, // 予約の掲出料と追加料金を、チェックインした時点でオーナーに支払うか
+    listed: bool, // 部屋を掲載しているか（掲載していない部屋は検索や新しい予約の対象外）
+    slug: String, // 部屋の名前から生成した読みやすい識別子
+    pending_owner_id: Option<AccountId>, // 所有権の移転先として指定されたアカウントID
+    property_id: Option<PropertyId>, // 部屋が所属する施設のID
+    housekeeping_status: HousekeepingStatus, // 部屋の清掃状況
+    maintenance: Option<Maintenance>, // メンテナンス期間
+    shares: HashMap<AccountId, u32>, // 部屋の持分[保有者のアカウントID, 保有数]（空の場合はオーナーが全てを所有する）
+    storage_bytes: u64,  // 部屋の登録で使用したストレージのバイト数
  }
 
  #[near_bindgen]
@@ -56,6 +360,47 @@ pub struct RegisteredRoom {
  pub struct Contract {
      rooms_per_owner: LookupMap<AccountId, Vec<RoomId>>,
      rooms_by_id: HashMap<RoomId, Room>,
+     bookings_by_id: LookupMap<BookingId, Booking>,
+     bookings_per_guest: LookupMap<AccountId, Vec<BookingId>>,
+     bookings_per_room: LookupMap<RoomId, Vec<BookingId>>,
+     next_booking_id: BookingId,
+     next_room_id: u64,
+     escrow_by_booking: LookupMap<BookingId, Balance>,
+     held_security_deposits: Vec<BookingId>,
+     unpaid_bookings: Vec<BookingId>,
+     admin_id: AccountId,
+     payment_tokens: HashSet<AccountId>,
+     oracle_id: Option<AccountId>,
+     oracle_asset_id: String,
+     platform_fee_bps: u16,
+     treasury_id: AccountId,
+     treasury_balances: HashMap<Option<AccountId>, Balance>,
+     earnings_by_owner: LookupMap<AccountId, HashMap<Option<AccountId>, Balance>>,
+     auto_payouts: HashMap<AccountId, AutoPayout>,
+     claimable_refunds: LookupMap<AccountId, HashMap<Option<AccountId>, Balance>>,
+     storage_accounts: LookupMap<AccountId, StorageAccount>,
+     credits: LookupMap<AccountId, Balance>,
+     gift_codes: LookupMap<CryptoHash, Balance>,
+     tax_rates: HashMap<String, TaxRate>,
+     tax_collected: HashMap<String, HashMap<Option<AccountId>, Balance>>,
+     loyalty: LoyaltyProgram,
+     referrers: LookupMap<AccountId, AccountId>,
+     referral_reward: Balance,
+     affiliate_commission_bps: u16,
+     cashback_campaign: Option<CashbackCampaign>,
+     insurance_share_bps: u16,
+     insurance_pool: HashMap<Option<AccountId>, Balance>,
+     host_stakes: LookupMap<AccountId, HostStake>,
+     slash_bps: u16,
+     max_rooms_per_owner: u32,
+     verified_operators: HashSet<AccountId>,
+     price_history: LookupMap<RoomId, Vec<PriceChange>>,
+     properties: LookupMap<PropertyId, Property>,
+     properties_per_owner: LookupMap<AccountId, Vec<PropertyId>>,
+     next_property_id: PropertyId,
+     rooms_by_tag: RoomIndex,
+     rooms_by_city: RoomIndex,
+     rooms_by_geohash: RoomIndex,
 }
 
 impl Default for Contract {
@@ -63,65 +408,145 @@ impl Default for Contract {
          Self {
             rooms_per_owner: LookupMap::new(b"m"),
             rooms_by_id: HashMap::new(),
+            bookings_by_id: LookupMap::new(b"b"),
+            bookings_per_guest: LookupMap::new(b"g"),
+            bookings_per_room: LookupMap::new(b"r"),
+            next_booking_id: 0,
+            next_room_id: 0,
+            escrow_by_booking: LookupMap::new(b"e"),
+            held_security_deposits: Vec::new(),
+            unpaid_bookings: Vec::new(),
+            admin_id: env::current_account_id(),
+            payment_tokens: HashSet::new(),
+            oracle_id: None,
+            oracle_asset_id: "wrap.near".to_string(),
+            platform_fee_bps: 0,
+            treasury_id: env::current_account_id(),
+            treasury_balances: HashMap::new(),
+            earnings_by_owner: LookupMap::new(b"h"),
+            auto_payouts: HashMap::new(),
+            claimable_refunds: LookupMap::new(b"c"),
+            storage_accounts: LookupMap::new(b"s"),
+            credits: LookupMap::new(b"k"),
+            gift_codes: LookupMap::new(b"q"),
+            tax_rates: HashMap::new(),
+            tax_collected: HashMap::new(),
+            loyalty: LoyaltyProgram::new(),
+            referrers: LookupMap::new(b"f"),
+            referral_reward: 0,
+            affiliate_commission_bps: 0,
+            cashback_campaign: None,
+            insurance_share_bps: 0,
+            insurance_pool: HashMap::new(),
+            host_stakes: LookupMap::new(b"t"),
+            slash_bps: 0,
+            max_rooms_per_owner: 0,
+            verified_operators: HashSet::new(),
+            price_history: LookupMap::new(b"p"),
+            properties: LookupMap::new(b"o"),
+            properties_per_owner: LookupMap::new(b"w"),
+            next_property_id: 0,
+            rooms_by_tag: LookupMap::new(b"a"),
+            rooms_by_city: LookupMap::new(b"y"),
+            rooms_by_geohash: LookupMap::new(b"u"),
          }
      }
  }
 
  #[near_bindgen]
  impl Contract {
+     #[payable]
      pub fn add_room_to_owner(
          &mut self,
          name: String,
-         image: String,
+         images: Vec<String>,
+         cover_image_index: u8,
          beds: u8,
+         bookable_by_bed: bool,
+         units: u16,
          description: String,
-         location: String,
+         location: Location,
          price: U128,
-     ) 
+         payment_token: Option<AccountId>,
+         usd_pricing: bool,
+         instant_book: bool,
+         approval_window_hours: u64,
+         min_nights: u16,
+         max_nights: u16,
+         booking_window_days: u16,
+         min_lead_time_hours: u16,
+         same_day_cutoff_hour: u8,
+         timezone_offset_minutes: i16,
+         security_deposit: U128,
+         deposit_release_days: u16,
+         overstay_grace_hours: u16,
+         overstay_penalty_per_day: U128,
+         free_reschedule_hours: u16,
+         reschedule_fee: U128,
+         cancellation_policy: CancellationPolicy,
+         cleaning_fee: U128,
+         extra_guest_fee: U128,
+         included_guests: u16,
+         max_guests: u16,
+         room_type: RoomType,
+         amenities: Vec<String>,
+     ) -> RoomId
      {
          // 関数をコールしたアカウントIDを取得
          let owner_id = env::signer_account_id();
  
-         // 部屋のIDをオーナーのアカウントIDと部屋の名前で生成
-         let room_id = format!("{}{}", owner_id, name);
- 
-         // Room構造体を、データを入れて生成
-         let new_room = Room {
-             owner_id: owner_id.clone(),
-             name,
-             image,
-             beds,
-             description,
-             location,
-             price,
-             status: UsageStatus::Available,
-             booked_info: HashMap::new(),
-         };
- 
-         // 部屋のデータを`RoomId`と紐付けて保存
-         self.rooms_by_id.insert(room_id.clone(), new_room);
- 
-         // オーナーのアカウントIDと`RoomId`のVectorを紐付けて保存
-         match self.rooms_per_owner.get(&owner_id) {
-             // オーナーが既に別の部屋を登録済みの時
-             Some(mut rooms) => {
-                 rooms.push(room_id);
-                 self.rooms_per_owner.insert(&owner_id, &rooms);
-             }
-             // オーナーが初めて部屋を登録する時
-             None => {
-                 // `room_id`を初期値にVectorを生成する
-                 let new_rooms = vec![room_id];
-                 self.rooms_per_owner.insert(&owner_id, &new_rooms);
-             }
-         }
+         // 部屋のIDを、オーナーのアカウントIDと連番で生成
+         let room_id = self.internal_next_room_id(&owner_id);
+         let new_room = self.internal_new_room(
+             owner_id,
+             RoomInput {
+                 name,
+                 images,
+                 cover_image_index,
+                 beds,
+                 bookable_by_bed,
+                 units,
+                 description,
+                 location,
+                 price,
+                 payment_token,
+                 usd_pricing,
+                 instant_book,
+                 approval_window_hours,
+                 min_nights,
+                 max_nights,
+                 booking_window_days,
+                 min_lead_time_hours,
+                 same_day_cutoff_hour,
+                 timezone_offset_minutes,
+                 security_deposit,
+                 deposit_release_days,
+                 overstay_grace_hours,
+                 overstay_penalty_per_day,
+                 free_reschedule_hours,
+                 reschedule_fee,
+                 cancellation_policy,
+                 cleaning_fee,
+                 extra_guest_fee,
+                 included_guests,
+                 max_guests,
+                 room_type,
+                 amenities,
+             },
+         );
+
+         // 部屋のデータを保存し、使用したストレージの代金を支払う
+         self.internal_register_room(room_id.clone(), new_room);
+         room_id
      }
-     // `room_id`が既に存在するかを確認する
+     // オーナーが同じ名前の部屋を既に登録しているかを確認する
      // 同じ部屋名を複数所有することは想定しないため、`add_room_to_owner`を実行する前にコールされる
      pub fn exists(&self, owner_id: AccountId, room_name: String) -> bool {
-         let room_id = format!("{}{}", owner_id, room_name);
-
-         self.rooms_by_id.contains_key(&room_id)
+         self.rooms_per_owner
+             .get(&owner_id)
+             .unwrap_or_default()
+             .iter()
+             .any(|room_id| self.rooms_by_id[room_id].name == room_name)
      }
 
      pub fn get_rooms_registered_by_owner(&self, owner_id: AccountId) -> Vec<RegisteredRoom> {
@@ -136,25 +561,63 @@ impl Default for Contract {
                  // `room_id`をkeyとして、マップされている`Room`構造体のデータを取得
                  let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
  
-                 // 部屋のステータスを複製する
-                 let status = match &room.status {
-                 // ステータスが`Available`の時
-                        UsageStatus::Available => UsageStatus::Available,
-                        // ステータスが`Stay`の時
-                        UsageStatus::Stay { check_in_date } => UsageStatus::Stay {
-                            check_in_date: check_in_date.clone(),
-                        },
-                    };
+                 // 宿泊中の予約がある時は、最初にチェックインした予約のチェックイン日を表示する
+                 let status = match room.stays.first() {
+                     Some(booking_id) => UsageStatus::Stay {
+                         check_in_date: self.bookings_by_id.get(booking_id).unwrap().check_in_date,
+                     },
+                     None => UsageStatus::Available,
+                 };
  
                  // 取得した部屋のデータをもとに、`RegisteredRoom`構造体を生成
                  let registered_room = RegisteredRoom {
+                     room_id: room_id.clone(),
                      name: room.name.clone(),
+                     slug: room.slug.clone(),
+                     property_id: room.property_id,
+                     housekeeping_status: room.housekeeping_status,
+                     maintenance: room.maintenance.clone(),
                      beds: room.beds,
-                     image: room.image.clone(),
+                     bookable_by_bed: room.bookable_by_bed,
+                     units: room.units,
+                     images: room.images.clone(),
+                     cover_image_index: room.cover_image_index,
                      description: room.description.clone(),
                      location: room.location.clone(),
                      price: room.price,
+                     payment_token: room.payment_token.clone(),
+                     usd_pricing: room.usd_pricing,
                      status,
+                     instant_book: room.instant_book,
+                     min_nights: room.min_nights,
+                     max_nights: room.max_nights,
+                     booking_window_days: room.booking_window_days,
+                     min_lead_time_hours: room.min_lead_time_hours,
+                     same_day_cutoff_hour: room.same_day_cutoff_hour,
+                     timezone_offset_minutes: room.timezone_offset_minutes,
+                     security_deposit: room.security_deposit,
+                     deposit_release_days: room.deposit_release_days,
+                     overstay_grace_hours: room.overstay_grace_hours,
+                     overstay_penalty_per_day: room.overstay_penalty_per_day,
+                     free_reschedule_hours: room.free_reschedule_hours,
+                     reschedule_fee: room.reschedule_fee,
+                     cancellation_policy: room.cancellation_policy.clone(),
+                     cleaning_fee: room.cleaning_fee,
+                     extra_guest_fee: room.extra_guest_fee,
+                     included_guests: room.included_guests,
+                     max_guests: room.max_guests,
+                     house_rules: room.house_rules.clone(),
+                     check_in_hour: room.check_in_hour,
+                     check_out_hour: room.check_out_hour,
+                     attributes: room.attributes,
+                     room_type: room.room_type,
+                     amenities: room.amenities.clone(),
+                     tags: room.tags.clone(),
+                     installment_deposit_percent: room.installment_deposit_percent,
+                     installment_due_days: room.installment_due_days,
+                     streaming_min_nights: room.streaming_min_nights,
+                     payout_splits: room.payout_splits.clone(),
+                     listed: room.listed,
                  };
                  // Vectorに追加
                  registered_rooms.push(registered_room);
@@ -165,4 +628,48 @@ impl Default for Contract {
      }
  }
 
+     // 指定された日付に予約可能な部屋を全て取得する
+     pub fn get_available_rooms(
+         &self,
+         check_in_date: CheckInDate,
+         lang: Option<String>,
+     ) -> Vec<AvailableRoom> {
+         check_in_date.assert_not_past(&date::earliest_today());
+         let mut available_rooms = vec![];
+
+         for (room_id, room) in self.rooms_by_id.iter() {
+             // 施設のタイムゾーンで過去の日付になる部屋は除外する
+             if check_in_date < room.today() {
+                 continue;
+             }
+             // 満室、または仮押さえで空きがない部屋は除外する
+             if !room.is_available(&check_in_date) {
+                 continue;
+             }
+
+             available_rooms.push(room.to_available_room(room_id, lang.as_deref()));
+         }
+         available_rooms
+     }
+
+     // 指定された期間（`to_date`の前日まで）の日ごとの予約可否を取得する
+     pub fn get_room_availability(
+         &self,
+         room_id: RoomId,
+         from_date: CheckInDate,
+         to_date: CheckInDate,
+     ) -> HashMap<CheckInDate, bool> {
+         let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+         from_date.assert_not_past(&room.today());
+         let dates = date::nights(&from_date, &to_date);
+         assert!(dates.len() <= MAX_CALENDAR_DAYS, "ERR_DATE_RANGE_TOO_LONG");
+
+         dates
+             .into_iter()
+             .map(|date| {
+                 (date, room.is_available(&date))
+             })
+             .collect()
+     }
+
  }
\ No newline at end of file
@@ -0,0 +1,110 @@
+// 部屋の名前と説明の翻訳
+// 部屋の`name`と`description`を既定の言語とし、言語コード（`ja`、`en`、`en-us`など）ごとに翻訳を登録できる
+// 一覧の取得時に言語を指定すると翻訳を返し、翻訳がない場合は既定の言語で返す
+
+use crate::*;
+
+// 1つの部屋に登録できる翻訳の言語の最大数
+const MAX_TRANSLATIONS: usize = 10;
+// 言語コードの最大の長さ
+const MAX_LANG_LENGTH: usize = 16;
+
+// 1つの言語の翻訳
+// 翻訳されていない項目は既定の言語で表示する
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Translation {
+    name: Option<String>,
+    description: Option<String>,
+}
+
+#[near_bindgen]
+impl Contract {
+    // 指定された言語の部屋の名前と説明の翻訳を登録する
+    // `name`と`description`の両方を省略すると、その言語の翻訳を削除する
+    // 部屋のデータが増えた分のストレージの代金は、預けている代金と添付されたNEARから支払う
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn set_translation(
+        &mut self,
+        room_id: RoomId,
+        lang: String,
+        name: Option<String>,
+        description: Option<String>,
+    ) {
+        let lang = normalize_lang(&lang);
+        assert!(name != Some(String::new()), "ERR_INVALID_NAME");
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        if name.is_none() && description.is_none() {
+            room.translations.remove(&lang);
+        } else {
+            room.translations
+                .insert(lang, Translation { name, description });
+        }
+        assert!(
+            room.translations.len() <= MAX_TRANSLATIONS,
+            "ERR_TOO_MANY_TRANSLATIONS"
+        );
+
+        // 部屋のデータのサイズの変化に応じて、使用しているストレージを調整する
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // 部屋に登録されている翻訳を、言語コードごとに取得する
+    pub fn get_translations(&self, room_id: RoomId) -> HashMap<String, Translation> {
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        room.translations.clone()
+    }
+}
+
+impl Room {
+    // 指定された言語の部屋の名前を返す
+    pub(crate) fn localized_name(&self, lang: Option<&str>) -> String {
+        self.translated(lang, |translation| translation.name.as_ref())
+            .unwrap_or(&self.name)
+            .clone()
+    }
+
+    // 指定された言語の部屋の説明を返す
+    pub(crate) fn localized_description(&self, lang: Option<&str>) -> String {
+        self.translated(lang, |translation| translation.description.as_ref())
+            .unwrap_or(&self.description)
+            .clone()
+    }
+
+    // 指定された言語（`en-us`の場合は`en`も）の翻訳から項目を探す
+    fn translated<'a>(
+        &'a self,
+        lang: Option<&str>,
+        field: impl Fn(&'a Translation) -> Option<&'a String>,
+    ) -> Option<&'a String> {
+        let lang = lang?.to_ascii_lowercase();
+        let primary = lang.split('-').next().unwrap_or_default();
+        [lang.as_str(), primary]
+            .iter()
+            .find_map(|lang| self.translations.get(*lang).and_then(&field))
+    }
+}
+
+// 言語コードが、2〜3文字の言語と、任意の地域などのサブタグ（`-`で区切った英数字）であるかを確認し、小文字にそろえる
+fn normalize_lang(lang: &str) -> String {
+    let lang = lang.to_ascii_lowercase();
+    let mut subtags = lang.split('-');
+    let primary = subtags.next().unwrap_or_default();
+    assert!(
+        lang.len() <= MAX_LANG_LENGTH
+            && (2..=3).contains(&primary.len())
+            && primary.chars().all(|c| c.is_ascii_lowercase())
+            && subtags.all(|subtag| {
+                !subtag.is_empty() && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+            }),
+        "ERR_INVALID_LANG"
+    );
+    lang
+}
@@ -0,0 +1,117 @@
+// 施設の所在地
+// 国・都市・住所・郵便番号と、任意の緯度・経度に分けて保存し、都市ごとの部屋のIDの索引から地域別の一覧を取得できるようにする
+
+use crate::geo::assert_valid_location_coordinates;
+use crate::room_index::update_room_index;
+use crate::*;
+
+// 都市の名前の最大の長さ
+const MAX_CITY_LENGTH: usize = 64;
+// 住所の最大の長さ
+const MAX_ADDRESS_LINE_LENGTH: usize = 200;
+// 郵便番号の最大の長さ
+const MAX_POSTAL_CODE_LENGTH: usize = 16;
+
+// 施設の所在地
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Location {
+    pub country: String,      // 国（ISO 3166-1の2文字のコード）
+    pub city: String,         // 都市
+    pub address_line: String, // 都市以降の住所
+    pub postal_code: String,  // 郵便番号
+    pub lat: Option<i32>,     // 緯度（10^-6度単位）
+    pub lng: Option<i32>,     // 経度（10^-6度単位）
+}
+
+#[near_bindgen]
+impl Contract {
+    // 指定された都市にある、掲載中の部屋を取得する
+    // 都市の名前は大文字と小文字を区別しない
+    // `from_index`番目から最大`limit`件を返す
+    pub fn get_rooms_by_city(
+        &self,
+        city: String,
+        from_index: u64,
+        limit: u64,
+        lang: Option<String>,
+    ) -> Vec<AvailableRoom> {
+        self.internal_indexed_rooms(
+            &self.rooms_by_city,
+            &city_key(&city),
+            from_index,
+            limit,
+            lang.as_deref(),
+        )
+    }
+}
+
+impl Contract {
+    // 所在地の変更に合わせて、都市とジオハッシュの索引を更新する
+    pub(crate) fn internal_update_location_index(
+        &mut self,
+        room_id: &RoomId,
+        old_location: Option<&Location>,
+        new_location: Option<&Location>,
+    ) {
+        update_room_index(
+            &mut self.rooms_by_city,
+            b"z",
+            room_id,
+            &old_location.map(Location::city_keys).unwrap_or_default(),
+            &new_location.map(Location::city_keys).unwrap_or_default(),
+        );
+        update_room_index(
+            &mut self.rooms_by_geohash,
+            b"v",
+            room_id,
+            &old_location.map(Location::geohash_keys).unwrap_or_default(),
+            &new_location.map(Location::geohash_keys).unwrap_or_default(),
+        );
+    }
+}
+
+impl Location {
+    // 都市の索引のキーを返す
+    // 都市が登録されていない所在地（旧バージョンから移行した部屋など）は索引に含めない
+    fn city_keys(&self) -> Vec<String> {
+        let key = city_key(&self.city);
+        if key.is_empty() {
+            vec![]
+        } else {
+            vec![key]
+        }
+    }
+}
+
+// 国のコードを大文字にそろえ、各項目の形式と長さを確認する
+pub(crate) fn normalize_location(mut location: Location) -> Location {
+    location.country = location.country.to_ascii_uppercase();
+    assert!(
+        location.country.len() == 2 && location.country.chars().all(|c| c.is_ascii_uppercase()),
+        "ERR_INVALID_COUNTRY"
+    );
+    assert!(
+        !city_key(&location.city).is_empty() && location.city.chars().count() <= MAX_CITY_LENGTH,
+        "ERR_INVALID_CITY"
+    );
+    assert!(
+        location.address_line.chars().count() <= MAX_ADDRESS_LINE_LENGTH,
+        "ERR_INVALID_ADDRESS_LINE"
+    );
+    assert!(
+        location.postal_code.len() <= MAX_POSTAL_CODE_LENGTH
+            && location
+                .postal_code
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | ' ')),
+        "ERR_INVALID_POSTAL_CODE"
+    );
+    assert_valid_location_coordinates(location.lat, location.lng);
+    location
+}
+
+// 都市の名前の前後の空白を除き、小文字にそろえる
+fn city_key(city: &str) -> String {
+    city.trim().to_lowercase()
+}
@@ -0,0 +1,118 @@
+// 予約者のロイヤルティポイント
+// NEARで支払った宿泊の掲出料に応じてポイントが貯まり、以降の予約の掲出料の割引に使える
+
+use near_sdk::serde_json::json;
+
+use crate::event::emit_event;
+use crate::*;
+
+// ポイントの獲得・利用の割合
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LoyaltyRatios {
+    earn_bps: u16, // 支払った掲出料（yoctoNEAR）に対して獲得するポイントの割合（ベーシスポイント、0の場合は獲得なし）
+    redeem_bps: u16, // 1ポイントで割り引く額（yoctoNEAR）の割合（ベーシスポイント、0の場合は利用不可）
+}
+
+// ポイントの台帳と設定
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct LoyaltyProgram {
+    ratios: LoyaltyRatios,
+    points: LookupMap<AccountId, u128>,
+}
+
+#[near_bindgen]
+impl Contract {
+    // ポイントの獲得・利用の割合を変更する
+    // 管理者のみがコールできる
+    pub fn set_loyalty_ratios(&mut self, earn_bps: u16, redeem_bps: u16) {
+        self.assert_admin();
+        assert!(earn_bps <= 10_000, "ERR_INVALID_EARN_RATIO");
+        self.loyalty.ratios = LoyaltyRatios {
+            earn_bps,
+            redeem_bps,
+        };
+    }
+
+    // ポイントの獲得・利用の割合を取得する
+    pub fn get_loyalty_ratios(&self) -> LoyaltyRatios {
+        self.loyalty.ratios.clone()
+    }
+
+    // アカウントが持っているポイントを取得する
+    pub fn get_loyalty_points(&self, account_id: AccountId) -> U128 {
+        U128(self.loyalty.points_of(&account_id))
+    }
+}
+
+impl LoyaltyProgram {
+    pub(crate) fn new() -> Self {
+        Self {
+            ratios: LoyaltyRatios::default(),
+            points: LookupMap::new(b"l"),
+        }
+    }
+
+    // 宿泊を終えた予約者に、支払った掲出料に応じたポイントを付与する
+    pub(crate) fn earn(&mut self, account_id: &AccountId, booking_id: BookingId, spent: Balance) {
+        let earned = spent * self.ratios.earn_bps as u128 / 10_000;
+        if earned == 0 {
+            return;
+        }
+        let points = self.points_of(account_id) + earned;
+        self.points.insert(account_id, &points);
+        emit_event(
+            "loyalty_points_earned",
+            json!({
+                "account_id": account_id,
+                "booking_id": booking_id,
+                "points": U128(earned),
+                "balance": U128(points),
+            }),
+        );
+    }
+
+    // 指定された上限までのポイントを使い、掲出料から割り引く額を返す
+    // 掲出料を超えて割り引く分のポイントは使わない
+    pub(crate) fn redeem(
+        &mut self,
+        account_id: &AccountId,
+        max_points: u128,
+        amount: Balance,
+    ) -> Balance {
+        assert!(
+            self.ratios.redeem_bps > 0,
+            "ERR_LOYALTY_REDEMPTION_DISABLED"
+        );
+        let balance = self.points_of(account_id);
+        assert!(max_points <= balance, "ERR_INSUFFICIENT_LOYALTY_POINTS");
+        let redeem_bps = self.ratios.redeem_bps as u128;
+
+        let discount = (max_points * redeem_bps / 10_000).min(amount);
+        // 端数は切り上げ、予約者に有利にならないようにする
+        let used = (discount * 10_000).div_ceil(redeem_bps).min(max_points);
+        if used == 0 {
+            return 0;
+        }
+        let points = balance - used;
+        if points == 0 {
+            self.points.remove(account_id);
+        } else {
+            self.points.insert(account_id, &points);
+        }
+        emit_event(
+            "loyalty_points_redeemed",
+            json!({
+                "account_id": account_id,
+                "points": U128(used),
+                "discount": U128(discount),
+                "balance": U128(points),
+            }),
+        );
+        discount
+    }
+
+    fn points_of(&self, account_id: &AccountId) -> u128 {
+        self.points.get(account_id).unwrap_or(0)
+    }
+}
@@ -0,0 +1,221 @@
+// 部屋のメンテナンス期間
+// メンテナンス期間中は新しい予約を受け付けず、期間と重なる既存の予約はオーナーが全額返金でキャンセルできる
+// 確定済みの予約をキャンセルした場合は、オーナー都合のキャンセルと同じく担保を没収する
+
+use near_sdk::serde_json::json;
+
+use crate::event::emit_event;
+use crate::*;
+
+// メンテナンスの理由の最大の文字数
+const MAX_REASON_LENGTH: usize = 200;
+
+// 部屋のメンテナンス期間
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Maintenance {
+    from_date: CheckInDate,               // メンテナンスの開始日
+    to_date: CheckInDate,                 // メンテナンスの終了日（この日は含まない）
+    reason: String,                       // メンテナンスの理由
+    conflicting_bookings: Vec<BookingId>, // 期間と重なり、キャンセルが必要な予約のID
+}
+
+#[near_bindgen]
+impl Contract {
+    // 指定された期間（`to_date`の前日まで）をメンテナンス期間とし、期間中の新しい予約を停止する
+    // 期間と重なる承認待ち・確定済みの予約は`cancel_for_maintenance`でキャンセルする予約として記録し、予約IDを返す
+    // 既にメンテナンス期間が設定されている場合は置き換える
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn set_maintenance(
+        &mut self,
+        room_id: RoomId,
+        from_date: CheckInDate,
+        to_date: CheckInDate,
+        reason: String,
+    ) -> Vec<BookingId> {
+        assert!(
+            reason.chars().count() <= MAX_REASON_LENGTH,
+            "ERR_REASON_TOO_LONG"
+        );
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        from_date.assert_not_past(&room.today());
+        let nights = date::nights(&from_date, &to_date);
+        assert!(
+            !nights.is_empty() && nights.len() <= MAX_CALENDAR_DAYS,
+            "ERR_INVALID_DATE_RANGE"
+        );
+
+        let mut conflicting_bookings: Vec<BookingId> = nights
+            .iter()
+            .filter_map(|night| room.booked_info.get(night))
+            .flatten()
+            .copied()
+            .collect();
+        conflicting_bookings.sort();
+        conflicting_bookings.dedup();
+        conflicting_bookings.retain(|booking_id| {
+            matches!(
+                self.internal_booking(*booking_id).status,
+                BookingStatus::Pending | BookingStatus::Confirmed
+            )
+        });
+
+        emit_event(
+            "maintenance_scheduled",
+            json!({
+                "room_id": room_id,
+                "from_date": from_date,
+                "to_date": to_date,
+                "reason": reason,
+                "conflicting_bookings": conflicting_bookings,
+            }),
+        );
+        self.rooms_by_id.get_mut(&room_id).unwrap().maintenance = Some(Maintenance {
+            from_date,
+            to_date,
+            reason,
+            conflicting_bookings: conflicting_bookings.clone(),
+        });
+        self.internal_update_room_storage(&room_id, initial_usage);
+        conflicting_bookings
+    }
+
+    // メンテナンス期間を取り消し、再び予約を受け付ける
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn clear_maintenance(&mut self, room_id: RoomId) {
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.maintenance = None;
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // メンテナンス期間と重なる予約をキャンセルし、預かり金の全額を予約者に返金する
+    // 確定済みの予約の場合は、`host_cancel_booking`と同じくオーナーの担保の一部を没収して予約者に支払う
+    // 部屋のオーナーのみがコールできる
+    pub fn cancel_for_maintenance(&mut self, booking_id: BookingId) -> Promise {
+        let mut booking = self.internal_booking(booking_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        let maintenance = room.maintenance.as_mut().expect("ERR_NO_MAINTENANCE");
+        assert!(
+            maintenance.conflicting_bookings.contains(&booking_id),
+            "ERR_NOT_CONFLICTING_BOOKING"
+        );
+        assert!(
+            matches!(
+                booking.status,
+                BookingStatus::Pending | BookingStatus::Confirmed
+            ),
+            "ERR_CANNOT_CANCEL_BOOKING"
+        );
+        maintenance
+            .conflicting_bookings
+            .retain(|id| *id != booking_id);
+
+        let confirmed = booking.status == BookingStatus::Confirmed;
+        let refund = self.escrow_by_booking.get(&booking_id).unwrap_or(0);
+        self.internal_close_booking(&mut booking, BookingStatus::Cancelled);
+        if confirmed {
+            self.internal_slash_stake(&booking);
+        }
+        self.internal_release_escrow(booking_id, booking.guest_id, refund)
+    }
+
+    // 部屋のメンテナンス期間を取得する
+    pub fn get_maintenance(&self, room_id: RoomId) -> Option<Maintenance> {
+        self.rooms_by_id
+            .get(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM")
+            .maintenance
+            .clone()
+    }
+}
+
+impl Room {
+    // 指定された日付がメンテナンス期間に含まれるかを確認する
+    pub(crate) fn is_under_maintenance(&self, date: &CheckInDate) -> bool {
+        self.maintenance.as_ref().is_some_and(|maintenance| {
+            maintenance.from_date <= *date && *date < maintenance.to_date
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::ONE_NEAR;
+
+    use crate::test_utils::*;
+
+    #[test]
+    fn slashes_stake_when_cancelling_confirmed_booking() {
+        let (mut contract, room_id) = setup();
+        set_caller(&owner_id(), PRICE);
+        contract.stake_collateral();
+        set_caller(&contract_id(), 0);
+        contract.set_slash_rate(5_000);
+        let check_in_date = today().add_days(10);
+        let booking_id = book(&mut contract, &room_id, check_in_date, 1, PRICE);
+
+        set_caller(&owner_id(), ONE_NEAR);
+        let conflicting = contract.set_maintenance(
+            room_id,
+            check_in_date,
+            check_in_date.add_days(1),
+            "repair".to_string(),
+        );
+        assert_eq!(conflicting, vec![booking_id]);
+        set_caller(&owner_id(), 0);
+        contract.cancel_for_maintenance(booking_id);
+        assert_eq!(transferred_to(&guest_id()), PRICE + PRICE / 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DATE_BLOCKED")]
+    fn rejects_new_booking_during_maintenance() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = today().add_days(10);
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.set_maintenance(
+            room_id.clone(),
+            check_in_date,
+            check_in_date.add_days(3),
+            "repair".to_string(),
+        );
+        book(
+            &mut contract,
+            &room_id,
+            check_in_date.add_days(2),
+            2,
+            2 * PRICE,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_CONFLICTING_BOOKING")]
+    fn rejects_cancel_of_booking_outside_maintenance() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = today().add_days(10);
+        let booking_id = book(&mut contract, &room_id, check_in_date, 1, PRICE);
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.set_maintenance(
+            room_id,
+            check_in_date.add_days(1),
+            check_in_date.add_days(3),
+            "repair".to_string(),
+        );
+        set_caller(&owner_id(), 0);
+        contract.cancel_for_maintenance(booking_id);
+    }
+}
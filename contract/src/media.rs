@@ -0,0 +1,98 @@
+// 部屋の画像
+// 1つの部屋に複数の画像を登録し、一覧に表示するカバー画像を指定できる
+// 画像などの参照先は登録時に形式を確認し、不正な値が保存されないようにする
+
+use crate::*;
+
+// 1つの部屋に登録できる画像の最大数
+const MAX_IMAGES: usize = 10;
+// 画像などの参照先（IPFSのCIDまたはURL）の最大の長さ
+const MAX_MEDIA_URI_LENGTH: usize = 512;
+// Base58の文字（CIDv0で使用）
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+#[near_bindgen]
+impl Contract {
+    // 一覧に表示するカバー画像を、登録済みの画像の中から変更する
+    // 部屋のオーナーのみがコールできる
+    pub fn set_cover_image(&mut self, room_id: RoomId, cover_image_index: u8) {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        assert_valid_images(&room.images, cover_image_index);
+
+        room.cover_image_index = cover_image_index;
+    }
+}
+
+impl Room {
+    // 一覧に表示するカバー画像を返す
+    pub(crate) fn cover_image(&self) -> String {
+        self.images[self.cover_image_index as usize].clone()
+    }
+}
+
+// 画像が1枚以上、上限以下であり、カバー画像の番号が画像の範囲内であるかを確認する
+pub(crate) fn assert_valid_images(images: &[String], cover_image_index: u8) {
+    assert!(
+        !images.is_empty() && images.len() <= MAX_IMAGES,
+        "ERR_INVALID_IMAGES"
+    );
+    assert!(
+        (cover_image_index as usize) < images.len(),
+        "ERR_INVALID_COVER_IMAGE_INDEX"
+    );
+    for image in images {
+        assert_valid_media_uri(image);
+    }
+}
+
+// 画像などの参照先が、IPFSのCID（`ipfs://`を付けたものを含む）またはhttpsのURLであるかを確認する
+pub(crate) fn assert_valid_media_uri(uri: &str) {
+    assert!(
+        uri.len() <= MAX_MEDIA_URI_LENGTH && is_valid_media_uri(uri),
+        "ERR_INVALID_MEDIA_URI"
+    );
+}
+
+fn is_valid_media_uri(uri: &str) -> bool {
+    if let Some(url) = uri.strip_prefix("https://") {
+        let (host, path) = url.split_at(url.find('/').unwrap_or(url.len()));
+        return is_valid_host(host) && is_valid_path(path);
+    }
+    let ipfs_path = uri.strip_prefix("ipfs://").unwrap_or(uri);
+    let (cid, path) = ipfs_path.split_at(ipfs_path.find('/').unwrap_or(ipfs_path.len()));
+    // パスを指定できるのは`ipfs://`を付けた場合のみ
+    is_valid_cid(cid) && (path.is_empty() || (uri.starts_with("ipfs://") && is_valid_path(path)))
+}
+
+// CIDv0（`Qm`で始まるBase58の46文字）またはCIDv1（`b`で始まるBase32）であるかを確認する
+fn is_valid_cid(cid: &str) -> bool {
+    if cid.starts_with("Qm") {
+        return cid.len() == 46 && cid.chars().all(|c| BASE58_ALPHABET.contains(c));
+    }
+    match cid.strip_prefix('b') {
+        Some(encoded) => {
+            encoded.len() >= 58
+                && encoded
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || ('2'..='7').contains(&c))
+        }
+        None => false,
+    }
+}
+
+// ホスト名（ポート番号を含む）が英数字と`.`、`-`、`:`のみで構成されているかを確認する
+fn is_valid_host(host: &str) -> bool {
+    !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':'))
+}
+
+// パスに空白や制御文字、ASCII以外の文字が含まれていないかを確認する
+fn is_valid_path(path: &str) -> bool {
+    path.chars().all(|c| c.is_ascii_graphic())
+}
@@ -0,0 +1,306 @@
+// 旧バージョンのコントラクトの状態を現在の形式に移行する
+
+use crate::booking::push_booking_id;
+use crate::*;
+
+// 日付を`YYYY-MM-DD`形式の文字列で保存していたバージョンの利用状況
+#[derive(BorshDeserialize)]
+enum OldUsageStatus {
+    Available,
+    Stay { check_in_date: String },
+}
+
+// 日付を`YYYY-MM-DD`形式の文字列で保存していたバージョンの部屋のデータ
+#[derive(BorshDeserialize)]
+struct OldRoom {
+    name: String,
+    owner_id: AccountId,
+    image: String,
+    beds: u8,
+    description: String,
+    location: String,
+    price: U128,
+    status: OldUsageStatus,
+    instant_book: bool,
+    approval_window_hours: u64,
+    min_nights: u16,
+    max_nights: u16,
+    booking_window_days: u16,
+    min_lead_time_hours: u16,
+    same_day_cutoff_hour: u8,
+    timezone_offset_minutes: i16,
+    booked_info: HashMap<String, OldBookedInfo>,
+    holds: HashMap<String, OldHold>,
+    waitlists: HashMap<String, Vec<AccountId>>,
+    blocked_dates: HashSet<String>,
+}
+
+// 複数の仮押さえと預かり金の導入前の仮押さえのデータ
+#[derive(BorshDeserialize)]
+struct OldHold {
+    guest_id: AccountId,
+    expires_at: u64,
+}
+
+// 保証金の導入前の予約データ
+#[derive(BorshDeserialize)]
+struct OldBookedInfo {
+    guest_id: AccountId,
+    status: BookingStatus,
+    booked_at: u64,
+    paid: U128,
+}
+
+#[derive(BorshDeserialize)]
+struct OldContract {
+    rooms_per_owner: LookupMap<AccountId, Vec<RoomId>>,
+    rooms_by_id: HashMap<RoomId, OldRoom>,
+}
+
+#[near_bindgen]
+impl Contract {
+    // 日付を文字列で保存していたバージョンからの移行
+    // 部屋のIDは新しい形式の連番に振り直される
+    // コントラクトを再デプロイした直後に一度だけコールする
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old_state: OldContract = env::state_read().expect("ERR_NOT_INITIALIZED");
+
+        let mut contract = Self::default();
+        let mut old_rooms = old_state.rooms_by_id;
+
+        // オーナーのアカウントIDと部屋の名前をつなげていたIDを、オーナーごとに登録した順に連番のIDへ振り直す
+        let mut owner_ids: Vec<AccountId> = old_rooms
+            .values()
+            .map(|room| room.owner_id.clone())
+            .collect();
+        owner_ids.sort();
+        owner_ids.dedup();
+        for owner_id in owner_ids {
+            let mut room_ids = vec![];
+            for old_room_id in old_state.rooms_per_owner.get(&owner_id).unwrap_or_default() {
+                if let Some(room) = old_rooms.remove(&old_room_id) {
+                    let room_id = contract.internal_next_room_id(&owner_id);
+                    contract.migrate_room(room_id.clone(), room);
+                    room_ids.push(room_id);
+                }
+            }
+            contract.rooms_per_owner.insert(&owner_id, &room_ids);
+        }
+        contract
+    }
+}
+
+impl Contract {
+    // 部屋のデータを移行し、宿泊日ごとの予約データを予約単位にまとめて保存する
+    fn migrate_room(&mut self, room_id: RoomId, mut room: OldRoom) {
+        let mut booked_info: Vec<(Date, OldBookedInfo)> =
+            migrate_keys(std::mem::take(&mut room.booked_info))
+                .into_iter()
+                .collect();
+        booked_info.sort_by_key(|(date, _)| *date);
+        let stay_date = match &room.status {
+            OldUsageStatus::Stay { check_in_date } => Some(parse_date(check_in_date)),
+            OldUsageStatus::Available => None,
+        };
+        let mut new_room = migrate_room_fields(room);
+
+        // 連続した宿泊日で、予約者と進行状況が同じものを1つの予約とみなす
+        let mut current: Option<Booking> = None;
+        for (date, info) in booked_info {
+            match current.as_mut() {
+                Some(booking)
+                    if booking.check_out_date == date
+                        && booking.guest_id == info.guest_id
+                        && booking.status == info.status =>
+                {
+                    booking.check_out_date = date.next();
+                    booking.amount = U128(booking.amount.0 + info.paid.0);
+                }
+                _ => {
+                    if let Some(booking) = current.take() {
+                        self.migrate_booking(&mut new_room, booking);
+                    }
+                    current = Some(Booking {
+                        booking_id: self.next_booking_id,
+                        room_id: room_id.clone(),
+                        guest_id: info.guest_id,
+                        check_in_date: date,
+                        check_out_date: date.next(),
+                        quantity: 1,
+                        guests: 1,
+                        payment_token: None,
+                        amount: info.paid,
+                        discount: U128(0),
+                        fees: U128(0),
+                        cleaning_fee: U128(0),
+                        extra_guest_fee: U128(0),
+                        reschedule_fees: U128(0),
+                        security_deposit: U128(0),
+                        tips: U128(0),
+                        balance_due: U128(0),
+                        balance_due_at: 0,
+                        stream_started_at: 0,
+                        streamed: U128(0),
+                        captured: U128(0),
+                        status: info.status,
+                        created_at: info.booked_at,
+                        updated_at: info.booked_at,
+                        time_requests: vec![],
+                        note: String::new(),
+                        deposit_release_at: 0,
+                        damage_claim: None,
+                        platform_fee_bps: 0,
+                        tax_region: String::new(),
+                        tax_bps: 0,
+                        affiliate_id: None,
+                        affiliate_commission_bps: 0,
+                        insurance_claim: None,
+                        rate_plan: RatePlan::Standard,
+                        capture_on_check_in: false,
+                        guest_public_key: None,
+                        access_code: None,
+                        encrypted_instructions: None,
+                    });
+                    self.next_booking_id += 1;
+                }
+            }
+        }
+        if let Some(booking) = current {
+            self.migrate_booking(&mut new_room, booking);
+        }
+
+        // 宿泊中だった予約を記録する
+        if let Some(booking_id) = stay_date
+            .and_then(|date| new_room.booked_info.get(&date))
+            .and_then(|booking_ids| booking_ids.first())
+        {
+            new_room.stays.push(*booking_id);
+        }
+
+        self.rooms_by_id.insert(room_id, new_room);
+    }
+
+    fn migrate_booking(&mut self, room: &mut Room, booking: Booking) {
+        for night in date::nights(&booking.check_in_date, &booking.check_out_date) {
+            room.booked_info.insert(night, vec![booking.booking_id]);
+            room.booked_count.insert(night, 1);
+        }
+        self.bookings_by_id.insert(&booking.booking_id, &booking);
+        self.internal_hold_escrow(booking.booking_id, booking.amount.0);
+        push_booking_id(
+            &mut self.bookings_per_guest,
+            &booking.guest_id,
+            booking.booking_id,
+        );
+        push_booking_id(
+            &mut self.bookings_per_room,
+            &booking.room_id,
+            booking.booking_id,
+        );
+    }
+}
+
+fn migrate_room_fields(room: OldRoom) -> Room {
+    let slug = slugify(&room.name);
+    Room {
+        name: room.name,
+        owner_id: room.owner_id,
+        images: vec![room.image],
+        cover_image_index: 0,
+        beds: room.beds,
+        bookable_by_bed: false,
+        units: 1,
+        description: room.description,
+        // 自由入力だった施設の場所は住所として引き継ぐ
+        location: Location {
+            address_line: room.location,
+            ..Default::default()
+        },
+        price: room.price,
+        price_rules: Vec::new(),
+        date_prices: HashMap::new(),
+        weekly_discount_percent: 0,
+        monthly_discount_percent: 0,
+        last_minute_days: 0,
+        last_minute_discount_percent: 0,
+        early_bird_days: 0,
+        early_bird_discount_percent: 0,
+        pricing_contract_id: None,
+        surge_window_days: 0,
+        surge_threshold_percent: 0,
+        surge_percent: 0,
+        payment_token: None,
+        usd_pricing: false,
+        instant_book: room.instant_book,
+        approval_window_hours: room.approval_window_hours,
+        min_nights: room.min_nights,
+        max_nights: room.max_nights,
+        booking_window_days: room.booking_window_days,
+        min_lead_time_hours: room.min_lead_time_hours,
+        same_day_cutoff_hour: room.same_day_cutoff_hour,
+        timezone_offset_minutes: room.timezone_offset_minutes,
+        security_deposit: U128(0),
+        deposit_release_days: 0,
+        overstay_grace_hours: 0,
+        overstay_penalty_per_day: U128(0),
+        free_reschedule_hours: 0,
+        reschedule_fee: U128(0),
+        cancellation_policy: CancellationPolicy::Moderate,
+        cleaning_fee: U128(0),
+        extra_guest_fee: U128(0),
+        included_guests: 1,
+        max_guests: 0,
+        house_rules: String::new(),
+        check_in_hour: 0,
+        check_out_hour: 0,
+        attributes: RoomAttributes::default(),
+        room_type: RoomType::PrivateRoom,
+        amenities: Vec::new(),
+        translations: HashMap::new(),
+        tags: Vec::new(),
+        installment_deposit_percent: 0,
+        installment_due_days: 0,
+        streaming_min_nights: 0,
+        payout_splits: Vec::new(),
+        booked_info: HashMap::new(),
+        booked_count: HashMap::new(),
+        stays: Vec::new(),
+        holds: migrate_keys(room.holds)
+            .into_iter()
+            .map(|(date, hold)| (date, vec![Hold::new(hold.guest_id, hold.expires_at, 0)]))
+            .collect(),
+        waitlists: migrate_keys(room.waitlists),
+        blocked_dates: room
+            .blocked_dates
+            .iter()
+            .map(|date| parse_date(date))
+            .collect(),
+        promo_codes: HashMap::new(),
+        auctions: HashMap::new(),
+        private_rates: HashMap::new(),
+        non_refundable_discount_percent: 0,
+        payment_window_hours: 0,
+        capture_on_check_in: false,
+        listed: true,
+        slug,
+        pending_owner_id: None,
+        property_id: None,
+        housekeeping_status: HousekeepingStatus::Ready,
+        maintenance: None,
+        shares: HashMap::new(),
+        storage_bytes: 0,
+    }
+}
+
+// 日付文字列をキーとするマップを、`Date`をキーとするマップに変換する
+fn migrate_keys<V>(map: HashMap<String, V>) -> HashMap<Date, V> {
+    map.into_iter()
+        .map(|(date, value)| (parse_date(&date), value))
+        .collect()
+}
+
+fn parse_date(date: &str) -> Date {
+    Date::parse(date).unwrap_or_else(|err| env::panic_str(err))
+}
@@ -0,0 +1,86 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // チェックイン日を過ぎてもチェックインされなかった予約を、ノーショーとして終了する
+    // 経過した宿泊日の掲出料はオーナーの売上に記録され、残りの宿泊日は解放されて再び予約できるようになる
+    // 残りの宿泊日の掲出料はキャンセルポリシーに基づいて分配し、追加料金・保証金は予約者に返金される
+    // 部屋のオーナーのみがコールできる
+    pub fn mark_no_show(&mut self, booking_id: BookingId) -> U128 {
+        let mut booking = self.internal_booking(booking_id);
+        let room = self
+            .rooms_by_id
+            .get(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        assert_eq!(
+            booking.status,
+            BookingStatus::Confirmed,
+            "ERR_BOOKING_NOT_CONFIRMED"
+        );
+
+        // 施設のタイムゾーンでチェックイン日が終わるまではノーショーにできない
+        let today = room.today();
+        assert!(
+            today > booking.check_in_date,
+            "ERR_CHECK_IN_WINDOW_NOT_PASSED"
+        );
+
+        // 経過した宿泊日の掲出料はオーナーに支払い、残りはキャンセルポリシーに基づいて分配する
+        // 割引や落札額を含めて支払われた掲出料を、経過した宿泊日の数で按分する
+        let nights = date::nights(&booking.check_in_date, &booking.check_out_date);
+        let passed_nights = nights.iter().filter(|night| **night < today).count();
+        let passed_price = booking.amount.0 * passed_nights as Balance / nights.len() as Balance;
+        let price_refund = room.cancellation_refund(&booking, booking.amount.0 - passed_price);
+        let (payment, refund) = booking.deduct_balance_due(
+            booking.amount.0 - price_refund,
+            price_refund + booking.fees.0 + booking.security_deposit.0,
+        );
+
+        self.internal_close_booking(&mut booking, BookingStatus::NoShow);
+        if refund > 0 {
+            self.internal_release_escrow(booking_id, booking.guest_id, refund);
+        }
+        if payment > 0 {
+            self.internal_pay_owner(booking_id, payment);
+        }
+        U128(payment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    #[test]
+    fn pays_owner_and_releases_remaining_nights_of_no_show() {
+        let (mut contract, room_id) = setup();
+        let booking_id = book(&mut contract, &room_id, today(), 3, 3 * PRICE);
+
+        // チェックイン日の翌日にノーショーにする
+        let next_day = today().add_days(1);
+        set_caller_at(&owner_id(), 0, noon(&next_day));
+        assert_eq!(contract.mark_no_show(booking_id).0, 3 * PRICE);
+        assert_eq!(earnings_of(&contract, &owner_id()), 3 * PRICE);
+
+        // 残りの宿泊日は、別の予約者が予約できる
+        let rebooked = book_as(
+            &mut contract,
+            &other_guest_id(),
+            &room_id,
+            next_day,
+            1,
+            PRICE,
+        );
+        assert_eq!(contract.internal_booking(rebooked).check_in_date, next_day);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CHECK_IN_WINDOW_NOT_PASSED")]
+    fn rejects_no_show_on_check_in_date() {
+        let (mut contract, room_id) = setup();
+        let booking_id = book(&mut contract, &room_id, today(), 1, PRICE);
+        set_caller(&owner_id(), 0);
+        contract.mark_no_show(booking_id);
+    }
+}
@@ -0,0 +1,264 @@
+// 米ドル建ての掲出料を、価格オラクルの価格でNEARに換算して支払う
+// オラクルはNEARの`priceoracle`コントラクトの`get_price_data`を想定している
+
+use near_sdk::serde_json::json;
+use near_sdk::{Gas, PromiseError};
+
+use crate::booking::PaymentMethod;
+use crate::date::NANOS_PER_HOUR;
+use crate::*;
+
+// オラクルのコールと、その結果を受け取るコールバックに割り当てるガス
+const GAS_FOR_ORACLE: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_ON_ORACLE_PRICE: Gas = Gas(30_000_000_000_000);
+
+// 米ドル建ての金額の小数点以下の桁数
+const USD_DECIMALS: u8 = 6;
+
+// 支払いの確認を待つ時間（1時間）
+// 過ぎてもコールバックで精算されない予約は、`cancel_awaiting_payment`で取り消せる
+const PAYMENT_CONFIRMATION_TIMEOUT: u64 = NANOS_PER_HOUR;
+
+// オラクルが返す価格
+// 資産の最小単位あたりの価格（米ドル）は`multiplier / 10^decimals`になる
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Price {
+    multiplier: U128,
+    decimals: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetOptionalPrice {
+    asset_id: String,
+    price: Option<Price>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceData {
+    prices: Vec<AssetOptionalPrice>,
+}
+
+#[near_bindgen]
+impl Contract {
+    // 価格オラクルのコントラクトと、NEARの価格を取得する資産IDを設定する
+    // 管理者のみがコールできる
+    pub fn set_oracle(&mut self, oracle_id: AccountId, asset_id: String) {
+        self.assert_admin();
+        self.oracle_id = Some(oracle_id);
+        self.oracle_asset_id = asset_id;
+    }
+
+    // 米ドル建ての部屋を予約する
+    // 宿泊日を確保してからオラクルの価格を取得し、コールバックでNEARに換算した額を精算する
+    // 換算した額が添付したNEARまたは`max_amount`を超える場合は予約を取り消して全額を返金し、余った分も返金する
+    #[payable]
+    pub fn book_with_oracle(
+        &mut self,
+        room_id: RoomId,
+        check_in_date: CheckInDate,
+        check_out_date: CheckInDate,
+        guests: Option<u16>,
+        note: Option<String>,
+        max_amount: U128,
+    ) -> Promise {
+        let oracle_id = self.oracle_id.clone().expect("ERR_ORACLE_NOT_SET");
+        let guest_id = env::signer_account_id();
+        let (booking_id, _) = self.internal_create_booking(
+            &guest_id,
+            PaymentMethod::OracleNear,
+            &room_id,
+            check_in_date,
+            check_out_date,
+            1,
+            guests,
+            note,
+            None,
+            None,
+            None,
+        );
+
+        // 価格が確定するまでは、米ドル建ての金額のまま支払い待ちにしておく
+        // 添付されたNEARはそのまま預かり金として記録する
+        let mut booking = self.internal_booking(booking_id);
+        booking.status = BookingStatus::AwaitingPayment;
+        self.bookings_by_id.insert(&booking_id, &booking);
+        self.escrow_by_booking.remove(&booking_id);
+        self.internal_hold_escrow(booking_id, env::attached_deposit());
+
+        Promise::new(oracle_id)
+            .function_call(
+                "get_price_data".to_string(),
+                json!({ "asset_ids": [self.oracle_asset_id] })
+                    .to_string()
+                    .into_bytes(),
+                0,
+                GAS_FOR_ORACLE,
+            )
+            .then(
+                Promise::new(env::current_account_id()).function_call(
+                    "on_oracle_price".to_string(),
+                    json!({ "booking_id": booking_id, "max_amount": max_amount })
+                        .to_string()
+                        .into_bytes(),
+                    0,
+                    GAS_FOR_ON_ORACLE_PRICE,
+                ),
+            )
+    }
+
+    // オラクルの価格を受け取り、米ドル建ての予約をNEARで精算する
+    // 予約が確定した場合は`true`を返す
+    #[private]
+    pub fn on_oracle_price(
+        &mut self,
+        booking_id: BookingId,
+        max_amount: U128,
+        #[callback_result] price_data: Result<PriceData, PromiseError>,
+    ) -> bool {
+        let mut booking = self.internal_booking(booking_id);
+        // 確認待ちの期限を過ぎて取り消された予約は精算しない
+        if booking.status != BookingStatus::AwaitingPayment {
+            return false;
+        }
+        let deposit = self.escrow_by_booking.get(&booking_id).unwrap_or(0);
+        let room = &self.rooms_by_id[&booking.room_id];
+
+        // 価格を取得できた場合のみ、米ドル建ての金額をNEARに換算する
+        let converted = price_data
+            .ok()
+            .and_then(|price_data| self.near_price(price_data))
+            .map(|price| {
+                (
+                    usd_to_yocto(booking.amount.0, &price),
+                    usd_to_yocto(booking.cleaning_fee.0, &price),
+                    usd_to_yocto(booking.extra_guest_fee.0, &price),
+                    usd_to_yocto(booking.security_deposit.0, &price),
+                )
+            });
+        let limit = deposit.min(max_amount.0);
+        let (amount, cleaning_fee, extra_guest_fee, security_deposit) = match converted {
+            Some((amount, cleaning_fee, extra_guest_fee, security_deposit))
+                if amount + cleaning_fee + extra_guest_fee + security_deposit <= limit =>
+            {
+                (amount, cleaning_fee, extra_guest_fee, security_deposit)
+            }
+            _ => {
+                // 価格を取得できない、添付されたNEARが不足する、または価格が変動して
+                // 予約者が指定した上限を超える場合は予約を取り消す
+                self.internal_close_booking(&mut booking, BookingStatus::Cancelled);
+                self.internal_release_escrow(booking_id, booking.guest_id, deposit);
+                return false;
+            }
+        };
+
+        booking.amount = U128(amount);
+        booking.cleaning_fee = U128(cleaning_fee);
+        booking.extra_guest_fee = U128(extra_guest_fee);
+        booking.fees = U128(cleaning_fee + extra_guest_fee);
+        booking.security_deposit = U128(security_deposit);
+        booking.status = if room.instant_book {
+            BookingStatus::Confirmed
+        } else {
+            BookingStatus::Pending
+        };
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+
+        let change = deposit - amount - booking.fees.0 - security_deposit;
+        if change > 0 {
+            self.internal_release_escrow(booking_id, booking.guest_id, change);
+        }
+        true
+    }
+
+    // オラクルや料金設定コントラクトのコールバックが失敗するなどして、支払いの確認待ちのまま`PAYMENT_CONFIRMATION_TIMEOUT`を過ぎた予約を取り消す
+    // 預かっていたNEARの全額を予約者に返金する
+    // 誰でもコールできる
+    pub fn cancel_awaiting_payment(&mut self, booking_id: BookingId) -> Promise {
+        let mut booking = self.internal_booking(booking_id);
+        assert_eq!(
+            booking.status,
+            BookingStatus::AwaitingPayment,
+            "ERR_BOOKING_NOT_AWAITING_PAYMENT"
+        );
+        assert!(
+            env::block_timestamp() >= booking.created_at + PAYMENT_CONFIRMATION_TIMEOUT,
+            "ERR_PAYMENT_CONFIRMATION_NOT_TIMED_OUT"
+        );
+
+        let refund = self.escrow_by_booking.get(&booking_id).unwrap_or(0);
+        self.internal_close_booking(&mut booking, BookingStatus::Cancelled);
+        self.internal_release_escrow(booking_id, booking.guest_id, refund)
+    }
+}
+
+impl Contract {
+    // オラクルの価格データから、NEARの価格を取り出す
+    fn near_price(&self, price_data: PriceData) -> Option<Price> {
+        price_data
+            .prices
+            .into_iter()
+            .find(|asset| asset.asset_id == self.oracle_asset_id)
+            .and_then(|asset| asset.price)
+            .filter(|price| price.multiplier.0 > 0 && price.decimals >= USD_DECIMALS)
+    }
+}
+
+// 米ドル建ての金額を、NEARの価格でyoctoNEARに換算する
+fn usd_to_yocto(usd: Balance, price: &Price) -> Balance {
+    usd * 10u128.pow((price.decimals - USD_DECIMALS) as u32) / price.multiplier.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    // 米ドル建ての部屋をオラクルの価格で予約し、支払いの確認待ちにする
+    fn book_awaiting_price(contract: &mut Contract, room_id: &RoomId) -> BookingId {
+        contract.rooms_by_id.get_mut(room_id).unwrap().usd_pricing = true;
+        set_caller(&contract_id(), 0);
+        contract.set_oracle("oracle.near".parse().unwrap(), "wrap.near".to_string());
+        set_caller(&guest_id(), 2 * PRICE);
+        let booking_id = contract.next_booking_id;
+        let check_in_date = today().add_days(10);
+        contract.book_with_oracle(
+            room_id.clone(),
+            check_in_date,
+            check_in_date.add_days(1),
+            None,
+            None,
+            U128(2 * PRICE),
+        );
+        booking_id
+    }
+
+    #[test]
+    fn refunds_booking_when_oracle_callback_never_settles() {
+        let (mut contract, room_id) = setup();
+        let booking_id = book_awaiting_price(&mut contract, &room_id);
+
+        set_caller_at(
+            &owner_id(),
+            0,
+            noon(&today()) + PAYMENT_CONFIRMATION_TIMEOUT,
+        );
+        contract.cancel_awaiting_payment(booking_id);
+        assert_eq!(
+            contract.internal_booking(booking_id).status,
+            BookingStatus::Cancelled
+        );
+        assert_eq!(transferred_to(&guest_id()), 2 * PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PAYMENT_CONFIRMATION_NOT_TIMED_OUT")]
+    fn rejects_cancel_before_payment_confirmation_timeout() {
+        let (mut contract, room_id) = setup();
+        let booking_id = book_awaiting_price(&mut contract, &room_id);
+        contract.cancel_awaiting_payment(booking_id);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::date::{NANOS_PER_DAY, NANOS_PER_HOUR, NANOS_PER_MINUTE};
+use crate::*;
+
+// 超過滞在の状況を表示する際に使用
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Overstay {
+    guest_id: AccountId,
+    check_out_date: CheckInDate,
+    overdue_days: u64,
+    pub(crate) penalty: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    // 超過滞在とみなすまでの猶予時間と、1日あたりの違約金を変更する
+    // 部屋のオーナーのみがコールできる
+    pub fn set_overstay_policy(
+        &mut self,
+        room_id: RoomId,
+        overstay_grace_hours: u16,
+        overstay_penalty_per_day: U128,
+    ) {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.overstay_grace_hours = overstay_grace_hours;
+        room.overstay_penalty_per_day = overstay_penalty_per_day;
+    }
+
+    // 宿泊中の予約が超過滞在になっている場合、その状況を取得する
+    pub fn get_overstay(&self, booking_id: BookingId) -> Option<Overstay> {
+        let booking = self.internal_booking(booking_id);
+        if booking.status != BookingStatus::CheckedIn {
+            return None;
+        }
+        let room = self
+            .rooms_by_id
+            .get(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.overstay(&booking)
+    }
+
+    // 超過滞在中の予約者の保証金から違約金を受け取り、宿泊を終了させる
+    // 違約金は保証金を上限とし、残りの保証金は予約者に返金される（返金日が設定されている場合はその日まで預かる）
+    pub fn claim_overstay_penalty(&mut self, booking_id: BookingId) -> U128 {
+        let booking = self.internal_booking(booking_id);
+        let room = self
+            .rooms_by_id
+            .get(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        let overstay = self.get_overstay(booking_id).expect("ERR_NOT_OVERSTAYED");
+
+        let (payment, security_deposit) = self.internal_finish_stay(booking_id);
+        let penalty = overstay.penalty.0.min(security_deposit);
+        self.internal_return_security_deposit(booking_id, security_deposit - penalty);
+        self.internal_pay_owner(booking_id, payment + penalty);
+        U128(penalty)
+    }
+}
+
+impl Room {
+    // チェックアウト日の猶予時間を過ぎても宿泊中の場合、超過滞在の状況を返す
+    pub(crate) fn overstay(&self, booking: &Booking) -> Option<Overstay> {
+        let check_out_date = booking.check_out_date;
+
+        // 施設のタイムゾーンでのチェックアウト日の開始時刻から猶予時間を過ぎているかを確認
+        // 部屋のチェックアウトの時刻、またはレイトチェックアウトが確定している場合はその時刻から猶予時間を数える
+        let offset = self.timezone_offset_minutes as i64 * NANOS_PER_MINUTE as i64;
+        let late_check_out =
+            booking.late_check_out_hour().unwrap_or(self.check_out_hour) as u64 * NANOS_PER_HOUR;
+        let check_out_at = (check_out_date.start_timestamp() + late_check_out) as i64 - offset;
+        let grace = (self.overstay_grace_hours as u64 * NANOS_PER_HOUR) as i64;
+        let now = env::block_timestamp() as i64;
+        if now <= check_out_at + grace {
+            return None;
+        }
+
+        // チェックアウト日から数えて、超過した日数分の違約金を計算する
+        let overdue_days = ((now - check_out_at) as u64) / NANOS_PER_DAY + 1;
+        let penalty = self.overstay_penalty_per_day.0 * overdue_days as Balance;
+        Some(Overstay {
+            guest_id: booking.guest_id.clone(),
+            check_out_date,
+            overdue_days,
+            penalty: U128(penalty),
+        })
+    }
+}
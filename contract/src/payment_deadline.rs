@@ -0,0 +1,62 @@
+// 予約リクエストの支払い期限
+// 承認が必要な部屋では、予約リクエスト時には支払わずに、オーナーの承認後の期限までに支払う
+// 期限までに支払われない予約は、`cancel_unpaid_bookings`でキャンセルされ、宿泊日が解放される
+
+use crate::date::NANOS_PER_HOUR;
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // 予約リクエストの承認後に支払うまでの期限（時間）を変更する
+    // 0の場合は、予約リクエスト時に全額を支払う
+    // 変更後の設定は、これから作成される予約にのみ適用される
+    // 部屋のオーナーのみがコールできる
+    pub fn set_payment_window(&mut self, room_id: RoomId, payment_window_hours: u16) {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.payment_window_hours = payment_window_hours;
+    }
+}
+
+impl Contract {
+    // まだ何も支払われていない予約リクエストが承認された時に、承認した時点から支払い期限を数え直す
+    pub(crate) fn internal_start_payment_window(&mut self, booking_id: BookingId) {
+        if self.escrow_by_booking.get(&booking_id).unwrap_or(0) > 0 {
+            return;
+        }
+        let mut booking = self.internal_booking(booking_id);
+        if booking.balance_due.0 == 0 {
+            return;
+        }
+        let room = &self.rooms_by_id[&booking.room_id];
+        booking.balance_due_at = room.payment_due_at(&booking.check_in_date, 0);
+        self.bookings_by_id.insert(&booking_id, &booking);
+    }
+}
+
+impl Room {
+    // 承認後に支払う予約リクエストの場合は、後から支払う額（全額）とその支払い期限を返す
+    // 期限は承認期間と支払い期限を合わせた時刻とし、チェックインの時刻を超えない
+    pub(crate) fn deferred_payment(
+        &self,
+        total: Balance,
+        check_in_date: &CheckInDate,
+    ) -> Option<(Balance, u64)> {
+        if self.instant_book || self.payment_window_hours == 0 {
+            return None;
+        }
+        let approval_window = self.approval_window_hours * NANOS_PER_HOUR;
+        Some((total, self.payment_due_at(check_in_date, approval_window)))
+    }
+
+    // 現在から`wait`と支払い期限が経過した時刻と、チェックインの時刻のうち早い方を返す
+    fn payment_due_at(&self, check_in_date: &CheckInDate, wait: u64) -> u64 {
+        let due_at =
+            env::block_timestamp() + wait + self.payment_window_hours as u64 * NANOS_PER_HOUR;
+        due_at.min(self.local_start_timestamp(check_in_date).max(0) as u64)
+    }
+}
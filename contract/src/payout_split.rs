@@ -0,0 +1,143 @@
+// 部屋の売上を複数の受取人に分配する（共同ホスト・清掃業者など）
+
+use crate::*;
+
+// 分配の割合の合計（ベーシスポイント）
+const TOTAL_SHARE_BPS: u16 = 10_000;
+
+// 分配できる受取人の最大数
+const MAX_PAYOUT_SPLITS: usize = 10;
+
+// 売上の受取人と、その受取人に分配される割合
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PayoutSplit {
+    account_id: AccountId, // 受取人のアカウントID
+    share_bps: u16,        // 分配される割合（ベーシスポイント）
+}
+
+#[near_bindgen]
+impl Contract {
+    // 売上の受取人と割合を変更する
+    // 割合の合計は10000（100%）でなければならず、空の場合はオーナーが全額を受け取る
+    // 変更後の分配は、これから行われる支払いに適用される
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn set_payout_splits(&mut self, room_id: RoomId, payout_splits: Vec<PayoutSplit>) {
+        assert_valid_payout_splits(&payout_splits);
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.payout_splits = payout_splits;
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // 部屋の売上の受取人と割合を取得する
+    pub fn get_payout_splits(&self, room_id: RoomId) -> Vec<PayoutSplit> {
+        self.rooms_by_id
+            .get(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM")
+            .payout_splits
+            .clone()
+    }
+}
+
+impl Room {
+    // オーナーへの支払いを受取人ごとの額に分ける
+    // 受取人に分配した残りは、部屋の持分の保有者に分配する
+    pub(crate) fn split_payout(&self, amount: Balance) -> Vec<(AccountId, Balance)> {
+        let mut payouts: Vec<(AccountId, Balance)> = self
+            .payout_splits
+            .iter()
+            .filter(|split| split.account_id != self.owner_id)
+            .map(|split| {
+                let share = amount * split.share_bps as Balance / TOTAL_SHARE_BPS as Balance;
+                (split.account_id.clone(), share)
+            })
+            .collect();
+        let distributed: Balance = payouts.iter().map(|(_, share)| share).sum();
+        payouts.extend(self.distribute_to_shareholders(amount - distributed));
+        payouts
+    }
+}
+
+// 受取人と割合の設定が正しいかを確認する
+pub(crate) fn assert_valid_payout_splits(payout_splits: &[PayoutSplit]) {
+    if payout_splits.is_empty() {
+        return;
+    }
+    assert!(
+        payout_splits.len() <= MAX_PAYOUT_SPLITS,
+        "ERR_TOO_MANY_PAYOUT_SPLITS"
+    );
+    let mut account_ids = HashSet::new();
+    assert!(
+        payout_splits
+            .iter()
+            .all(|split| account_ids.insert(&split.account_id)),
+        "ERR_DUPLICATE_PAYOUT_RECIPIENT"
+    );
+    let total: u32 = payout_splits
+        .iter()
+        .map(|split| split.share_bps as u32)
+        .sum();
+    assert_eq!(total, TOTAL_SHARE_BPS as u32, "ERR_INVALID_PAYOUT_SHARES");
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::ONE_NEAR;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    fn cohost_id() -> AccountId {
+        "cohost.near".parse().unwrap()
+    }
+
+    fn split(account_id: AccountId, share_bps: u16) -> PayoutSplit {
+        PayoutSplit {
+            account_id,
+            share_bps,
+        }
+    }
+
+    #[test]
+    fn splits_owner_payment_between_recipients() {
+        let (mut contract, room_id) = setup();
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.set_payout_splits(
+            room_id.clone(),
+            vec![split(owner_id(), 7_000), split(cohost_id(), 3_000)],
+        );
+
+        let booking_id = book(&mut contract, &room_id, today(), 1, PRICE);
+        contract.check_in(booking_id);
+        contract.check_out(booking_id);
+        assert_eq!(earnings_of(&contract, &owner_id()), PRICE * 7 / 10);
+        assert_eq!(earnings_of(&contract, &cohost_id()), PRICE * 3 / 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_PAYOUT_SHARES")]
+    fn rejects_splits_not_adding_up_to_whole() {
+        let (mut contract, room_id) = setup();
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.set_payout_splits(room_id, vec![split(cohost_id(), 3_000)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DUPLICATE_PAYOUT_RECIPIENT")]
+    fn rejects_duplicate_recipients() {
+        let (mut contract, room_id) = setup();
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.set_payout_splits(
+            room_id,
+            vec![split(cohost_id(), 5_000), split(cohost_id(), 5_000)],
+        );
+    }
+}
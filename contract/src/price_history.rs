@@ -0,0 +1,60 @@
+// 掲出料の変更履歴
+// 基本の掲出料と日付ごとの掲出料の変更を追記のみの履歴に記録し、割引前の掲出料が不当に引き上げられていないかを確認できるようにする
+
+use crate::*;
+
+// 掲出料の変更の記録
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceChange {
+    date: Option<CheckInDate>, // 変更された日付（基本の掲出料の変更の場合は`None`）
+    old_price: U128,           // 変更前の1泊あたりの掲出料
+    new_price: U128,           // 変更後の1泊あたりの掲出料
+    changed_at: u64,           // 変更した日時
+    changed_by: AccountId,     // 変更したアカウントID
+}
+
+#[near_bindgen]
+impl Contract {
+    // 部屋の掲出料の変更履歴を変更した順に取得する
+    // `from_index`番目から最大`limit`件を返す
+    pub fn get_price_history(
+        &self,
+        room_id: RoomId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<PriceChange> {
+        self.price_history
+            .get(&room_id)
+            .unwrap_or_default()
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+impl Contract {
+    // 掲出料の変更を履歴に追記する
+    // 掲出料が変わらない場合は記録しない
+    pub(crate) fn internal_record_price_change(
+        &mut self,
+        room_id: &RoomId,
+        date: Option<CheckInDate>,
+        old_price: Balance,
+        new_price: Balance,
+    ) {
+        if old_price == new_price {
+            return;
+        }
+        let mut history = self.price_history.get(room_id).unwrap_or_default();
+        history.push(PriceChange {
+            date,
+            old_price: U128(old_price),
+            new_price: U128(new_price),
+            changed_at: env::block_timestamp(),
+            changed_by: env::signer_account_id(),
+        });
+        self.price_history.insert(room_id, &history);
+    }
+}
@@ -0,0 +1,447 @@
+// 宿泊日ごとの掲出料の計算
+// 基本の掲出料に、期間ごとの料金ルール（繁忙期・閑散期など）や日付ごとの掲出料を適用する
+
+use crate::*;
+
+// 部屋に設定できる料金ルールの最大数
+const MAX_PRICE_RULES: usize = 50;
+
+// 1回の`update_prices`で変更できる部屋の最大数
+const MAX_BULK_PRICES: usize = 100;
+
+// 週単位・月単位の割引を適用する最小宿泊数
+const WEEKLY_NIGHTS: usize = 7;
+const MONTHLY_NIGHTS: usize = 28;
+
+// 期間ごとの料金ルール
+// 開始日から終了日までの宿泊日の掲出料を、基本の掲出料の指定された割合にする
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceRule {
+    start_date: CheckInDate, // ルールを適用する最初の宿泊日
+    end_date: CheckInDate,   // ルールを適用する最後の宿泊日
+    price_percent: u16,      // 基本の掲出料に対する割合（%、200の場合は2倍）
+}
+
+// 予約カレンダーの1日分の空き状況と掲出料
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CalendarDay {
+    available: bool, // 予約可能か
+    price: U128,     // 1泊あたりの掲出料
+}
+
+#[near_bindgen]
+impl Contract {
+    // 期間ごとの料金ルールを変更する
+    // 期間が重なる場合は、一覧の先にあるルールが適用される
+    // 変更後のルールは、これから作成される予約にのみ適用される
+    // 部屋のデータが増えた分のストレージの代金は、預けている代金と添付されたNEARから支払う
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn set_price_rules(&mut self, room_id: RoomId, price_rules: Vec<PriceRule>) {
+        assert!(
+            price_rules.len() <= MAX_PRICE_RULES,
+            "ERR_TOO_MANY_PRICE_RULES"
+        );
+        for rule in price_rules.iter() {
+            assert!(rule.start_date <= rule.end_date, "ERR_INVALID_DATE_RANGE");
+            assert!(rule.price_percent > 0, "ERR_INVALID_PRICE_PERCENT");
+        }
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.price_rules = price_rules;
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // 部屋の料金ルールを取得する
+    pub fn get_price_rules(&self, room_id: RoomId) -> Vec<PriceRule> {
+        self.rooms_by_id
+            .get(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM")
+            .price_rules
+            .clone()
+    }
+
+    // 基本の1泊あたりの掲出料を変更する
+    // 変更は掲出料の変更履歴に記録され、履歴が増えた分のストレージの代金は、預けている代金と添付されたNEARから支払う
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn set_price(&mut self, room_id: RoomId, price: U128) {
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        let old_price = std::mem::replace(&mut room.price, price);
+        self.internal_record_price_change(&room_id, None, old_price.0, price.0);
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // 複数の部屋の基本の掲出料をまとめて変更する
+    // 1つでもオーナーでない部屋が含まれる場合は、全ての変更が取り消される
+    // 変更は掲出料の変更履歴に記録され、履歴が増えた分のストレージの代金は、預けている代金から支払う
+    pub fn update_prices(&mut self, prices: Vec<(RoomId, U128)>) {
+        assert!(prices.len() <= MAX_BULK_PRICES, "ERR_TOO_MANY_PRICES");
+        for (room_id, price) in prices {
+            self.set_price(room_id, price);
+        }
+    }
+
+    // 指定された日付の掲出料を、料金ルールより優先して設定する
+    // 変更は掲出料の変更履歴に記録される
+    // 部屋のデータと履歴が増えた分のストレージの代金は、預けている代金と添付されたNEARから支払う
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn set_date_price(&mut self, room_id: RoomId, date: CheckInDate, price: U128) {
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        date.assert_not_past(&room.today());
+
+        let old_price = room.base_price_for(&date);
+        room.date_prices.insert(date, price);
+        self.internal_record_price_change(&room_id, Some(date), old_price, price.0);
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // 日付ごとに設定した掲出料を削除し、基本の掲出料と料金ルールに戻す
+    // 変更は掲出料の変更履歴に記録され、履歴が増えた分のストレージの代金は、預けている代金と添付されたNEARから支払う
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn clear_date_price(&mut self, room_id: RoomId, date: CheckInDate) {
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        let old_price = room.base_price_for(&date);
+        room.date_prices.remove(&date);
+        let new_price = room.base_price_for(&date);
+        self.internal_record_price_change(&room_id, Some(date), old_price, new_price);
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // 指定された期間の日付ごとの空き状況と掲出料を取得する
+    pub fn get_room_calendar(
+        &self,
+        room_id: RoomId,
+        from_date: CheckInDate,
+        to_date: CheckInDate,
+    ) -> HashMap<CheckInDate, CalendarDay> {
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        from_date.assert_not_past(&room.today());
+        let dates = date::nights(&from_date, &to_date);
+        assert!(dates.len() <= MAX_CALENDAR_DAYS, "ERR_DATE_RANGE_TOO_LONG");
+
+        let surge_active = room.is_surge_active();
+        dates
+            .into_iter()
+            .map(|date| {
+                let day = CalendarDay {
+                    available: room.is_available(&date),
+                    price: U128(room.price_for(&date, surge_active)),
+                };
+                (date, day)
+            })
+            .collect()
+    }
+
+    // 1週間以上・1か月以上の宿泊に適用する割引率を変更する
+    // 変更後の割引は、これから作成される予約にのみ適用される
+    // 部屋のオーナーのみがコールできる
+    pub fn set_stay_discounts(
+        &mut self,
+        room_id: RoomId,
+        weekly_discount_percent: u8,
+        monthly_discount_percent: u8,
+    ) {
+        assert!(
+            weekly_discount_percent <= 100 && monthly_discount_percent <= 100,
+            "ERR_INVALID_DISCOUNT"
+        );
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.weekly_discount_percent = weekly_discount_percent;
+        room.monthly_discount_percent = monthly_discount_percent;
+    }
+
+    // 直前割引を変更する
+    // 今日から`last_minute_days`日以内でまだ予約が入っていない日付の掲出料を、指定された割合だけ割り引く
+    // 部屋のオーナーのみがコールできる
+    pub fn set_last_minute_discount(
+        &mut self,
+        room_id: RoomId,
+        last_minute_days: u16,
+        last_minute_discount_percent: u8,
+    ) {
+        assert!(last_minute_discount_percent <= 100, "ERR_INVALID_DISCOUNT");
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.last_minute_days = last_minute_days;
+        room.last_minute_discount_percent = last_minute_discount_percent;
+    }
+
+    // 早期予約割引を変更する
+    // チェックインの`early_bird_days`日より前に予約した場合に、掲出料を指定された割合だけ割り引く
+    // 部屋のオーナーのみがコールできる
+    pub fn set_early_bird_discount(
+        &mut self,
+        room_id: RoomId,
+        early_bird_days: u16,
+        early_bird_discount_percent: u8,
+    ) {
+        assert!(early_bird_discount_percent <= 100, "ERR_INVALID_DISCOUNT");
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.early_bird_days = early_bird_days;
+        room.early_bird_discount_percent = early_bird_discount_percent;
+    }
+
+    // 稼働率に応じた値上げを変更する
+    // 今日から`surge_window_days`日間の稼働率が`surge_threshold_percent`%を超えると、掲出料を`surge_percent`%値上げする
+    // `surge_percent`が0の場合は値上げしない
+    // 部屋のオーナーのみがコールできる
+    pub fn set_surge_pricing(
+        &mut self,
+        room_id: RoomId,
+        surge_window_days: u16,
+        surge_threshold_percent: u8,
+        surge_percent: u16,
+    ) {
+        assert!(
+            surge_window_days as usize <= MAX_CALENDAR_DAYS,
+            "ERR_DATE_RANGE_TOO_LONG"
+        );
+        assert!(surge_threshold_percent < 100, "ERR_INVALID_THRESHOLD");
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.surge_window_days = surge_window_days;
+        room.surge_threshold_percent = surge_threshold_percent;
+        room.surge_percent = surge_percent;
+    }
+
+    // 指定されたアカウントだけに適用する1泊あたりの掲出料を設定する
+    // 設定したアカウントが予約する場合は、料金ルールや割引に関わらずこの掲出料で予約される
+    // 部屋のデータが増えた分のストレージの代金は、預けている代金と添付されたNEARから支払う
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn set_private_rate(&mut self, room_id: RoomId, account_id: AccountId, price: U128) {
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.private_rates.insert(account_id, price);
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // アカウントに設定した掲出料を削除する
+    // 部屋のオーナーのみがコールできる
+    pub fn remove_private_rate(&mut self, room_id: RoomId, account_id: AccountId) {
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.private_rates
+            .remove(&account_id)
+            .expect("ERR_NOT_FOUND_PRIVATE_RATE");
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // アカウントに設定された1泊あたりの掲出料を取得する
+    pub fn get_private_rate(&self, room_id: RoomId, account_id: AccountId) -> Option<U128> {
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        room.private_rates.get(&account_id).copied()
+    }
+
+    // 指定された宿泊の掲出料の合計を見積もる
+    // 宿泊日ごとの掲出料に予約する数を掛け、宿泊数に応じた割引と早期予約割引を適用した額を返す
+    pub fn get_quote(
+        &self,
+        room_id: RoomId,
+        check_in_date: CheckInDate,
+        check_out_date: CheckInDate,
+        quantity: Option<u16>,
+    ) -> U128 {
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        let nights = date::nights(&check_in_date, &check_out_date);
+        assert!(nights.len() <= MAX_CALENDAR_DAYS, "ERR_DATE_RANGE_TOO_LONG");
+        U128(room.price_for_nights(&nights, quantity.unwrap_or(1)))
+    }
+
+    // 指定された宿泊日の1泊あたりの掲出料を取得する
+    pub fn get_nightly_price(&self, room_id: RoomId, date: CheckInDate) -> U128 {
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        U128(room.price_for(&date, room.is_surge_active()))
+    }
+}
+
+impl Room {
+    // 指定された日付の1泊あたりの掲出料を返す
+    // `surge_active`が`true`の場合は値上げ後の、直前割引の対象の日付は割引後の掲出料を返す
+    fn price_for(&self, date: &CheckInDate, surge_active: bool) -> Balance {
+        let mut price = self.base_price_for(date);
+        if surge_active {
+            price += price * self.surge_percent as Balance / 100;
+        }
+        if self.is_last_minute(date) {
+            price - price * self.last_minute_discount_percent as Balance / 100
+        } else {
+            price
+        }
+    }
+
+    // 今日から値上げの判定期間の稼働率が下限を超えていて、値上げするかを返す
+    // 予約状況・部屋数・予約を停止した日付・値上げの設定と日付の変化が常に反映されるよう、掲出料を計算するたびに判定する
+    // 予約を停止した日付は、稼働率の計算に含めない
+    fn is_surge_active(&self) -> bool {
+        if self.surge_percent == 0 || self.surge_window_days == 0 {
+            return false;
+        }
+        let today = self.today();
+        let window_end = today.add_days(self.surge_window_days as u64);
+        let (booked, capacity) = date::nights(&today, &window_end)
+            .iter()
+            .filter(|date| !self.is_blocked(date))
+            .fold((0, 0), |(booked, capacity), date| {
+                let count = self.booked_count.get(date).copied().unwrap_or(0) as u64;
+                (booked + count, capacity + self.capacity() as u64)
+            });
+        capacity > 0 && booked * 100 > capacity * self.surge_threshold_percent as u64
+    }
+
+    // 直前割引の対象の日付かを確認する
+    // 施設のタイムゾーンで今日から指定された日数以内の日付で、まだ予約が入っていないものが対象になる
+    fn is_last_minute(&self, date: &CheckInDate) -> bool {
+        if self.last_minute_discount_percent == 0 {
+            return false;
+        }
+        let today = self.today();
+        *date >= today
+            && *date < today.add_days(self.last_minute_days as u64)
+            && !self.is_booked(date)
+    }
+
+    // 割引を適用する前の1泊あたりの掲出料を返す
+    // 日付ごとの掲出料が設定されている場合はその額を、日付を含む料金ルールがある場合は基本の掲出料にその割合を掛けた額を返す
+    fn base_price_for(&self, date: &CheckInDate) -> Balance {
+        if let Some(price) = self.date_prices.get(date) {
+            return price.0;
+        }
+        match self
+            .price_rules
+            .iter()
+            .find(|rule| rule.start_date <= *date && *date <= rule.end_date)
+        {
+            Some(rule) => self.price.0 * rule.price_percent as Balance / 100,
+            None => self.price.0,
+        }
+    }
+
+    // 宿泊日ごとの掲出料に予約する数を掛けた合計を返す
+    // 7泊以上の場合は週単位の割引を、28泊以上の場合は月単位の割引を適用する
+    // チェックイン日が早期予約割引の対象の場合は、さらに早期予約割引を適用する
+    pub(crate) fn price_for_nights(&self, nights: &[CheckInDate], quantity: u16) -> Balance {
+        let surge_active = self.is_surge_active();
+        let total: Balance = nights
+            .iter()
+            .map(|night| self.price_for(night, surge_active) * quantity as Balance)
+            .sum();
+        let discount_percent = match nights.len() {
+            n if n >= MONTHLY_NIGHTS => self.monthly_discount_percent,
+            n if n >= WEEKLY_NIGHTS => self.weekly_discount_percent,
+            _ => 0,
+        };
+        let total = total - total * discount_percent as Balance / 100;
+        match nights.first() {
+            Some(check_in_date) if self.is_early_bird(check_in_date) => {
+                total - total * self.early_bird_discount_percent as Balance / 100
+            }
+            _ => total,
+        }
+    }
+
+    // 予約者と料金プランに応じた宿泊日の掲出料の合計を返す
+    // 予約者に掲出料が設定されている場合は、その掲出料に宿泊数と予約する数を掛けた額を返す
+    pub(crate) fn price_for_guest(
+        &self,
+        guest_id: &AccountId,
+        nights: &[CheckInDate],
+        quantity: u16,
+        rate_plan: &RatePlan,
+    ) -> Balance {
+        let amount = match self.private_rates.get(guest_id) {
+            Some(price) => price.0 * nights.len() as Balance * quantity as Balance,
+            None => self.price_for_nights(nights, quantity),
+        };
+        self.price_for_rate_plan(rate_plan, amount)
+    }
+
+    // 早期予約割引の対象のチェックイン日かを確認する
+    // 施設のタイムゾーンで今日から指定された日数より先のチェックイン日が対象になる
+    fn is_early_bird(&self, check_in_date: &CheckInDate) -> bool {
+        self.early_bird_discount_percent > 0
+            && *check_in_date > self.today().add_days(self.early_bird_days as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::ONE_NEAR;
+
+    use crate::test_utils::*;
+
+    #[test]
+    fn applies_surge_when_blocked_dates_raise_occupancy() {
+        let (mut contract, room_id) = setup();
+        contract.set_surge_pricing(room_id.clone(), 10, 40, 50);
+        book(&mut contract, &room_id, today().add_days(1), 1, PRICE);
+        let date = today().add_days(20);
+        assert_eq!(contract.get_nightly_price(room_id.clone(), date).0, PRICE);
+
+        // 10日間のうち8日の予約を停止すると、残りの2日のうち1日が予約済みで稼働率は50%になる
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.block_dates(
+            room_id.clone(),
+            (2..10).map(|days| today().add_days(days)).collect(),
+        );
+        assert_eq!(
+            contract.get_nightly_price(room_id, date).0,
+            PRICE + PRICE / 2
+        );
+    }
+}
@@ -0,0 +1,264 @@
+// 外部の料金設定コントラクトによる掲出料の決定
+// 部屋に料金設定コントラクトが指定されている場合は、宿泊日ごとに`get_price(room_id, date)`をコールして掲出料を決める
+
+use near_sdk::serde_json::{self, json};
+use near_sdk::{Gas, PromiseResult};
+
+use crate::booking::PaymentMethod;
+use crate::*;
+
+// 料金設定コントラクトのコールと、その結果を受け取るコールバックに割り当てるガス
+const GAS_FOR_GET_PRICE: Gas = Gas(5_000_000_000_000);
+const GAS_FOR_ON_EXTERNAL_PRICES: Gas = Gas(30_000_000_000_000);
+
+// 料金設定コントラクトで掲出料を決める予約の最大宿泊数
+const MAX_EXTERNALLY_PRICED_NIGHTS: usize = 30;
+
+#[near_bindgen]
+impl Contract {
+    // 掲出料を決める外部の料金設定コントラクトを指定する
+    // `None`の場合は部屋の掲出料の設定をそのまま使う
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn set_pricing_contract(
+        &mut self,
+        room_id: RoomId,
+        pricing_contract_id: Option<AccountId>,
+    ) {
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        assert!(
+            pricing_contract_id.is_none() || (!room.usd_pricing && room.payment_token.is_none()),
+            "ERR_EXTERNALLY_PRICED_ROOM_PAYS_IN_NEAR"
+        );
+
+        room.pricing_contract_id = pricing_contract_id;
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // 料金設定コントラクトが指定された部屋を予約する
+    // 宿泊日を確保してから料金設定コントラクトに宿泊日ごとの掲出料を問い合わせ、コールバックで精算する
+    // 掲出料・追加料金・保証金の合計が添付したNEARまたは`max_amount`を超える場合は予約を取り消して全額を返金し、余った分も返金する
+    // コールバックで精算されないまま時間が経った予約は、`cancel_awaiting_payment`で取り消せる
+    #[payable]
+    pub fn book_with_pricing(
+        &mut self,
+        room_id: RoomId,
+        check_in_date: CheckInDate,
+        check_out_date: CheckInDate,
+        guests: Option<u16>,
+        note: Option<String>,
+        max_amount: U128,
+    ) -> Promise {
+        let pricing_contract_id = self
+            .rooms_by_id
+            .get(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM")
+            .pricing_contract_id
+            .clone()
+            .expect("ERR_PRICING_CONTRACT_NOT_SET");
+        let nights = date::nights(&check_in_date, &check_out_date);
+        assert!(
+            nights.len() <= MAX_EXTERNALLY_PRICED_NIGHTS,
+            "ERR_STAY_TOO_LONG"
+        );
+        let (booking_id, _) = self.internal_create_booking(
+            &env::signer_account_id(),
+            PaymentMethod::ExternalPrice,
+            &room_id,
+            check_in_date,
+            check_out_date,
+            1,
+            guests,
+            note,
+            None,
+            None,
+            None,
+        );
+
+        // 掲出料が確定するまでは支払い待ちにしておき、添付されたNEARはそのまま預かり金として記録する
+        let mut booking = self.internal_booking(booking_id);
+        booking.status = BookingStatus::AwaitingPayment;
+        self.bookings_by_id.insert(&booking_id, &booking);
+        self.escrow_by_booking.remove(&booking_id);
+        self.internal_hold_escrow(booking_id, env::attached_deposit());
+
+        let get_price = |night: &CheckInDate| {
+            Promise::new(pricing_contract_id.clone()).function_call(
+                "get_price".to_string(),
+                json!({ "room_id": room_id, "date": night })
+                    .to_string()
+                    .into_bytes(),
+                0,
+                GAS_FOR_GET_PRICE,
+            )
+        };
+        let prices = nights[1..]
+            .iter()
+            .fold(get_price(&nights[0]), |prices, night| {
+                prices.and(get_price(night))
+            });
+        prices.then(
+            Promise::new(env::current_account_id()).function_call(
+                "on_external_prices".to_string(),
+                json!({ "booking_id": booking_id, "max_amount": max_amount })
+                    .to_string()
+                    .into_bytes(),
+                0,
+                GAS_FOR_ON_EXTERNAL_PRICES,
+            ),
+        )
+    }
+
+    // 料金設定コントラクトから宿泊日ごとの掲出料を受け取り、予約を精算する
+    // 予約が確定した場合は`true`を返す
+    #[private]
+    pub fn on_external_prices(&mut self, booking_id: BookingId, max_amount: U128) -> bool {
+        let mut booking = self.internal_booking(booking_id);
+        // 確認待ちの期限を過ぎて取り消された予約は精算しない
+        if booking.status != BookingStatus::AwaitingPayment {
+            return false;
+        }
+        let deposit = self.escrow_by_booking.get(&booking_id).unwrap_or(0);
+        let room = &self.rooms_by_id[&booking.room_id];
+
+        // 全ての宿泊日の掲出料を受け取れた場合のみ、その合計を掲出料とする
+        // 合計があふれる掲出料は、受け取れなかったものとして扱う
+        let amount = (0..env::promise_results_count()).try_fold(0, |amount: Balance, index| {
+            match env::promise_result(index) {
+                PromiseResult::Successful(value) => serde_json::from_slice::<U128>(&value)
+                    .ok()
+                    .and_then(|price| amount.checked_add(price.0)),
+                _ => None,
+            }
+        });
+        let total = amount.and_then(|amount| {
+            amount
+                .checked_add(booking.fees.0)?
+                .checked_add(booking.security_deposit.0)
+        });
+        let limit = deposit.min(max_amount.0);
+        let amount = match (amount, total) {
+            (Some(amount), Some(total)) if total <= limit => amount,
+            _ => {
+                // 掲出料を受け取れない、または添付されたNEARや予約者が指定した上限を超える場合は予約を取り消す
+                self.internal_close_booking(&mut booking, BookingStatus::Cancelled);
+                self.internal_release_escrow(booking_id, booking.guest_id, deposit);
+                return false;
+            }
+        };
+
+        booking.amount = U128(amount);
+        booking.status = if room.instant_book {
+            BookingStatus::Confirmed
+        } else {
+            BookingStatus::Pending
+        };
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+
+        let change = deposit - amount - booking.fees.0 - booking.security_deposit.0;
+        if change > 0 {
+            self.internal_release_escrow(booking_id, booking.guest_id, change);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+
+    use super::*;
+    use crate::date::NANOS_PER_HOUR;
+    use crate::test_utils::*;
+
+    #[test]
+    fn cancels_booking_when_external_prices_overflow() {
+        let (mut contract, room_id) = setup();
+        contract.set_pricing_contract(room_id.clone(), Some("pricing.near".parse().unwrap()));
+        set_caller(&guest_id(), 3 * PRICE);
+        let booking_id = contract.next_booking_id;
+        let check_in_date = today().add_days(10);
+        contract.book_with_pricing(
+            room_id,
+            check_in_date,
+            check_in_date.add_days(2),
+            None,
+            None,
+            U128(Balance::MAX),
+        );
+
+        // 2泊の掲出料の合計が`Balance`の上限を超える
+        let price = serde_json::to_vec(&U128(Balance::MAX / 2 + 1)).unwrap();
+        testing_env!(
+            VMContextBuilder::new()
+                .current_account_id(contract_id())
+                .predecessor_account_id(contract_id())
+                .block_timestamp(noon(&today()))
+                .build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            HashMap::default(),
+            vec![
+                PromiseResult::Successful(price.clone()),
+                PromiseResult::Successful(price),
+            ],
+        );
+        assert!(!contract.on_external_prices(booking_id, U128(Balance::MAX)));
+        assert_eq!(
+            contract.internal_booking(booking_id).status,
+            BookingStatus::Cancelled
+        );
+        assert_eq!(transferred_to(&guest_id()), 3 * PRICE);
+    }
+
+    #[test]
+    fn ignores_external_prices_after_timeout_cancel() {
+        let (mut contract, room_id) = setup();
+        contract.set_pricing_contract(room_id.clone(), Some("pricing.near".parse().unwrap()));
+        set_caller(&guest_id(), 2 * PRICE);
+        let booking_id = contract.next_booking_id;
+        let check_in_date = today().add_days(10);
+        contract.book_with_pricing(
+            room_id,
+            check_in_date,
+            check_in_date.add_days(1),
+            None,
+            None,
+            U128(2 * PRICE),
+        );
+
+        set_caller_at(&guest_id(), 0, noon(&today()) + NANOS_PER_HOUR);
+        contract.cancel_awaiting_payment(booking_id);
+        assert_eq!(transferred_to(&guest_id()), 2 * PRICE);
+
+        // 取り消した後に届いた掲出料では、予約を確定させない
+        testing_env!(
+            VMContextBuilder::new()
+                .current_account_id(contract_id())
+                .predecessor_account_id(contract_id())
+                .block_timestamp(noon(&today()))
+                .build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            HashMap::default(),
+            vec![PromiseResult::Successful(
+                serde_json::to_vec(&U128(PRICE)).unwrap()
+            )],
+        );
+        assert!(!contract.on_external_prices(booking_id, U128(2 * PRICE)));
+        assert_eq!(
+            contract.internal_booking(booking_id).status,
+            BookingStatus::Cancelled
+        );
+        assert_eq!(transferred_to(&guest_id()), 0);
+    }
+}
@@ -0,0 +1,144 @@
+// オーナーが発行するプロモーションコード
+// コードそのものは保存せず、SHA-256ハッシュのみを部屋に保存する
+
+use near_sdk::json_types::Base58CryptoHash;
+
+use crate::*;
+
+// プロモーションコードの割引の種類
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Discount {
+    Percent { percent: u8 }, // 掲出料の何%を割り引くか
+    Fixed { amount: U128 },  // 掲出料から割り引く額
+}
+
+// 部屋に保存されるプロモーションコード
+#[derive(Serialize, Debug, BorshSerialize, BorshDeserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PromoCode {
+    discount: Discount,        // 割引の内容
+    max_uses: u32,             // 全体で使える回数
+    max_uses_per_account: u32, // 1つのアカウントが使える回数
+    expires_at: u64,           // 有効期限（この日時以降は使えない）
+    uses: u32,                 // これまでに使われた回数
+    #[serde(skip)]
+    used_by: HashMap<AccountId, u32>, // アカウントごとの使われた回数
+}
+
+#[near_bindgen]
+impl Contract {
+    // プロモーションコードを発行する
+    // `code_hash`はコードのSHA-256ハッシュで、予約時にコードを指定すると掲出料が割り引かれる
+    // 部屋のデータが増えた分のストレージの代金は、預けている代金と添付されたNEARから支払う
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn add_promo_code(
+        &mut self,
+        room_id: RoomId,
+        code_hash: Base58CryptoHash,
+        discount: Discount,
+        max_uses: u32,
+        max_uses_per_account: u32,
+        expires_at: u64,
+    ) {
+        if let Discount::Percent { percent } = discount {
+            assert!(percent > 0 && percent <= 100, "ERR_INVALID_DISCOUNT");
+        }
+        assert!(
+            max_uses > 0 && max_uses_per_account > 0,
+            "ERR_INVALID_MAX_USES"
+        );
+        assert!(
+            expires_at > env::block_timestamp(),
+            "ERR_PROMO_CODE_EXPIRED"
+        );
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        let promo_code = PromoCode {
+            discount,
+            max_uses,
+            max_uses_per_account,
+            expires_at,
+            uses: 0,
+            used_by: HashMap::new(),
+        };
+        assert!(
+            room.promo_codes
+                .insert(code_hash.into(), promo_code)
+                .is_none(),
+            "ERR_PROMO_CODE_EXISTS"
+        );
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // プロモーションコードを削除する
+    // 部屋のオーナーのみがコールできる
+    pub fn remove_promo_code(&mut self, room_id: RoomId, code_hash: Base58CryptoHash) {
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        room.promo_codes
+            .remove(&CryptoHash::from(code_hash))
+            .expect("ERR_INVALID_PROMO_CODE");
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // プロモーションコードの内容と使われた回数を取得する
+    pub fn get_promo_code(
+        &self,
+        room_id: RoomId,
+        code_hash: Base58CryptoHash,
+    ) -> Option<&PromoCode> {
+        self.rooms_by_id
+            .get(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM")
+            .promo_codes
+            .get(&CryptoHash::from(code_hash))
+    }
+}
+
+impl Room {
+    // プロモーションコードを使い、掲出料から割り引く額を返す
+    // 有効期限を過ぎている、または使える回数を超えている場合はパニックする
+    pub(crate) fn redeem_promo_code(
+        &mut self,
+        code: &str,
+        guest_id: &AccountId,
+        amount: Balance,
+    ) -> Balance {
+        let code_hash: CryptoHash = env::sha256(code.as_bytes()).try_into().unwrap();
+        let promo_code = self
+            .promo_codes
+            .get_mut(&code_hash)
+            .expect("ERR_INVALID_PROMO_CODE");
+        assert!(
+            env::block_timestamp() < promo_code.expires_at,
+            "ERR_PROMO_CODE_EXPIRED"
+        );
+        assert!(
+            promo_code.uses < promo_code.max_uses,
+            "ERR_PROMO_CODE_USED_UP"
+        );
+        let used = promo_code.used_by.entry(guest_id.clone()).or_default();
+        assert!(
+            *used < promo_code.max_uses_per_account,
+            "ERR_PROMO_CODE_USED_UP"
+        );
+
+        *used += 1;
+        promo_code.uses += 1;
+        match &promo_code.discount {
+            Discount::Percent { percent } => amount * *percent as Balance / 100,
+            Discount::Fixed { amount: discount } => discount.0.min(amount),
+        }
+    }
+}
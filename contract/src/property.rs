@@ -0,0 +1,189 @@
+// 複数の部屋をまとめる施設
+// ホテルなどの施設に部屋を所属させ、施設ごとに部屋の一覧と空室の数を確認できる
+
+use crate::*;
+
+// 施設の名前と住所の最大の文字数
+const MAX_PROPERTY_TEXT_LENGTH: usize = 200;
+
+// 実際にブロックチェーン上に保存される施設のデータ
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Property {
+    name: String,                                    // 施設の名前
+    address: String,                                 // 施設の住所
+    pub(crate) owner_id: AccountId,                  // 施設のオーナーのアカウントID
+    room_ids: Vec<RoomId>,                           // 施設に所属する部屋のID
+    pub(crate) staff: HashMap<AccountId, StaffRole>, // 施設のスタッフ[アカウントID, 権限]
+}
+
+#[near_bindgen]
+impl Contract {
+    // 施設を登録し、施設のIDを返す
+    // 使用したストレージの代金は、預けている代金と添付されたNEARから支払う
+    #[payable]
+    pub fn add_property(&mut self, name: String, address: String) -> PropertyId {
+        assert_valid_property_text(&name, &address);
+        let owner_id = env::signer_account_id();
+        let property_id = self.next_property_id;
+        self.next_property_id += 1;
+
+        let initial_storage_usage = env::storage_usage();
+        self.properties.insert(
+            &property_id,
+            &Property {
+                name,
+                address,
+                owner_id: owner_id.clone(),
+                room_ids: Vec::new(),
+                staff: HashMap::new(),
+            },
+        );
+        let mut property_ids = self.properties_per_owner.get(&owner_id).unwrap_or_default();
+        property_ids.push(property_id);
+        self.properties_per_owner.insert(&owner_id, &property_ids);
+        self.internal_charge_storage(&owner_id, env::storage_usage() - initial_storage_usage);
+        property_id
+    }
+
+    // 施設の名前と住所を変更する
+    // 施設のオーナーのみがコールできる
+    pub fn update_property(&mut self, property_id: PropertyId, name: String, address: String) {
+        assert_valid_property_text(&name, &address);
+        let mut property = self.internal_owned_property(property_id);
+
+        property.name = name;
+        property.address = address;
+        self.properties.insert(&property_id, &property);
+    }
+
+    // 部屋を施設に所属させる
+    // `property_id`が指定されない場合は、所属している施設から外す
+    // 部屋と施設の両方のオーナーのみがコールできる
+    pub fn set_room_property(&mut self, room_id: RoomId, property_id: Option<PropertyId>) {
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        self.internal_remove_room_from_property(&room_id);
+        if let Some(property_id) = property_id {
+            self.internal_owned_property(property_id);
+            self.internal_add_room_to_property(property_id, &room_id);
+        }
+    }
+
+    // 施設のデータを取得する
+    pub fn get_property(&self, property_id: PropertyId) -> Option<Property> {
+        self.properties.get(&property_id)
+    }
+
+    // オーナーが登録した施設のIDを取得する
+    pub fn get_properties_by_owner(&self, owner_id: AccountId) -> Vec<PropertyId> {
+        self.properties_per_owner.get(&owner_id).unwrap_or_default()
+    }
+
+    // 施設に所属する、掲載中の部屋を取得する
+    pub fn get_property_rooms(
+        &self,
+        property_id: PropertyId,
+        lang: Option<String>,
+    ) -> Vec<AvailableRoom> {
+        let property = self
+            .properties
+            .get(&property_id)
+            .expect("ERR_NOT_FOUND_PROPERTY");
+        property
+            .room_ids
+            .iter()
+            .map(|room_id| (room_id, &self.rooms_by_id[room_id]))
+            .filter(|(_, room)| room.listed)
+            .map(|(room_id, room)| room.to_available_room(room_id, lang.as_deref()))
+            .collect()
+    }
+
+    // 指定された期間（`to_date`の前日まで）の、施設全体の日ごとの空きの数を取得する
+    // 空きの数は、施設に所属する部屋の予約可能な部屋（ベッド）の数の合計
+    pub fn get_property_availability(
+        &self,
+        property_id: PropertyId,
+        from_date: CheckInDate,
+        to_date: CheckInDate,
+    ) -> HashMap<CheckInDate, u32> {
+        let property = self
+            .properties
+            .get(&property_id)
+            .expect("ERR_NOT_FOUND_PROPERTY");
+        from_date.assert_not_past(&date::earliest_today());
+        let dates = date::nights(&from_date, &to_date);
+        assert!(dates.len() <= MAX_CALENDAR_DAYS, "ERR_DATE_RANGE_TOO_LONG");
+
+        dates
+            .into_iter()
+            .map(|date| {
+                let vacancies = property
+                    .room_ids
+                    .iter()
+                    .map(|room_id| &self.rooms_by_id[room_id])
+                    .filter(|room| date >= room.today() && room.is_available(&date))
+                    .map(|room| room.vacancies(&date, None) as u32)
+                    .sum();
+                (date, vacancies)
+            })
+            .collect()
+    }
+}
+
+impl Contract {
+    // 施設を取得し、コールしたアカウントが施設のオーナーであるかを確認する
+    pub(crate) fn internal_owned_property(&self, property_id: PropertyId) -> Property {
+        let property = self
+            .properties
+            .get(&property_id)
+            .expect("ERR_NOT_FOUND_PROPERTY");
+        assert_eq!(
+            env::signer_account_id(),
+            property.owner_id,
+            "ERR_NOT_PROPERTY_OWNER"
+        );
+        property
+    }
+
+    // 部屋を施設に所属させる
+    pub(crate) fn internal_add_room_to_property(
+        &mut self,
+        property_id: PropertyId,
+        room_id: &RoomId,
+    ) {
+        let mut property = self
+            .properties
+            .get(&property_id)
+            .expect("ERR_NOT_FOUND_PROPERTY");
+        property.room_ids.push(room_id.clone());
+        self.properties.insert(&property_id, &property);
+        self.rooms_by_id.get_mut(room_id).unwrap().property_id = Some(property_id);
+    }
+
+    // 部屋が所属している施設から部屋を外す
+    pub(crate) fn internal_remove_room_from_property(&mut self, room_id: &RoomId) {
+        let property_id = match self.rooms_by_id.get_mut(room_id) {
+            Some(room) => match room.property_id.take() {
+                Some(property_id) => property_id,
+                None => return,
+            },
+            None => return,
+        };
+        if let Some(mut property) = self.properties.get(&property_id) {
+            property.room_ids.retain(|id| id != room_id);
+            self.properties.insert(&property_id, &property);
+        }
+    }
+}
+
+// 施設の名前が空でなく、名前と住所が最大の文字数を超えていないかを確認する
+fn assert_valid_property_text(name: &str, address: &str) {
+    assert!(
+        !name.is_empty()
+            && name.chars().count() <= MAX_PROPERTY_TEXT_LENGTH
+            && address.chars().count() <= MAX_PROPERTY_TEXT_LENGTH,
+        "ERR_INVALID_PROPERTY"
+    );
+}
@@ -0,0 +1,44 @@
+// 返金可能な標準料金と、割安で返金不可の料金の2つの料金プラン
+
+use crate::*;
+
+// 予約時に選ぶ料金プラン
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum RatePlan {
+    Standard,      // キャンセルポリシーに基づいて返金される標準料金
+    NonRefundable, // 割安だが、キャンセルしても掲出料は返金されない料金
+}
+
+#[near_bindgen]
+impl Contract {
+    // 返金不可の料金プランの、標準料金からの割引率を変更する
+    // 0の場合は返金不可の料金プランを提供しない
+    // 部屋のオーナーのみがコールできる
+    pub fn set_non_refundable_rate(&mut self, room_id: RoomId, discount_percent: u8) {
+        assert!(discount_percent < 100, "ERR_INVALID_DISCOUNT");
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.non_refundable_discount_percent = discount_percent;
+    }
+}
+
+impl Room {
+    // 標準料金の掲出料を、料金プランに応じた掲出料に変換する
+    pub(crate) fn price_for_rate_plan(&self, rate_plan: &RatePlan, amount: Balance) -> Balance {
+        match rate_plan {
+            RatePlan::Standard => amount,
+            RatePlan::NonRefundable => {
+                assert!(
+                    self.non_refundable_discount_percent > 0,
+                    "ERR_NON_REFUNDABLE_RATE_NOT_OFFERED"
+                );
+                amount - amount * self.non_refundable_discount_percent as Balance / 100
+            }
+        }
+    }
+}
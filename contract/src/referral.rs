@@ -0,0 +1,68 @@
+// 紹介プログラム
+// 新しい予約者が紹介者を登録すると、初めて宿泊を終えた時に紹介者と予約者の両方に手数料から報酬が付与される
+
+use near_sdk::serde_json::json;
+
+use crate::event::emit_event;
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // コールしたアカウントを紹介したアカウントを登録する
+    // まだ予約をしたことがないアカウントのみが、一度だけ登録できる
+    pub fn register_referral(&mut self, referrer_id: AccountId) {
+        let referee_id = env::signer_account_id();
+        assert_ne!(referee_id, referrer_id, "ERR_SELF_REFERRAL");
+        assert!(
+            self.bookings_per_guest.get(&referee_id).is_none(),
+            "ERR_NOT_NEW_GUEST"
+        );
+        assert!(
+            self.referrers.insert(&referee_id, &referrer_id).is_none(),
+            "ERR_REFERRAL_ALREADY_REGISTERED"
+        );
+    }
+
+    // アカウントを紹介したアカウントを取得する
+    // 報酬が付与された後は`None`を返す
+    pub fn get_referrer(&self, account_id: AccountId) -> Option<AccountId> {
+        self.referrers.get(&account_id)
+    }
+
+    // 紹介者と予約者のそれぞれに付与する報酬（NEAR）を変更する
+    // 管理者のみがコールできる
+    pub fn set_referral_reward(&mut self, referral_reward: U128) {
+        self.assert_admin();
+        self.referral_reward = referral_reward.0;
+    }
+
+    // 紹介者と予約者のそれぞれに付与する報酬を取得する
+    pub fn get_referral_reward(&self) -> U128 {
+        U128(self.referral_reward)
+    }
+}
+
+impl Contract {
+    // 紹介された予約者が初めて宿泊を終えた時に、紹介者と予約者にクレジットとして報酬を付与する
+    // トレジャリーに貯まっているNEARの手数料が足りない場合は、報酬を付与せずに紹介を終了する
+    pub(crate) fn internal_reward_referral(&mut self, referee_id: &AccountId) {
+        let referrer_id = match self.referrers.remove(referee_id) {
+            Some(referrer_id) => referrer_id,
+            None => return,
+        };
+        let reward = self.referral_reward;
+        if reward == 0 || !self.internal_spend_treasury(&None, reward * 2) {
+            return;
+        }
+        self.internal_add_credits(&referrer_id, reward);
+        self.internal_add_credits(referee_id, reward);
+        emit_event(
+            "referral_rewarded",
+            json!({
+                "referrer_id": referrer_id,
+                "referee_id": referee_id,
+                "reward": U128(reward),
+            }),
+        );
+    }
+}
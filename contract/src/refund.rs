@@ -0,0 +1,144 @@
+// 送金に失敗した返金の再請求
+// 削除されたアカウントなどへの送金に失敗した返金は台帳に記録し、受取人が後から再請求できるようにする
+
+use near_sdk::serde_json::json;
+use near_sdk::{Gas, PromiseResult};
+
+use crate::ft_payment::transfer_to;
+use crate::*;
+
+// 送金の結果を確認するコールバックに割り当てるガス
+const GAS_FOR_ON_REFUND_TRANSFERRED: Gas = Gas(10_000_000_000_000);
+
+#[near_bindgen]
+impl Contract {
+    // 送金に失敗して再請求できる返金の額を取得する
+    // `token_id`が`None`の場合はNEARの額を返す
+    pub fn get_claimable_refund(&self, account_id: AccountId, token_id: Option<AccountId>) -> U128 {
+        U128(self.internal_claimable_refund(&account_id, &token_id))
+    }
+
+    // 送金に失敗した返金の全額を再請求する
+    // 再び送金に失敗した場合は、再請求できる返金として台帳に戻される
+    pub fn claim_refund(&mut self, token_id: Option<AccountId>) -> Promise {
+        let account_id = env::signer_account_id();
+        let amount = self.internal_claimable_refund(&account_id, &token_id);
+        assert!(amount > 0, "ERR_NO_CLAIMABLE_REFUND");
+        self.internal_set_claimable_refund(&account_id, &token_id, 0);
+        self.internal_send_refund(account_id, token_id, amount)
+    }
+
+    // 返金の送金結果を確認し、失敗していた場合は再請求できる返金として記録する
+    #[private]
+    pub fn on_refund_transferred(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: Option<AccountId>,
+        amount: U128,
+    ) -> bool {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return true;
+        }
+        let claimable = self.internal_claimable_refund(&receiver_id, &token_id);
+        self.internal_set_claimable_refund(&receiver_id, &token_id, claimable + amount.0);
+        false
+    }
+}
+
+impl Contract {
+    // 受取人にNEARまたはトークンを送金し、送金の結果をコールバックで確認する
+    pub(crate) fn internal_send_refund(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: Option<AccountId>,
+        amount: Balance,
+    ) -> Promise {
+        transfer_to(receiver_id.clone(), &token_id, amount).then(
+            Promise::new(env::current_account_id()).function_call(
+                "on_refund_transferred".to_string(),
+                json!({ "receiver_id": receiver_id, "token_id": token_id, "amount": U128(amount) })
+                    .to_string()
+                    .into_bytes(),
+                0,
+                GAS_FOR_ON_REFUND_TRANSFERRED,
+            ),
+        )
+    }
+
+    fn internal_claimable_refund(
+        &self,
+        account_id: &AccountId,
+        token_id: &Option<AccountId>,
+    ) -> Balance {
+        self.claimable_refunds
+            .get(account_id)
+            .and_then(|refunds| refunds.get(token_id).copied())
+            .unwrap_or(0)
+    }
+
+    fn internal_set_claimable_refund(
+        &mut self,
+        account_id: &AccountId,
+        token_id: &Option<AccountId>,
+        amount: Balance,
+    ) {
+        let mut refunds = self.claimable_refunds.get(account_id).unwrap_or_default();
+        if amount == 0 {
+            refunds.remove(token_id);
+        } else {
+            refunds.insert(token_id.clone(), amount);
+        }
+        if refunds.is_empty() {
+            self.claimable_refunds.remove(account_id);
+        } else {
+            self.claimable_refunds.insert(account_id, &refunds);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    // 予約者への返金の送金が失敗した結果をコールバックで受け取る
+    fn fail_refund(contract: &mut Contract, amount: Balance) -> bool {
+        testing_env!(
+            VMContextBuilder::new()
+                .current_account_id(contract_id())
+                .predecessor_account_id(contract_id())
+                .build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            HashMap::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_refund_transferred(guest_id(), None, U128(amount))
+    }
+
+    #[test]
+    fn records_failed_refund_and_sends_it_on_claim() {
+        let (mut contract, _) = setup();
+        assert!(!fail_refund(&mut contract, PRICE));
+        assert!(!fail_refund(&mut contract, PRICE));
+        assert_eq!(contract.get_claimable_refund(guest_id(), None).0, 2 * PRICE);
+
+        set_caller(&guest_id(), 0);
+        contract.claim_refund(None);
+        assert_eq!(transferred_to(&guest_id()), 2 * PRICE);
+        assert_eq!(contract.get_claimable_refund(guest_id(), None).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_CLAIMABLE_REFUND")]
+    fn rejects_claim_without_failed_refund() {
+        let (mut contract, _) = setup();
+        set_caller(&guest_id(), 0);
+        contract.claim_refund(None);
+    }
+}
@@ -0,0 +1,353 @@
+use crate::date::{NANOS_PER_DAY, NANOS_PER_HOUR, NANOS_PER_MINUTE};
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // 1回の予約の最小・最大宿泊数を変更する
+    // 部屋のオーナーのみがコールできる
+    pub fn set_stay_limits(&mut self, room_id: RoomId, min_nights: u16, max_nights: u16) {
+        assert_valid_stay_limits(min_nights, max_nights);
+
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.min_nights = min_nights;
+        room.max_nights = max_nights;
+    }
+
+    // 予約を受け付ける期間を変更する
+    // 部屋のオーナーのみがコールできる
+    pub fn set_booking_window(
+        &mut self,
+        room_id: RoomId,
+        booking_window_days: u16,
+        min_lead_time_hours: u16,
+    ) {
+        assert_valid_booking_window(booking_window_days, min_lead_time_hours);
+
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.booking_window_days = booking_window_days;
+        room.min_lead_time_hours = min_lead_time_hours;
+    }
+
+    // 私用やメンテナンスのため、指定された日付の予約を停止する
+    // 既に予約されている日付は指定できない
+    // 部屋のデータが増えた分のストレージの代金は、預けている代金と添付されたNEARから支払う
+    #[payable]
+    pub fn block_dates(&mut self, room_id: RoomId, dates: Vec<CheckInDate>) {
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        for date in dates {
+            assert!(!room.is_booked(&date), "ERR_ALREADY_BOOKED");
+            room.blocked_dates.insert(date);
+        }
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // 予約を停止していた日付を、再び予約できるようにする
+    pub fn unblock_dates(&mut self, room_id: RoomId, dates: Vec<CheckInDate>) {
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        for date in dates {
+            room.blocked_dates.remove(&date);
+        }
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // 予約を停止している日付を取得する
+    pub fn get_blocked_dates(&self, room_id: RoomId) -> Vec<CheckInDate> {
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        room.blocked_dates.iter().cloned().collect()
+    }
+
+    // 部屋を掲載するかを変更する
+    // 掲載していない部屋は検索結果に表示されず、新しい予約や仮押さえを受け付けない
+    // 既存の予約と予約履歴はそのまま残る
+    // 部屋のオーナーのみがコールできる
+    pub fn set_listing_status(&mut self, room_id: RoomId, listed: bool) {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.listed = listed;
+    }
+
+    // 当日予約を受け付ける時刻を変更する
+    // `24`を指定すると、当日中はいつでも予約を受け付ける
+    pub fn set_same_day_cutoff(&mut self, room_id: RoomId, same_day_cutoff_hour: u8) {
+        assert_valid_cutoff_hour(same_day_cutoff_hour);
+
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.same_day_cutoff_hour = same_day_cutoff_hour;
+    }
+
+    // 施設のタイムゾーン（UTCからの差分・分）を変更する
+    pub fn set_timezone(&mut self, room_id: RoomId, timezone_offset_minutes: i16) {
+        assert_valid_timezone(timezone_offset_minutes);
+
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.timezone_offset_minutes = timezone_offset_minutes;
+    }
+
+    // 予約の変更を無料で受け付ける期限（チェックインの何時間前まで）と、期限後の変更手数料を変更する
+    pub fn set_reschedule_policy(
+        &mut self,
+        room_id: RoomId,
+        free_reschedule_hours: u16,
+        reschedule_fee: U128,
+    ) {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.free_reschedule_hours = free_reschedule_hours;
+        room.reschedule_fee = reschedule_fee;
+    }
+}
+
+impl Contract {
+    // 部屋のデータを保存してオーナーの部屋の一覧に追加し、使用したストレージの代金を支払う
+    pub(crate) fn internal_register_room(&mut self, room_id: RoomId, room: Room) {
+        let owner_id = room.owner_id.clone();
+        let storage_bytes = self.internal_store_room(room_id, room);
+        self.internal_charge_storage(&owner_id, storage_bytes);
+    }
+
+    // 部屋のデータを保存してオーナーの部屋の一覧に追加し、使用したストレージのバイト数を返す
+    pub(crate) fn internal_store_room(&mut self, room_id: RoomId, room: Room) -> u64 {
+        let owner_id = room.owner_id.clone();
+        let tags = room.tags.clone();
+        let location = room.location.clone();
+
+        // 部屋のデータはコールの終了時にまとめて保存されるため、シリアライズしたサイズで数える
+        let initial_storage_usage = env::storage_usage();
+        let room_bytes =
+            (room_id.try_to_vec().unwrap().len() + room.try_to_vec().unwrap().len()) as u64;
+        self.rooms_by_id.insert(room_id.clone(), room);
+
+        // オーナーのアカウントIDと`RoomId`のVectorを紐付けて保存
+        let mut rooms = self.rooms_per_owner.get(&owner_id).unwrap_or_default();
+        rooms.push(room_id.clone());
+        self.assert_within_room_limit(&owner_id, rooms.len());
+        self.rooms_per_owner.insert(&owner_id, &rooms);
+        self.internal_update_tag_index(&room_id, &[], &tags);
+        self.internal_update_location_index(&room_id, None, Some(&location));
+
+        let storage_bytes = room_bytes + env::storage_usage() - initial_storage_usage;
+        self.rooms_by_id.get_mut(&room_id).unwrap().storage_bytes = storage_bytes;
+        storage_bytes
+    }
+
+    // オーナーの部屋の一覧から部屋を取り除く
+    pub(crate) fn internal_remove_room_from_owner(
+        &mut self,
+        owner_id: &AccountId,
+        room_id: &RoomId,
+    ) {
+        let mut rooms = self.rooms_per_owner.get(owner_id).unwrap_or_default();
+        rooms.retain(|id| id != room_id);
+        if rooms.is_empty() {
+            self.rooms_per_owner.remove(owner_id);
+        } else {
+            self.rooms_per_owner.insert(owner_id, &rooms);
+        }
+    }
+}
+
+impl Room {
+    // 部屋の一覧に表示するデータを生成する
+    // 名前と説明は、`lang`で指定された言語の翻訳があれば翻訳を使用する
+    pub(crate) fn to_available_room(&self, room_id: &RoomId, lang: Option<&str>) -> AvailableRoom {
+        AvailableRoom {
+            room_id: room_id.clone(),
+            owner_id: self.owner_id.clone(),
+            name: self.localized_name(lang),
+            slug: self.slug.clone(),
+            images: self.images.clone(),
+            cover_image_index: self.cover_image_index,
+            beds: self.beds,
+            description: self.localized_description(lang),
+            location: self.location.clone(),
+            price: self.price,
+            payment_token: self.payment_token.clone(),
+            usd_pricing: self.usd_pricing,
+            max_guests: self.max_guests,
+            attributes: self.attributes,
+            room_type: self.room_type,
+            amenities: self.amenities.clone(),
+            tags: self.tags.clone(),
+        }
+    }
+
+    // 指定された日付の予約をオーナーが停止しているか、メンテナンス期間に含まれるかを確認する
+    pub(crate) fn is_blocked(&self, date: &CheckInDate) -> bool {
+        self.blocked_dates.contains(date) || self.is_under_maintenance(date)
+    }
+
+    // 関数をコールしたアカウントが部屋のオーナーであるかを確認する
+    pub(crate) fn assert_owner(&self) {
+        assert_eq!(
+            env::signer_account_id(),
+            self.owner_id,
+            "ERR_NOT_ROOM_OWNER"
+        );
+    }
+
+    // 宿泊日が予約を受け付ける期間内であるかを確認する
+    pub(crate) fn assert_within_booking_window(&self, date: &CheckInDate) {
+        let (after_lead_time, within_window) = self.booking_window_status(date);
+        assert!(after_lead_time, "ERR_BOOKING_TOO_LATE");
+        assert!(within_window, "ERR_BOOKING_TOO_FAR_AHEAD");
+    }
+
+    // 宿泊日が予約を受け付ける期間内であるかを返す
+    pub(crate) fn is_within_booking_window(&self, date: &CheckInDate) -> bool {
+        self.booking_window_status(date) == (true, true)
+    }
+
+    // 予約の猶予時間を過ぎていないか、受付期間の上限より先でないかを返す
+    fn booking_window_status(&self, date: &CheckInDate) -> (bool, bool) {
+        let now = env::block_timestamp() as i64;
+        let check_in_at = self.local_start_timestamp(date);
+        // 過去の日付と当日予約の受付時刻は別途確認するため、猶予時間が0の場合は確認しない
+        let min_lead_time = (self.min_lead_time_hours as u64 * NANOS_PER_HOUR) as i64;
+        let after_lead_time = self.min_lead_time_hours == 0 || check_in_at >= now + min_lead_time;
+        let booking_window = (self.booking_window_days as u64 * NANOS_PER_DAY) as i64;
+        (after_lead_time, check_in_at <= now + booking_window)
+    }
+
+    // 予約の変更にかかる手数料を返す
+    // チェックインの`free_reschedule_hours`時間前までは無料で変更できる
+    pub(crate) fn reschedule_fee_for(&self, check_in_date: &CheckInDate) -> Balance {
+        let now = env::block_timestamp() as i64;
+        let deadline = self.local_start_timestamp(check_in_date)
+            - (self.free_reschedule_hours as u64 * NANOS_PER_HOUR) as i64;
+        if now <= deadline {
+            0
+        } else {
+            self.reschedule_fee.0
+        }
+    }
+
+    // 施設のタイムゾーンでの当日の日付を返す
+    pub(crate) fn today(&self) -> Date {
+        date::local_now(self.timezone_offset_minutes).0
+    }
+
+    // 施設のタイムゾーンでの日付の開始時刻を、UTCのタイムスタンプで返す
+    pub(crate) fn local_start_timestamp(&self, date: &CheckInDate) -> i64 {
+        let offset = self.timezone_offset_minutes as i64 * NANOS_PER_MINUTE as i64;
+        date.start_timestamp() as i64 - offset
+    }
+
+    // 当日予約の場合、受付時刻を過ぎていないかを確認する
+    pub(crate) fn assert_before_same_day_cutoff(&self, date: &CheckInDate) {
+        assert!(
+            self.is_before_same_day_cutoff(date),
+            "ERR_SAME_DAY_CUTOFF_PASSED"
+        );
+    }
+
+    // 当日予約の受付時刻を過ぎていないかを返す（当日以外の日付は常に`true`）
+    pub(crate) fn is_before_same_day_cutoff(&self, date: &CheckInDate) -> bool {
+        let (today, hour) = date::local_now(self.timezone_offset_minutes);
+        date != &today || hour < self.same_day_cutoff_hour
+    }
+
+    // 宿泊数が部屋の制限の範囲内であるかを確認する
+    pub(crate) fn assert_stay_length(&self, nights: usize) {
+        assert!(
+            nights >= self.min_nights as usize,
+            "ERR_STAY_SHORTER_THAN_MIN_NIGHTS"
+        );
+        assert!(
+            nights <= self.max_nights as usize,
+            "ERR_STAY_LONGER_THAN_MAX_NIGHTS"
+        );
+    }
+
+    // 宿泊数が部屋の制限の範囲内であるかを返す
+    pub(crate) fn allows_stay_length(&self, nights: usize) -> bool {
+        (self.min_nights as usize..=self.max_nights as usize).contains(&nights)
+    }
+}
+
+// 最小・最大宿泊数の組み合わせが正しいかを確認する
+pub(crate) fn assert_valid_stay_limits(min_nights: u16, max_nights: u16) {
+    assert!(
+        min_nights >= 1 && min_nights <= max_nights,
+        "ERR_INVALID_STAY_LIMITS"
+    );
+}
+
+// 当日予約の受付時刻が0〜24時の範囲であるかを確認する
+pub(crate) fn assert_valid_cutoff_hour(hour: u8) {
+    assert!(hour <= 24, "ERR_INVALID_CUTOFF_HOUR");
+}
+
+// 予約を受け付ける期間が1日以上で、チェックインの何時間前まで受け付けるかがその期間を超えないかを確認する
+pub(crate) fn assert_valid_booking_window(booking_window_days: u16, min_lead_time_hours: u16) {
+    assert!(
+        booking_window_days > 0 && min_lead_time_hours as u32 <= booking_window_days as u32 * 24,
+        "ERR_INVALID_BOOKING_WINDOW"
+    );
+}
+
+// タイムゾーンがUTC-12:00〜UTC+14:00の範囲であるかを確認する
+pub(crate) fn assert_valid_timezone(timezone_offset_minutes: i16) {
+    assert!(
+        (-12 * 60..=14 * 60).contains(&timezone_offset_minutes),
+        "ERR_INVALID_TIMEZONE"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_BOOKING_WINDOW")]
+    fn rejects_zero_booking_window() {
+        let (mut contract, room_id) = setup();
+        contract.set_booking_window(room_id, 0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_BOOKING_WINDOW")]
+    fn rejects_lead_time_beyond_booking_window() {
+        let (mut contract, room_id) = setup();
+        contract.set_booking_window(room_id, 7, 7 * 24 + 1);
+    }
+}
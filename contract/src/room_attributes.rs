@@ -0,0 +1,51 @@
+// ペット・喫煙・バリアフリーなどの部屋の属性
+// 属性を指定して、ペット可や車椅子対応の部屋を検索できる
+
+use crate::*;
+
+// 部屋の属性
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Default, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoomAttributes {
+    pets_allowed: bool,          // ペットを連れて宿泊できるか
+    smoking_allowed: bool,       // 喫煙できるか
+    wheelchair_accessible: bool, // 車椅子で利用できるか
+}
+
+#[near_bindgen]
+impl Contract {
+    // 部屋の属性を変更する
+    // 部屋のオーナーのみがコールできる
+    pub fn set_room_attributes(&mut self, room_id: RoomId, attributes: RoomAttributes) {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.attributes = attributes;
+    }
+
+    // 指定された属性に一致する、掲載中の部屋を取得する
+    // 指定されなかった属性は条件にしない
+    pub fn get_rooms_with_attributes(
+        &self,
+        pets_allowed: Option<bool>,
+        smoking_allowed: Option<bool>,
+        wheelchair_accessible: Option<bool>,
+        lang: Option<String>,
+    ) -> Vec<AvailableRoom> {
+        let matches = |expected: Option<bool>, actual: bool| expected.is_none_or(|e| e == actual);
+        self.rooms_by_id
+            .iter()
+            .filter(|(_, room)| {
+                let attributes = &room.attributes;
+                room.listed
+                    && matches(pets_allowed, attributes.pets_allowed)
+                    && matches(smoking_allowed, attributes.smoking_allowed)
+                    && matches(wheelchair_accessible, attributes.wheelchair_accessible)
+            })
+            .map(|(room_id, room)| room.to_available_room(room_id, lang.as_deref()))
+            .collect()
+    }
+}
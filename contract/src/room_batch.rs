@@ -0,0 +1,207 @@
+// 複数の部屋の一括登録
+// ホテルなどが多数の部屋を登録する際に、1回のトランザクションで登録し、ストレージの代金もまとめて支払う
+
+use crate::*;
+
+// 1回の`add_rooms`で登録できる部屋の最大数
+const MAX_BATCH_ROOMS: usize = 50;
+
+// 登録する部屋のデータ
+// 各項目は`add_room_to_owner`の引数と同じ
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoomInput {
+    pub name: String,
+    pub images: Vec<String>,
+    pub cover_image_index: u8,
+    pub beds: u8,
+    pub bookable_by_bed: bool,
+    pub units: u16,
+    pub description: String,
+    pub location: Location,
+    pub price: U128,
+    pub payment_token: Option<AccountId>,
+    pub usd_pricing: bool,
+    pub instant_book: bool,
+    pub approval_window_hours: u64,
+    pub min_nights: u16,
+    pub max_nights: u16,
+    pub booking_window_days: u16,
+    pub min_lead_time_hours: u16,
+    pub same_day_cutoff_hour: u8,
+    pub timezone_offset_minutes: i16,
+    pub security_deposit: U128,
+    pub deposit_release_days: u16,
+    pub overstay_grace_hours: u16,
+    pub overstay_penalty_per_day: U128,
+    pub free_reschedule_hours: u16,
+    pub reschedule_fee: U128,
+    pub cancellation_policy: CancellationPolicy,
+    pub cleaning_fee: U128,
+    pub extra_guest_fee: U128,
+    pub included_guests: u16,
+    pub max_guests: u16,
+    pub room_type: RoomType,
+    pub amenities: Vec<String>,
+}
+
+#[near_bindgen]
+impl Contract {
+    // 複数の部屋をまとめて登録し、登録した順に部屋のIDを返す
+    // 使用したストレージの代金は、全ての部屋の分をまとめて預けている代金と添付されたNEARから支払う
+    #[payable]
+    pub fn add_rooms(&mut self, rooms: Vec<RoomInput>) -> Vec<RoomId> {
+        assert!(
+            !rooms.is_empty() && rooms.len() <= MAX_BATCH_ROOMS,
+            "ERR_INVALID_ROOMS"
+        );
+        let owner_id = env::signer_account_id();
+
+        let mut storage_bytes = 0;
+        let room_ids = rooms
+            .into_iter()
+            .map(|input| {
+                let room_id = self.internal_next_room_id(&owner_id);
+                let room = self.internal_new_room(owner_id.clone(), input);
+                storage_bytes += self.internal_store_room(room_id.clone(), room);
+                room_id
+            })
+            .collect();
+        self.internal_charge_storage(&owner_id, storage_bytes);
+        room_ids
+    }
+}
+
+impl Contract {
+    // 登録する部屋のデータを確認し、保存する部屋のデータを生成する
+    pub(crate) fn internal_new_room(&self, owner_id: AccountId, input: RoomInput) -> Room {
+        let RoomInput {
+            name,
+            images,
+            cover_image_index,
+            beds,
+            bookable_by_bed,
+            units,
+            description,
+            location,
+            price,
+            payment_token,
+            usd_pricing,
+            instant_book,
+            approval_window_hours,
+            min_nights,
+            max_nights,
+            booking_window_days,
+            min_lead_time_hours,
+            same_day_cutoff_hour,
+            timezone_offset_minutes,
+            security_deposit,
+            deposit_release_days,
+            overstay_grace_hours,
+            overstay_penalty_per_day,
+            free_reschedule_hours,
+            reschedule_fee,
+            cancellation_policy,
+            cleaning_fee,
+            extra_guest_fee,
+            included_guests,
+            max_guests,
+            room_type,
+            amenities,
+        } = input;
+        let slug = slugify(&name);
+
+        assert!(beds > 0, "ERR_INVALID_BEDS");
+        assert!(units > 0, "ERR_INVALID_UNITS");
+        assert_valid_guest_limits(included_guests, max_guests);
+        assert_valid_images(&images, cover_image_index);
+        assert_valid_stay_limits(min_nights, max_nights);
+        assert_valid_booking_window(booking_window_days, min_lead_time_hours);
+        assert_valid_cutoff_hour(same_day_cutoff_hour);
+        assert_valid_timezone(timezone_offset_minutes);
+        assert_valid_cancellation_policy(&cancellation_policy);
+        self.assert_payment_token(&payment_token);
+        assert!(
+            !usd_pricing || payment_token.is_none(),
+            "ERR_USD_PRICED_ROOM_PAYS_IN_NEAR"
+        );
+
+        Room {
+            owner_id,
+            name,
+            images,
+            cover_image_index,
+            beds,
+            bookable_by_bed,
+            units,
+            description,
+            location: normalize_location(location),
+            price,
+            price_rules: Vec::new(),
+            date_prices: HashMap::new(),
+            weekly_discount_percent: 0,
+            monthly_discount_percent: 0,
+            last_minute_days: 0,
+            last_minute_discount_percent: 0,
+            early_bird_days: 0,
+            early_bird_discount_percent: 0,
+            pricing_contract_id: None,
+            surge_window_days: 0,
+            surge_threshold_percent: 0,
+            surge_percent: 0,
+            payment_token,
+            usd_pricing,
+            instant_book,
+            approval_window_hours,
+            min_nights,
+            max_nights,
+            booking_window_days,
+            min_lead_time_hours,
+            same_day_cutoff_hour,
+            timezone_offset_minutes,
+            security_deposit,
+            deposit_release_days,
+            overstay_grace_hours,
+            overstay_penalty_per_day,
+            free_reschedule_hours,
+            reschedule_fee,
+            cancellation_policy,
+            cleaning_fee,
+            extra_guest_fee,
+            included_guests,
+            max_guests,
+            house_rules: String::new(),
+            check_in_hour: 0,
+            check_out_hour: 0,
+            attributes: RoomAttributes::default(),
+            room_type,
+            amenities: normalize_amenities(amenities),
+            translations: HashMap::new(),
+            tags: Vec::new(),
+            installment_deposit_percent: 0,
+            installment_due_days: 0,
+            streaming_min_nights: 0,
+            payout_splits: Vec::new(),
+            booked_info: HashMap::new(),
+            booked_count: HashMap::new(),
+            stays: Vec::new(),
+            holds: HashMap::new(),
+            waitlists: HashMap::new(),
+            blocked_dates: HashSet::new(),
+            promo_codes: HashMap::new(),
+            auctions: HashMap::new(),
+            private_rates: HashMap::new(),
+            non_refundable_discount_percent: 0,
+            payment_window_hours: 0,
+            capture_on_check_in: false,
+            listed: true,
+            slug,
+            pending_owner_id: None,
+            property_id: None,
+            housekeeping_status: HousekeepingStatus::Ready,
+            maintenance: None,
+            shares: HashMap::new(),
+            storage_bytes: 0,
+        }
+    }
+}
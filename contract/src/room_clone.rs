@@ -0,0 +1,64 @@
+// 部屋の複製
+// 同じような部屋を複数持つオーナーが、既存の部屋の設定と料金ルールを引き継いで新しい部屋を登録できる
+
+use near_sdk::serde_json::json;
+
+use crate::event::emit_event;
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // 部屋の設定と料金ルールを複製し、新しい名前の部屋として登録して、新しい部屋のIDを返す
+    // 予約や仮押さえ、オークション、予約を停止した日付、プロモーションコード、特定のアカウント向けの掲出料、持分、
+    // 所有権の移転先、清掃状況、メンテナンス期間は引き継がない
+    // 日付ごとの掲出料は料金ルールとして引き継ぐ
+    // 使用したストレージの代金は、預けている代金と添付されたNEARから支払う
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn clone_room(&mut self, room_id: RoomId, new_name: String) -> RoomId {
+        assert!(!new_name.is_empty(), "ERR_INVALID_NAME");
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        assert!(
+            !self.exists(room.owner_id.clone(), new_name.clone()),
+            "ERR_ROOM_NAME_TAKEN"
+        );
+
+        let mut new_room = Room::try_from_slice(&room.try_to_vec().unwrap()).unwrap();
+        new_room.slug = slugify(&new_name);
+        new_room.name = new_name;
+        new_room.booked_info.clear();
+        new_room.booked_count.clear();
+        new_room.stays.clear();
+        new_room.holds.clear();
+        new_room.waitlists.clear();
+        new_room.blocked_dates.clear();
+        new_room.promo_codes.clear();
+        new_room.auctions.clear();
+        new_room.private_rates.clear();
+        new_room.shares.clear();
+        new_room.pending_owner_id = None;
+        new_room.housekeeping_status = HousekeepingStatus::Ready;
+        new_room.maintenance = None;
+        new_room.storage_bytes = 0;
+
+        let owner_id = new_room.owner_id.clone();
+        let property_id = new_room.property_id;
+        let new_room_id = self.internal_next_room_id(&owner_id);
+        self.internal_register_room(new_room_id.clone(), new_room);
+        // 複製した部屋は、元の部屋と同じ施設に所属させる
+        if let Some(property_id) = property_id {
+            self.internal_add_room_to_property(property_id, &new_room_id);
+        }
+
+        emit_event(
+            "room_cloned",
+            json!({
+                "room_id": room_id,
+                "new_room_id": new_room_id,
+                "owner_id": owner_id,
+            }),
+        );
+        new_room_id
+    }
+}
@@ -0,0 +1,53 @@
+// 登録済みの部屋の削除
+// 進行中の予約や預かり金が残っている部屋は削除できず、削除した部屋のストレージの代金はオーナーに返金する
+// 予約のデータは履歴として残す
+
+use near_sdk::serde_json::json;
+
+use crate::event::emit_event;
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // 部屋を削除し、部屋の登録で使用したストレージの代金をオーナーに返金する
+    // 承認待ち・確定済み・宿泊中の予約や、預かり金が残っている予約がある場合は削除できない
+    // 部屋のオーナーのみがコールできる
+    pub fn delete_room(&mut self, room_id: RoomId) {
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        assert!(room.auctions.is_empty(), "ERR_ROOM_HAS_AUCTIONS");
+        let has_active_bookings = self
+            .bookings_per_room
+            .get(&room_id)
+            .unwrap_or_default()
+            .iter()
+            .any(|booking_id| {
+                let booking = self.internal_booking(*booking_id);
+                let active = matches!(
+                    booking.status,
+                    BookingStatus::Pending
+                        | BookingStatus::Confirmed
+                        | BookingStatus::CheckedIn
+                        | BookingStatus::AwaitingPayment
+                );
+                active || self.escrow_by_booking.get(booking_id).unwrap_or(0) > 0
+            });
+        assert!(!has_active_bookings, "ERR_ROOM_HAS_ACTIVE_BOOKINGS");
+
+        self.internal_remove_room_from_property(&room_id);
+        let mut room = self.rooms_by_id.remove(&room_id).unwrap();
+        room.clear_holds();
+        self.internal_update_tag_index(&room_id, &room.tags, &[]);
+        self.internal_update_location_index(&room_id, Some(&room.location), None);
+        self.internal_remove_room_from_owner(&room.owner_id, &room_id);
+        self.internal_refund_storage(&room.owner_id, room.storage_bytes);
+
+        emit_event(
+            "room_deleted",
+            json!({
+                "room_id": room_id,
+                "owner_id": room.owner_id,
+            }),
+        );
+    }
+}
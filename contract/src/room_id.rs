@@ -0,0 +1,24 @@
+// 部屋のID
+// オーナーのアカウントIDと部屋の名前をつなげたIDでは、`alice.near`の`1room`と`alice.near1`の`room`が衝突する
+// アカウントIDに使えない`:`で区切り、コントラクト全体で連番の番号を付けることで、IDが重複しないようにする
+// 部屋の名前から生成した読みやすい識別子（スラッグ）は、IDとは別に保存する
+
+use crate::*;
+
+impl Contract {
+    // 新しい部屋のIDを`<オーナーのアカウントID>:<連番>`の形式で生成する
+    pub(crate) fn internal_next_room_id(&mut self, owner_id: &AccountId) -> RoomId {
+        let room_id = format!("{}:{}", owner_id, self.next_room_id);
+        self.next_room_id += 1;
+        room_id
+    }
+}
+
+// 部屋の名前から、英数字以外の文字を`-`に置き換えた小文字のスラッグを生成する
+pub(crate) fn slugify(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
@@ -0,0 +1,63 @@
+// タグや都市などのキーごとの部屋のIDの索引
+// 部屋の保存・変更・削除の際に更新し、キーに一致する部屋を全ての部屋を調べずに取得できるようにする
+
+use near_sdk::collections::UnorderedSet;
+
+use crate::*;
+
+pub(crate) type RoomIndex = LookupMap<String, UnorderedSet<RoomId>>;
+
+impl Contract {
+    // 索引のキーに一致する、掲載中の部屋を取得する
+    // `from_index`番目から最大`limit`件を返す
+    pub(crate) fn internal_indexed_rooms(
+        &self,
+        index: &RoomIndex,
+        key: &str,
+        from_index: u64,
+        limit: u64,
+        lang: Option<&str>,
+    ) -> Vec<AvailableRoom> {
+        let room_ids = match index.get(&key.to_string()) {
+            Some(room_ids) => room_ids,
+            None => return vec![],
+        };
+        room_ids
+            .iter()
+            .filter_map(|room_id| {
+                let room = &self.rooms_by_id[&room_id];
+                room.listed.then(|| room.to_available_room(&room_id, lang))
+            })
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+// キーの変更に合わせて、外れたキーの索引から部屋を取り除き、付いたキーの索引に部屋を追加する
+// キーごとの部屋のIDは、`prefix`とキーのハッシュをつなげたプレフィックスで保存する
+pub(crate) fn update_room_index(
+    index: &mut RoomIndex,
+    prefix: &[u8],
+    room_id: &RoomId,
+    old_keys: &[String],
+    new_keys: &[String],
+) {
+    for key in old_keys.iter().filter(|key| !new_keys.contains(key)) {
+        let mut room_ids = index.get(key).unwrap();
+        room_ids.remove(room_id);
+        // 部屋がなくなったキーは索引ごと削除する
+        if room_ids.is_empty() {
+            index.remove(key);
+        } else {
+            index.insert(key, &room_ids);
+        }
+    }
+    for key in new_keys.iter().filter(|key| !old_keys.contains(key)) {
+        let mut room_ids = index
+            .get(key)
+            .unwrap_or_else(|| UnorderedSet::new([prefix, &env::sha256(key.as_bytes())].concat()));
+        room_ids.insert(room_id);
+        index.insert(key, &room_ids);
+    }
+}
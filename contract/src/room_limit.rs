@@ -0,0 +1,53 @@
+// オーナーごとに登録できる部屋の数の上限
+// 1つのアカウントが大量の部屋を登録してストレージを圧迫したり、マーケットプレイスを荒らしたりすることを防ぐ
+// 確認済みの大規模な事業者は、上限を超えて登録できる
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // オーナーごとに登録できる部屋の数の上限を変更する
+    // 0の場合は上限を設けない
+    // 管理者のみがコールできる
+    pub fn set_max_rooms_per_owner(&mut self, max_rooms_per_owner: u32) {
+        self.assert_admin();
+        self.max_rooms_per_owner = max_rooms_per_owner;
+    }
+
+    // オーナーごとに登録できる部屋の数の上限を取得する
+    pub fn get_max_rooms_per_owner(&self) -> u32 {
+        self.max_rooms_per_owner
+    }
+
+    // 部屋の数の上限を適用しない、確認済みの事業者を追加する
+    // 管理者のみがコールできる
+    pub fn add_verified_operator(&mut self, account_id: AccountId) {
+        self.assert_admin();
+        self.verified_operators.insert(account_id);
+    }
+
+    // 確認済みの事業者から削除する
+    // 既に上限を超えて登録している部屋は削除されない
+    // 管理者のみがコールできる
+    pub fn remove_verified_operator(&mut self, account_id: AccountId) {
+        self.assert_admin();
+        self.verified_operators.remove(&account_id);
+    }
+
+    // アカウントが確認済みの事業者であるかを確認する
+    pub fn is_verified_operator(&self, account_id: AccountId) -> bool {
+        self.verified_operators.contains(&account_id)
+    }
+}
+
+impl Contract {
+    // オーナーの部屋の数が上限を超えていないかを確認する
+    pub(crate) fn assert_within_room_limit(&self, owner_id: &AccountId, rooms: usize) {
+        assert!(
+            self.max_rooms_per_owner == 0
+                || rooms <= self.max_rooms_per_owner as usize
+                || self.verified_operators.contains(owner_id),
+            "ERR_TOO_MANY_ROOMS"
+        );
+    }
+}
@@ -0,0 +1,100 @@
+// 部屋の持分
+// 部屋の経済的な所有権を持分に分け、オーナーに支払われる売上を持分に応じて保有者に分配する
+// 持分は保有者間で譲渡できる
+
+use near_sdk::serde_json::json;
+
+use crate::event::emit_event;
+use crate::*;
+
+// 1つの部屋の持分を保有できるアカウントの最大数
+const MAX_SHAREHOLDERS: usize = 20;
+
+#[near_bindgen]
+impl Contract {
+    // 部屋の持分を発行し、全ての持分をオーナーが保有する
+    // 発行は一度だけ行える
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn issue_room_shares(&mut self, room_id: RoomId, total_shares: u32) {
+        assert!(total_shares > 0, "ERR_INVALID_SHARES");
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        assert!(room.shares.is_empty(), "ERR_SHARES_ALREADY_ISSUED");
+
+        room.shares.insert(room.owner_id.clone(), total_shares);
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // 保有している部屋の持分を別のアカウントに譲渡する
+    pub fn transfer_room_shares(&mut self, room_id: RoomId, receiver_id: AccountId, shares: u32) {
+        let sender_id = env::signer_account_id();
+        assert_ne!(sender_id, receiver_id, "ERR_SELF_TRANSFER");
+        assert!(shares > 0, "ERR_INVALID_SHARES");
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        let balance = room.shares.get(&sender_id).copied().unwrap_or(0);
+        assert!(shares <= balance, "ERR_INSUFFICIENT_SHARES");
+
+        // 持分の保有者が増えた分のストレージの代金は、部屋のオーナーが預けている代金から支払う
+        let initial_size = room.serialized_size();
+        if shares == balance {
+            room.shares.remove(&sender_id);
+        } else {
+            room.shares.insert(sender_id.clone(), balance - shares);
+        }
+        *room.shares.entry(receiver_id.clone()).or_default() += shares;
+        assert!(
+            room.shares.len() <= MAX_SHAREHOLDERS,
+            "ERR_TOO_MANY_SHAREHOLDERS"
+        );
+        self.internal_track_room_storage(&room_id, initial_size);
+        emit_event(
+            "room_shares_transfer",
+            json!({
+                "room_id": room_id,
+                "sender_id": sender_id,
+                "receiver_id": receiver_id,
+                "shares": shares,
+            }),
+        );
+    }
+
+    // 部屋の持分の保有者と保有数を取得する
+    pub fn get_room_shares(&self, room_id: RoomId) -> HashMap<AccountId, u32> {
+        self.rooms_by_id
+            .get(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM")
+            .shares
+            .clone()
+    }
+}
+
+impl Room {
+    // オーナーが受け取る売上を、持分に応じて保有者ごとの額に分ける
+    // 持分が発行されていない場合はオーナーが全額を受け取り、端数もオーナーが受け取る
+    pub(crate) fn distribute_to_shareholders(&self, amount: Balance) -> Vec<(AccountId, Balance)> {
+        let total_shares: u64 = self.shares.values().map(|shares| *shares as u64).sum();
+        if total_shares == 0 {
+            return vec![(self.owner_id.clone(), amount)];
+        }
+        let mut payouts: Vec<(AccountId, Balance)> = self
+            .shares
+            .iter()
+            .filter(|(account_id, _)| **account_id != self.owner_id)
+            .map(|(account_id, shares)| {
+                let payout = amount * *shares as Balance / total_shares as Balance;
+                (account_id.clone(), payout)
+            })
+            .collect();
+        let distributed: Balance = payouts.iter().map(|(_, payout)| payout).sum();
+        payouts.push((self.owner_id.clone(), amount - distributed));
+        payouts
+    }
+}
@@ -0,0 +1,84 @@
+// 部屋の所有権の移転
+// 事業の売却などで部屋のオーナーが変わる際に、現在のオーナーが指定したアカウントが受け入れることで所有権を移す
+// 予約と予約履歴は部屋に残り、移転後の売上は新しいオーナーに支払われる
+
+use near_sdk::serde_json::json;
+
+use crate::event::emit_event;
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // 部屋の所有権の移転を申し込む
+    // `new_owner_id`が`accept_room_transfer`をコールすると移転が完了する
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn transfer_room(&mut self, room_id: RoomId, new_owner_id: AccountId) {
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        assert_ne!(room.owner_id, new_owner_id, "ERR_SELF_TRANSFER");
+
+        room.pending_owner_id = Some(new_owner_id);
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // 申し込んだ所有権の移転を取り消す
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn cancel_room_transfer(&mut self, room_id: RoomId) {
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.pending_owner_id = None;
+        self.internal_update_room_storage(&room_id, initial_usage);
+    }
+
+    // 申し込まれた所有権の移転を受け入れ、部屋のオーナーになる
+    // 売上の受取人の設定と所属する施設は引き継がず、部屋のストレージの代金は新しいオーナーが預けている代金と添付されたNEARから支払う
+    // 移転先に指定されたアカウントのみがコールできる
+    #[payable]
+    pub fn accept_room_transfer(&mut self, room_id: RoomId) {
+        let new_owner_id = env::signer_account_id();
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        assert_eq!(
+            room.pending_owner_id.as_ref(),
+            Some(&new_owner_id),
+            "ERR_NOT_PENDING_OWNER"
+        );
+
+        let old_owner_id = std::mem::replace(&mut room.owner_id, new_owner_id.clone());
+        room.pending_owner_id = None;
+        room.payout_splits.clear();
+        let storage_bytes = room.storage_bytes;
+
+        // 施設は以前のオーナーのものであるため、部屋を施設から外す
+        self.internal_remove_room_from_property(&room_id);
+        self.internal_remove_room_from_owner(&old_owner_id, &room_id);
+        let mut rooms = self.rooms_per_owner.get(&new_owner_id).unwrap_or_default();
+        rooms.push(room_id.clone());
+        self.assert_within_room_limit(&new_owner_id, rooms.len());
+        self.rooms_per_owner.insert(&new_owner_id, &rooms);
+        self.internal_refund_storage(&old_owner_id, storage_bytes);
+        self.internal_charge_storage(&new_owner_id, storage_bytes);
+
+        emit_event(
+            "room_transferred",
+            json!({
+                "room_id": room_id,
+                "old_owner_id": old_owner_id,
+                "new_owner_id": new_owner_id,
+            }),
+        );
+    }
+}
@@ -0,0 +1,33 @@
+// 部屋の種類
+// 説明文に書く代わりに部屋の種類を登録し、種類を指定して部屋を検索できる
+
+use crate::*;
+
+// 部屋の種類
+#[derive(
+    Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone, Copy,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum RoomType {
+    PrivateRoom, // 個室
+    EntirePlace, // 一棟貸し
+    SharedRoom,  // 相部屋
+    Suite,       // スイートルーム
+    Dorm,        // ドミトリー
+}
+
+#[near_bindgen]
+impl Contract {
+    // 指定された種類の、掲載中の部屋を取得する
+    pub fn get_rooms_by_type(
+        &self,
+        room_type: RoomType,
+        lang: Option<String>,
+    ) -> Vec<AvailableRoom> {
+        self.rooms_by_id
+            .iter()
+            .filter(|(_, room)| room.listed && room.room_type == room_type)
+            .map(|(room_id, room)| room.to_available_room(room_id, lang.as_deref()))
+            .collect()
+    }
+}
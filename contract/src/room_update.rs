@@ -0,0 +1,144 @@
+// 登録済みの部屋の編集
+// オーナーは部屋の名前や説明の誤りを直したり、掲出料や部屋数を変更したりできる
+// 名前を変更するとスラッグも変わるが、部屋のIDは変わらない
+
+use near_sdk::serde_json::json;
+
+use crate::event::emit_event;
+use crate::*;
+
+// `update_room`で変更する項目
+// 指定されなかった項目は変更しない
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UpdateRoomArgs {
+    pub name: Option<String>,
+    pub images: Option<Vec<String>>,
+    pub cover_image_index: Option<u8>,
+    pub description: Option<String>,
+    pub location: Option<Location>,
+    pub price: Option<U128>,
+    pub beds: Option<u8>,
+    pub bookable_by_bed: Option<bool>,
+    pub units: Option<u16>,
+    pub instant_book: Option<bool>,
+    pub approval_window_hours: Option<u64>,
+    pub room_type: Option<RoomType>,
+    pub amenities: Option<Vec<String>>,
+    pub house_rules: Option<String>,
+    pub check_in_hour: Option<u8>,
+    pub check_out_hour: Option<u8>,
+    pub attributes: Option<RoomAttributes>,
+}
+
+#[near_bindgen]
+impl Contract {
+    // 部屋のデータを変更し、インデクサー向けのイベントを発行する
+    // 掲出可能な数を、既に予約されている数より少なくすることはできない
+    // 部屋のデータが増えた分のストレージの代金は、預けている代金と添付されたNEARから支払う
+    // 部屋のオーナーと、施設のマネージャーがコールできる
+    #[payable]
+    pub fn update_room(&mut self, room_id: RoomId, args: UpdateRoomArgs) {
+        assert!(args.beds != Some(0), "ERR_INVALID_BEDS");
+        assert!(args.units != Some(0), "ERR_INVALID_UNITS");
+        assert!(args.name != Some(String::new()), "ERR_INVALID_NAME");
+
+        self.assert_room_operator(&room_id, EDIT_ROOM_ROLES);
+        let initial_usage = self.internal_room_storage_usage(&room_id);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        let owner_id = room.owner_id.clone();
+
+        if let Some(name) = &args.name {
+            room.name = name.clone();
+            room.slug = slugify(name);
+        }
+        if let Some(images) = &args.images {
+            room.images = images.clone();
+        }
+        if let Some(cover_image_index) = args.cover_image_index {
+            room.cover_image_index = cover_image_index;
+        }
+        // 画像だけを減らした場合にも、カバー画像の番号が範囲内であるかを確認する
+        assert_valid_images(&room.images, room.cover_image_index);
+        if let Some(description) = &args.description {
+            room.description = description.clone();
+        }
+        let old_location = args.location.as_ref().map(|location| {
+            std::mem::replace(&mut room.location, normalize_location(location.clone()))
+        });
+        if let Some(beds) = args.beds {
+            room.beds = beds;
+        }
+        if let Some(bookable_by_bed) = args.bookable_by_bed {
+            room.bookable_by_bed = bookable_by_bed;
+        }
+        if let Some(units) = args.units {
+            room.units = units;
+        }
+        if let Some(instant_book) = args.instant_book {
+            room.instant_book = instant_book;
+        }
+        if let Some(approval_window_hours) = args.approval_window_hours {
+            room.approval_window_hours = approval_window_hours;
+        }
+        if let Some(room_type) = args.room_type {
+            room.room_type = room_type;
+        }
+        if let Some(amenities) = &args.amenities {
+            room.amenities = normalize_amenities(amenities.clone());
+        }
+        if let Some(house_rules) = &args.house_rules {
+            room.house_rules = house_rules.clone();
+        }
+        if let Some(check_in_hour) = args.check_in_hour {
+            room.check_in_hour = check_in_hour;
+        }
+        if let Some(check_out_hour) = args.check_out_hour {
+            room.check_out_hour = check_out_hour;
+        }
+        if let Some(attributes) = args.attributes {
+            room.attributes = attributes;
+        }
+        assert_valid_house_rules(&room.house_rules, room.check_in_hour, room.check_out_hour);
+        room.assert_capacity_covers_bookings();
+        let old_price = room.price.0;
+        if let Some(price) = args.price {
+            room.price = price;
+        }
+        let new_price = room.price.0;
+        if let Some(old_location) = &old_location {
+            let new_location = self.rooms_by_id[&room_id].location.clone();
+            self.internal_update_location_index(&room_id, Some(old_location), Some(&new_location));
+        }
+        self.internal_record_price_change(&room_id, None, old_price, new_price);
+
+        // 部屋のデータと索引・掲出料の変更履歴のサイズの変化に応じて、使用しているストレージを調整する
+        self.internal_update_room_storage(&room_id, initial_usage);
+
+        emit_event(
+            "room_updated",
+            json!({
+                "room_id": room_id,
+                "owner_id": owner_id,
+                "changes": args,
+            }),
+        );
+    }
+}
+
+impl Room {
+    // 今日以降の全ての日付で、予約されている数が掲出可能な数を超えていないかを確認する
+    fn assert_capacity_covers_bookings(&self) {
+        let today = self.today();
+        let capacity = self.capacity();
+        assert!(
+            self.booked_count
+                .iter()
+                .all(|(date, count)| *date < today || *count <= capacity),
+            "ERR_CAPACITY_BELOW_BOOKINGS"
+        );
+    }
+}
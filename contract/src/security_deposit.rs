@@ -0,0 +1,128 @@
+// チェックアウト後の保証金の返金
+
+use crate::date::NANOS_PER_DAY;
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // 予約時に預かる保証金と、チェックアウトから保証金を返金するまでの日数を変更する
+    // 変更後の保証金は、これから作成される予約にのみ適用される
+    // 部屋のオーナーのみがコールできる
+    pub fn set_security_deposit(
+        &mut self,
+        room_id: RoomId,
+        security_deposit: U128,
+        deposit_release_days: u16,
+    ) {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.security_deposit = security_deposit;
+        room.deposit_release_days = deposit_release_days;
+    }
+
+    // 返金日を過ぎた保証金を予約者に返金する
+    // 精算されていない損害の請求がある場合は返金できない
+    // 誰でもコールできる
+    pub fn release_security_deposit(&mut self, booking_id: BookingId) -> U128 {
+        let booking = self.internal_booking(booking_id);
+        assert!(
+            booking.deposit_release_at > 0,
+            "ERR_NO_SECURITY_DEPOSIT_HELD"
+        );
+        assert!(
+            env::block_timestamp() >= booking.deposit_release_at,
+            "ERR_SECURITY_DEPOSIT_NOT_RELEASABLE"
+        );
+        assert!(!booking.has_open_claim(), "ERR_CLAIM_IN_PROGRESS");
+
+        U128(self.internal_release_security_deposit(booking_id))
+    }
+
+    // 返金を待っている保証金の予約IDを取得する
+    pub fn get_held_security_deposits(&self) -> Vec<BookingId> {
+        self.held_security_deposits.clone()
+    }
+}
+
+impl Contract {
+    // チェックアウトした予約の保証金を予約者に返す
+    // 返金までの日数が設定されている部屋では、その日数が経過するまでコントラクトが預かる
+    pub(crate) fn internal_return_security_deposit(
+        &mut self,
+        booking_id: BookingId,
+        refund: Balance,
+    ) {
+        if refund == 0 {
+            return;
+        }
+        let mut booking = self.internal_booking(booking_id);
+        let room = self
+            .rooms_by_id
+            .get(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        if room.deposit_release_days == 0 {
+            self.internal_release_escrow(booking_id, booking.guest_id, refund);
+            return;
+        }
+
+        booking.security_deposit = U128(refund);
+        booking.deposit_release_at =
+            env::block_timestamp() + room.deposit_release_days as u64 * NANOS_PER_DAY;
+        self.bookings_by_id.insert(&booking_id, &booking);
+        self.held_security_deposits.push(booking_id);
+    }
+
+    // 預かっている保証金を予約者に返金し、返金した額を返す
+    pub(crate) fn internal_release_security_deposit(&mut self, booking_id: BookingId) -> Balance {
+        let mut booking = self.internal_booking(booking_id);
+        let refund = booking.security_deposit.0;
+        booking.deposit_release_at = 0;
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+        self.held_security_deposits.retain(|id| *id != booking_id);
+
+        self.internal_release_escrow(booking_id, booking.guest_id, refund);
+        refund
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::NANOS_PER_DAY;
+    use crate::test_utils::*;
+
+    // 保証金が`PRICE`の部屋の今日から1泊の予約を作成し、チェックインしてチェックアウトする
+    fn stay_with_deposit(contract: &mut Contract, room_id: &RoomId) -> BookingId {
+        set_caller(&owner_id(), 0);
+        contract.set_security_deposit(room_id.clone(), U128(PRICE), 3);
+        let booking_id = book(contract, room_id, today(), 1, 2 * PRICE);
+        contract.check_in(booking_id);
+        contract.check_out(booking_id);
+        booking_id
+    }
+
+    #[test]
+    fn releases_security_deposit_after_release_days() {
+        let (mut contract, room_id) = setup();
+        let booking_id = stay_with_deposit(&mut contract, &room_id);
+        assert_eq!(contract.get_held_security_deposits(), vec![booking_id]);
+
+        set_caller_at(&other_guest_id(), 0, noon(&today()) + 3 * NANOS_PER_DAY);
+        assert_eq!(contract.release_security_deposit(booking_id).0, PRICE);
+        assert_eq!(transferred_to(&guest_id()), PRICE);
+        assert!(contract.get_held_security_deposits().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_SECURITY_DEPOSIT_NOT_RELEASABLE")]
+    fn rejects_release_before_release_days() {
+        let (mut contract, room_id) = setup();
+        let booking_id = stay_with_deposit(&mut contract, &room_id);
+        contract.release_security_deposit(booking_id);
+    }
+}
@@ -0,0 +1,79 @@
+// 施設のスタッフの権限
+// 施設のオーナーは、オーナーの鍵を渡さずに、他のアカウントに部屋の運営を任せられる
+// マネージャーは部屋の編集・予約リクエストの承認・チェックインを、フロントは予約リクエストの承認とチェックインを行える
+
+use crate::*;
+
+// 1つの施設に登録できるスタッフの最大数
+const MAX_STAFF: usize = 20;
+
+// スタッフの権限
+#[derive(
+    Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone, Copy,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum StaffRole {
+    Manager,   // 部屋の編集・予約リクエストの承認・チェックインを行える
+    FrontDesk, // 予約リクエストの承認・チェックインを行える
+}
+
+// 各操作を行えるスタッフの権限
+pub(crate) const EDIT_ROOM_ROLES: &[StaffRole] = &[StaffRole::Manager];
+pub(crate) const FRONT_DESK_ROLES: &[StaffRole] = &[StaffRole::Manager, StaffRole::FrontDesk];
+
+#[near_bindgen]
+impl Contract {
+    // アカウントに施設のスタッフの権限を付与する
+    // 既に権限を持つアカウントの場合は、権限を置き換える
+    // 施設のオーナーのみがコールできる
+    pub fn grant_staff_role(
+        &mut self,
+        property_id: PropertyId,
+        account_id: AccountId,
+        role: StaffRole,
+    ) {
+        let mut property = self.internal_owned_property(property_id);
+        assert_ne!(property.owner_id, account_id, "ERR_OWNER_CANNOT_BE_STAFF");
+
+        property.staff.insert(account_id, role);
+        assert!(property.staff.len() <= MAX_STAFF, "ERR_TOO_MANY_STAFF");
+        self.properties.insert(&property_id, &property);
+    }
+
+    // アカウントから施設のスタッフの権限を取り消す
+    // 施設のオーナーのみがコールできる
+    pub fn revoke_staff_role(&mut self, property_id: PropertyId, account_id: AccountId) {
+        let mut property = self.internal_owned_property(property_id);
+
+        property.staff.remove(&account_id);
+        self.properties.insert(&property_id, &property);
+    }
+
+    // 施設のスタッフと権限を取得する
+    pub fn get_property_staff(&self, property_id: PropertyId) -> HashMap<AccountId, StaffRole> {
+        self.properties
+            .get(&property_id)
+            .expect("ERR_NOT_FOUND_PROPERTY")
+            .staff
+    }
+}
+
+impl Contract {
+    // コールしたアカウントが、部屋のオーナーまたは指定された権限を持つ施設のスタッフであるかを確認する
+    pub(crate) fn assert_room_operator(&self, room_id: &RoomId, roles: &[StaffRole]) {
+        assert!(self.is_room_operator(room_id, roles), "ERR_NOT_ROOM_OWNER");
+    }
+
+    // コールしたアカウントが、部屋のオーナーまたは指定された権限を持つ施設のスタッフであるかを返す
+    pub(crate) fn is_room_operator(&self, room_id: &RoomId, roles: &[StaffRole]) -> bool {
+        let room = self.rooms_by_id.get(room_id).expect("ERR_NOT_FOUND_ROOM");
+        let signer_id = env::signer_account_id();
+        if signer_id == room.owner_id {
+            return true;
+        }
+        room.property_id
+            .and_then(|property_id| self.properties.get(&property_id))
+            .and_then(|property| property.staff.get(&signer_id).copied())
+            .is_some_and(|role| roles.contains(&role))
+    }
+}
@@ -0,0 +1,196 @@
+// オーナーの担保のステーキング
+// オーナーはNEARを担保として預け、確定済みの予約をキャンセルした場合や不履行の場合は、担保の一部が予約者への補償に充てられる
+
+use near_sdk::serde_json::json;
+
+use crate::date::NANOS_PER_DAY;
+use crate::event::emit_event;
+use crate::*;
+
+// 担保の引き出しを申請してから引き出せるようになるまでの期間（7日）
+const UNSTAKE_DELAY: u64 = 7 * NANOS_PER_DAY;
+
+// 没収率の上限（ベーシスポイント）
+const MAX_SLASH_BPS: u16 = 10_000;
+
+// オーナーが預けている担保
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct HostStake {
+    staked: U128,              // 預けている担保
+    unstaking: U128,           // 引き出しを申請中の担保（引き出せるまでは没収の対象になる）
+    unstake_available_at: u64, // 申請中の担保を引き出せるようになる日時
+}
+
+impl Default for HostStake {
+    fn default() -> Self {
+        Self {
+            staked: U128(0),
+            unstaking: U128(0),
+            unstake_available_at: 0,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // 添付したNEARを担保として預ける
+    #[payable]
+    pub fn stake_collateral(&mut self) -> HostStake {
+        let owner_id = env::signer_account_id();
+        let amount = env::attached_deposit();
+        assert!(amount > 0, "ERR_NO_STAKE_DEPOSIT");
+        let mut stake = self.host_stakes.get(&owner_id).unwrap_or_default();
+        stake.staked = U128(stake.staked.0 + amount);
+        self.host_stakes.insert(&owner_id, &stake);
+        stake
+    }
+
+    // 担保の引き出しを申請する
+    // 申請から7日後に`withdraw_collateral`で引き出せる。申請中に再度申請すると、期間は最後の申請から数え直す
+    pub fn unstake_collateral(&mut self, amount: U128) -> HostStake {
+        let owner_id = env::signer_account_id();
+        let mut stake = self.host_stakes.get(&owner_id).expect("ERR_NO_STAKE");
+        assert!(
+            amount.0 > 0 && amount.0 <= stake.staked.0,
+            "ERR_INSUFFICIENT_STAKE"
+        );
+        stake.staked = U128(stake.staked.0 - amount.0);
+        stake.unstaking = U128(stake.unstaking.0 + amount.0);
+        stake.unstake_available_at = env::block_timestamp() + UNSTAKE_DELAY;
+        self.host_stakes.insert(&owner_id, &stake);
+        stake
+    }
+
+    // 引き出しを申請した担保を引き出す
+    pub fn withdraw_collateral(&mut self) -> Promise {
+        let owner_id = env::signer_account_id();
+        let mut stake = self.host_stakes.get(&owner_id).expect("ERR_NO_STAKE");
+        assert!(stake.unstaking.0 > 0, "ERR_NOTHING_TO_WITHDRAW");
+        assert!(
+            env::block_timestamp() >= stake.unstake_available_at,
+            "ERR_UNSTAKE_NOT_AVAILABLE"
+        );
+        let amount = stake.unstaking.0;
+        stake.unstaking = U128(0);
+        self.internal_set_host_stake(&owner_id, stake);
+        self.internal_send_refund(owner_id, None, amount)
+    }
+
+    // オーナーが預けている担保を取得する
+    pub fn get_host_stake(&self, owner_id: AccountId) -> HostStake {
+        self.host_stakes.get(&owner_id).unwrap_or_default()
+    }
+
+    // オーナーのキャンセル・不履行の際に、予約の掲出料に対して没収する担保の割合（ベーシスポイント）を変更する
+    // 管理者のみがコールできる
+    pub fn set_slash_rate(&mut self, slash_bps: u16) {
+        self.assert_admin();
+        assert!(slash_bps <= MAX_SLASH_BPS, "ERR_INVALID_SLASH_RATE");
+        self.slash_bps = slash_bps;
+    }
+
+    // 担保を没収する割合を取得する
+    pub fn get_slash_rate(&self) -> u16 {
+        self.slash_bps
+    }
+
+    // 確定済みの予約をオーナーの都合でキャンセルする
+    // 預かり金の全額を予約者に返金し、担保の一部を没収して予約者への補償に充てる
+    // 部屋のオーナーのみがチェックイン前にコールできる
+    pub fn host_cancel_booking(&mut self, booking_id: BookingId) -> U128 {
+        let mut booking = self.internal_booking(booking_id);
+        self.rooms_by_id
+            .get(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM")
+            .assert_owner();
+        assert_eq!(
+            booking.status,
+            BookingStatus::Confirmed,
+            "ERR_BOOKING_NOT_CONFIRMED"
+        );
+
+        let refund = self.escrow_by_booking.get(&booking_id).unwrap_or(0);
+        self.internal_close_booking(&mut booking, BookingStatus::Cancelled);
+        self.internal_release_escrow(booking_id, booking.guest_id.clone(), refund);
+        U128(self.internal_slash_stake(&booking))
+    }
+}
+
+impl Contract {
+    // オーナーの担保から予約の掲出料に没収率を掛けた額を没収し、予約者に送金する
+    // 引き出しを申請中の担保も没収の対象とし、担保が足りない場合はある分だけを没収する
+    // 担保はNEARで預かるため、トークンで支払われた予約では没収しない
+    pub(crate) fn internal_slash_stake(&mut self, booking: &Booking) -> Balance {
+        if booking.payment_token.is_some() {
+            return 0;
+        }
+        let owner_id = self.rooms_by_id[&booking.room_id].owner_id.clone();
+        let mut stake = match self.host_stakes.get(&owner_id) {
+            Some(stake) => stake,
+            None => return 0,
+        };
+        let target = booking.amount.0 * self.slash_bps as Balance / MAX_SLASH_BPS as Balance;
+        let from_staked = target.min(stake.staked.0);
+        let from_unstaking = (target - from_staked).min(stake.unstaking.0);
+        let slashed = from_staked + from_unstaking;
+        if slashed == 0 {
+            return 0;
+        }
+        stake.staked = U128(stake.staked.0 - from_staked);
+        stake.unstaking = U128(stake.unstaking.0 - from_unstaking);
+        self.internal_set_host_stake(&owner_id, stake);
+
+        self.internal_send_refund(booking.guest_id.clone(), None, slashed);
+        emit_event(
+            "stake_slashed",
+            json!({
+                "owner_id": owner_id,
+                "booking_id": booking.booking_id,
+                "guest_id": booking.guest_id,
+                "amount": U128(slashed),
+            }),
+        );
+        slashed
+    }
+
+    fn internal_set_host_stake(&mut self, owner_id: &AccountId, stake: HostStake) {
+        if stake.staked.0 == 0 && stake.unstaking.0 == 0 {
+            self.host_stakes.remove(owner_id);
+        } else {
+            self.host_stakes.insert(owner_id, &stake);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    #[test]
+    fn slashes_stake_on_host_cancellation() {
+        let (mut contract, room_id) = setup();
+        set_caller(&owner_id(), PRICE);
+        contract.stake_collateral();
+        set_caller(&contract_id(), 0);
+        contract.set_slash_rate(2_000);
+        let booking_id = book(&mut contract, &room_id, today().add_days(10), 2, 2 * PRICE);
+
+        set_caller(&owner_id(), 0);
+        assert_eq!(contract.host_cancel_booking(booking_id).0, 2 * PRICE / 5);
+        assert_eq!(transferred_to(&guest_id()), 2 * PRICE + 2 * PRICE / 5);
+    }
+
+    #[test]
+    fn slashes_only_available_stake() {
+        let (mut contract, room_id) = setup();
+        set_caller(&owner_id(), PRICE / 10);
+        contract.stake_collateral();
+        set_caller(&contract_id(), 0);
+        contract.set_slash_rate(5_000);
+        let booking_id = book(&mut contract, &room_id, today().add_days(10), 1, PRICE);
+
+        set_caller(&owner_id(), 0);
+        assert_eq!(contract.host_cancel_booking(booking_id).0, PRICE / 10);
+    }
+}
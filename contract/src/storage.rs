@@ -0,0 +1,346 @@
+// NEP-145のストレージ管理
+// オーナーは部屋のデータが使うストレージの代金を前払いし、コントラクトのアカウントが負担しないようにする
+
+use crate::*;
+
+// ストレージの代金を預けるアカウントの登録に必要な最小のバイト数
+const MIN_STORAGE_BYTES: u64 = 200;
+
+// アカウントが預けているストレージの代金
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalance {
+    total: U128,     // 預けている代金の合計
+    available: U128, // 使用中のストレージの代金を除いた、引き出せる代金
+}
+
+// ストレージの代金として預けられる額の下限と上限
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalanceBounds {
+    min: U128,
+    max: Option<U128>,
+}
+
+// 実際にブロックチェーン上に保存される、アカウントごとのストレージの代金
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+pub struct StorageAccount {
+    deposit: Balance, // 預けている代金の合計
+    used_bytes: u64,  // 部屋のデータなどで使用中のバイト数
+}
+
+#[near_bindgen]
+impl Contract {
+    // ストレージの代金を預ける
+    // `account_id`が指定されない場合は、コールしたアカウントの代金として預ける
+    // `registration_only`が`true`の場合は、登録に必要な最小の額を超える分を返金する
+    #[payable]
+    pub fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let deposit = env::attached_deposit();
+        let min = storage_min_balance();
+        let mut account = self.storage_accounts.get(&account_id);
+
+        let refund = match account.as_mut() {
+            // 登録済みのアカウントは、登録のみの場合は全額を返金する
+            Some(_) if registration_only == Some(true) => deposit,
+            Some(account) => {
+                account.deposit += deposit;
+                0
+            }
+            None => {
+                assert!(deposit >= min, "ERR_INSUFFICIENT_STORAGE_DEPOSIT");
+                let (kept, refund) = if registration_only == Some(true) {
+                    (min, deposit - min)
+                } else {
+                    (deposit, 0)
+                };
+                account = Some(StorageAccount {
+                    deposit: kept,
+                    used_bytes: 0,
+                });
+                refund
+            }
+        };
+        let account = account.unwrap();
+        self.storage_accounts.insert(&account_id, &account);
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+        account.balance()
+    }
+
+    // 使用中のストレージの代金を除いた、預けている代金を引き出す
+    // `amount`が指定されない場合は、引き出せる全額を引き出す
+    // 1yoctoNEARを添付してコールする
+    #[payable]
+    pub fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        assert_eq!(env::attached_deposit(), 1, "ERR_REQUIRES_ONE_YOCTO");
+        let account_id = env::predecessor_account_id();
+        let mut account = self
+            .storage_accounts
+            .get(&account_id)
+            .expect("ERR_NOT_REGISTERED");
+        let available = account.available();
+        let amount = amount.map_or(available, |amount| amount.0);
+        assert!(amount <= available, "ERR_INSUFFICIENT_STORAGE_BALANCE");
+
+        account.deposit -= amount;
+        self.storage_accounts.insert(&account_id, &account);
+        if amount > 0 {
+            Promise::new(account_id).transfer(amount);
+        }
+        account.balance()
+    }
+
+    // アカウントが預けているストレージの代金を取得する
+    pub fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.storage_accounts
+            .get(&account_id)
+            .map(|account| account.balance())
+    }
+
+    // ストレージの代金として預けられる額の下限と上限を取得する
+    pub fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds {
+            min: U128(storage_min_balance()),
+            max: None,
+        }
+    }
+}
+
+impl Contract {
+    // 使用したストレージの代金を、預けている代金と添付されたNEARから支払う
+    // 預けている代金で足りない分を添付されたNEARから預け入れ、余った分は返金する
+    pub(crate) fn internal_charge_storage(&mut self, account_id: &AccountId, bytes: u64) {
+        let deposit = env::attached_deposit();
+        let mut account = self.storage_accounts.get(account_id).unwrap_or_default();
+        account.used_bytes += bytes;
+        let required = account.locked().saturating_sub(account.deposit);
+        assert!(deposit >= required, "ERR_INSUFFICIENT_STORAGE_DEPOSIT");
+
+        account.deposit += required;
+        self.storage_accounts.insert(account_id, &account);
+        if deposit > required {
+            Promise::new(env::predecessor_account_id()).transfer(deposit - required);
+        }
+    }
+
+    // 使用しなくなったストレージの分だけ、使用中のバイト数を減らす
+    // 減らした分の代金は`storage_withdraw`で引き出せるようになる
+    pub(crate) fn internal_release_storage(&mut self, account_id: &AccountId, bytes: u64) {
+        if let Some(mut account) = self.storage_accounts.get(account_id) {
+            account.used_bytes = account.used_bytes.saturating_sub(bytes);
+            self.storage_accounts.insert(account_id, &account);
+        }
+    }
+
+    // 使用しなくなったストレージの代金を、預けている代金からアカウントに返金する
+    // 登録に必要な最小の額は返金しない
+    pub(crate) fn internal_refund_storage(&mut self, account_id: &AccountId, bytes: u64) {
+        let mut account = match self.storage_accounts.get(account_id) {
+            Some(account) => account,
+            None => return,
+        };
+        account.used_bytes = account.used_bytes.saturating_sub(bytes);
+        let refund = account
+            .available()
+            .min(bytes as Balance * env::storage_byte_cost());
+        account.deposit -= refund;
+        self.storage_accounts.insert(account_id, &account);
+        if refund > 0 {
+            Promise::new(account_id.clone()).transfer(refund);
+        }
+    }
+
+    // 部屋のデータと、コール中に書き込んだ索引や掲出料の変更履歴のサイズの合計を返す
+    // 部屋のデータはコールの終了時にまとめて保存されるため、シリアライズしたサイズで数える
+    pub(crate) fn internal_room_storage_usage(&self, room_id: &RoomId) -> u64 {
+        let room = self.rooms_by_id.get(room_id).expect("ERR_NOT_FOUND_ROOM");
+        room.serialized_size() + env::storage_usage()
+    }
+
+    // オーナーによる部屋の変更で増減したストレージを、オーナーが使用しているストレージに反映する
+    // `initial_usage`は変更前の`internal_room_storage_usage`の値
+    // 増えた分の代金は、預けている代金と添付されたNEARから支払う
+    // 増えなかった場合は、添付されたNEARを全額返金する
+    pub(crate) fn internal_update_room_storage(&mut self, room_id: &RoomId, initial_usage: u64) {
+        let usage = self.internal_room_storage_usage(room_id);
+        let owner_id = self.internal_resize_room_storage(room_id, initial_usage, usage);
+        if usage > initial_usage {
+            self.internal_charge_storage(&owner_id, usage - initial_usage);
+        } else {
+            self.internal_release_storage(&owner_id, initial_usage - usage);
+            let deposit = env::attached_deposit();
+            if deposit > 0 {
+                Promise::new(env::predecessor_account_id()).transfer(deposit);
+            }
+        }
+    }
+
+    // 予約や仮押さえなど、オーナー以外のコールで増減した部屋のデータのサイズを、オーナーが使用しているストレージに反映する
+    // `initial_size`は変更前の`Room::serialized_size`の値
+    // 添付されたNEARは予約の支払いなどに使うため、増えた分は使用中のバイト数に加えるだけにし、
+    // 預けている代金で足りない分は、オーナーが次にストレージを使う際に支払う
+    pub(crate) fn internal_track_room_storage(&mut self, room_id: &RoomId, initial_size: u64) {
+        let size = self.rooms_by_id[room_id].serialized_size();
+        let owner_id = self.internal_resize_room_storage(room_id, initial_size, size);
+        let mut account = self.storage_accounts.get(&owner_id).unwrap_or_default();
+        account.used_bytes = (account.used_bytes + size).saturating_sub(initial_size);
+        self.storage_accounts.insert(&owner_id, &account);
+    }
+
+    // 部屋が使用しているストレージのバイト数を更新し、オーナーのアカウントIDを返す
+    fn internal_resize_room_storage(
+        &mut self,
+        room_id: &RoomId,
+        initial: u64,
+        current: u64,
+    ) -> AccountId {
+        let room = self.rooms_by_id.get_mut(room_id).unwrap();
+        room.storage_bytes = (room.storage_bytes + current).saturating_sub(initial);
+        room.owner_id.clone()
+    }
+}
+
+impl Room {
+    // 部屋のデータをシリアライズしたサイズを返す
+    pub(crate) fn serialized_size(&self) -> u64 {
+        self.try_to_vec().unwrap().len() as u64
+    }
+}
+
+impl StorageAccount {
+    // 使用中のストレージの代金を除いた、引き出せる代金を返す
+    // 登録に必要な最小の額は引き出せない
+    fn available(&self) -> Balance {
+        self.deposit.saturating_sub(self.locked())
+    }
+
+    // 使用中のストレージの代金を返す
+    // 登録に必要な最小の額を下回ることはない
+    fn locked(&self) -> Balance {
+        (self.used_bytes as Balance * env::storage_byte_cost()).max(storage_min_balance())
+    }
+
+    fn balance(&self) -> StorageBalance {
+        StorageBalance {
+            total: U128(self.deposit),
+            available: U128(self.available()),
+        }
+    }
+}
+
+// アカウントの登録に必要な最小の代金を返す
+fn storage_min_balance() -> Balance {
+    MIN_STORAGE_BYTES as Balance * env::storage_byte_cost()
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::ONE_NEAR;
+
+    use super::*;
+    use crate::room_update::UpdateRoomArgs;
+    use crate::test_utils::*;
+
+    fn used_bytes(contract: &Contract) -> u64 {
+        contract
+            .storage_accounts
+            .get(&owner_id())
+            .unwrap()
+            .used_bytes
+    }
+
+    #[test]
+    fn charges_and_releases_storage_for_blocked_dates() {
+        let (mut contract, room_id) = setup();
+        let initial_bytes = used_bytes(&contract);
+        let room_bytes = contract.rooms_by_id[&room_id].storage_bytes;
+        let dates: Vec<CheckInDate> = (10..20).map(|days| today().add_days(days)).collect();
+
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.block_dates(room_id.clone(), dates.clone());
+        let added = used_bytes(&contract) - initial_bytes;
+        assert!(added > 0);
+        assert_eq!(
+            contract.rooms_by_id[&room_id].storage_bytes,
+            room_bytes + added
+        );
+
+        set_caller(&owner_id(), 0);
+        contract.unblock_dates(room_id.clone(), dates);
+        assert_eq!(used_bytes(&contract), initial_bytes);
+        assert_eq!(contract.rooms_by_id[&room_id].storage_bytes, room_bytes);
+    }
+
+    #[test]
+    fn refunds_deposit_when_update_shrinks_room() {
+        let (mut contract, room_id) = setup();
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.update_room(
+            room_id,
+            UpdateRoomArgs {
+                description: Some(String::new()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(transferred_to(&owner_id()), ONE_NEAR);
+    }
+
+    #[test]
+    fn counts_price_history_in_room_update_storage() {
+        let (mut contract, room_id) = setup();
+        let room_bytes = contract.rooms_by_id[&room_id].storage_bytes;
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.update_room(
+            room_id.clone(),
+            UpdateRoomArgs {
+                price: Some(U128(2 * PRICE)),
+                ..Default::default()
+            },
+        );
+        assert!(contract.rooms_by_id[&room_id].storage_bytes > room_bytes);
+    }
+
+    #[test]
+    fn charges_storage_for_house_rules() {
+        let (mut contract, room_id) = setup();
+        let initial_bytes = used_bytes(&contract);
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.set_house_rules(room_id.clone(), "no smoking".repeat(10), 15, 10);
+        assert!(used_bytes(&contract) > initial_bytes);
+
+        set_caller(&owner_id(), 0);
+        contract.set_house_rules(room_id, String::new(), 15, 10);
+        assert_eq!(used_bytes(&contract), initial_bytes);
+    }
+
+    #[test]
+    fn tracks_storage_for_share_transfers() {
+        let (mut contract, room_id) = setup();
+        set_caller(&owner_id(), ONE_NEAR);
+        contract.issue_room_shares(room_id.clone(), 100);
+        let initial_bytes = used_bytes(&contract);
+
+        set_caller(&owner_id(), 0);
+        contract.transfer_room_shares(room_id, guest_id(), 10);
+        assert!(used_bytes(&contract) > initial_bytes);
+    }
+
+    #[test]
+    fn tracks_storage_for_booked_nights() {
+        let (mut contract, room_id) = setup();
+        let initial_bytes = used_bytes(&contract);
+
+        let booking_id = book(&mut contract, &room_id, today().add_days(10), 3, 3 * PRICE);
+        assert!(used_bytes(&contract) > initial_bytes);
+
+        contract.cancel_booking(booking_id);
+        assert_eq!(used_bytes(&contract), initial_bytes);
+    }
+}
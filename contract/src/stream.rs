@@ -0,0 +1,156 @@
+// 長期滞在の掲出料のストリーミング払い
+// 宿泊中は経過した時間に応じて掲出料がオーナーに流れ、早期にチェックアウトした場合は流れていない分を予約者に返金する
+// Roketoなどの外部のストリーミング払いのコントラクトは使わず、このコントラクトの預かり金から経過時間に応じて支払う
+// 外部のコントラクトはトークンしか扱えずNEARのラップが必要になり、資金を移すと手数料・税・売上の分配や、
+// 保証金・損害の請求・保険の補償・担保の没収を預かり金から精算できなくなるため
+
+use crate::date::NANOS_PER_SECOND;
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // ストリーミング払いにする宿泊の最小宿泊数を変更する
+    // 0の場合はストリーミング払いを行わない
+    // 変更後の設定は、これからチェックインする予約に適用される
+    // 部屋のオーナーのみがコールできる
+    pub fn set_streaming_policy(&mut self, room_id: RoomId, streaming_min_nights: u16) {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+
+        room.streaming_min_nights = streaming_min_nights;
+    }
+
+    // 宿泊中の予約で、オーナーが受け取れる掲出料を取得する
+    pub fn get_streamable(&self, booking_id: BookingId) -> U128 {
+        let booking = self.internal_booking(booking_id);
+        let room = self
+            .rooms_by_id
+            .get(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        U128(room.streamable(&booking))
+    }
+
+    // 宿泊中の予約の経過した時間分の掲出料を、オーナーの売上として記録する
+    // 部屋のオーナーのみがコールできる
+    pub fn withdraw_stream(&mut self, booking_id: BookingId) -> U128 {
+        let mut booking = self.internal_booking(booking_id);
+        let room = self
+            .rooms_by_id
+            .get(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        assert_eq!(
+            booking.status,
+            BookingStatus::CheckedIn,
+            "ERR_NOT_FOUND_STAY"
+        );
+        let streamable = room.streamable(&booking);
+        assert!(streamable > 0, "ERR_NOTHING_STREAMED");
+
+        booking.streamed = U128(booking.streamed.0 + streamable);
+        self.bookings_by_id.insert(&booking_id, &booking);
+        self.internal_pay_owner(booking_id, streamable);
+        U128(streamable)
+    }
+
+    // ストリーミング払いの宿泊を、チェックアウト日より前に終了する
+    // 経過した時間分の掲出料と追加料金はオーナーの売上に記録され、残りの掲出料と保証金は予約者に返金される
+    // 予約したアカウントのみがコールできる
+    pub fn check_out_early(&mut self, booking_id: BookingId) -> U128 {
+        let booking = self.internal_booking(booking_id);
+        assert_eq!(
+            env::signer_account_id(),
+            booking.guest_id,
+            "ERR_NOT_BOOKING_GUEST"
+        );
+        assert_eq!(
+            booking.status,
+            BookingStatus::CheckedIn,
+            "ERR_NOT_FOUND_STAY"
+        );
+        assert!(booking.stream_started_at > 0, "ERR_NOT_STREAMING");
+        let room = &self.rooms_by_id[&booking.room_id];
+        assert!(
+            room.today() < booking.check_out_date,
+            "ERR_CHECK_OUT_DATE_REACHED"
+        );
+
+        let streamable = room.streamable(&booking);
+        let unstreamed = booking.amount.0 - booking.streamed.0 - streamable;
+        let (payment, security_deposit) = self.internal_finish_stay(booking_id);
+        self.internal_return_security_deposit(booking_id, security_deposit);
+        if unstreamed > 0 {
+            self.internal_release_escrow(booking_id, booking.guest_id, unstreamed);
+        }
+        self.internal_pay_owner(booking_id, payment - unstreamed);
+        U128(unstreamed)
+    }
+}
+
+impl Room {
+    // チェックイン時にストリーミング払いを始めるかを確認する
+    pub(crate) fn streams(&self, booking: &Booking) -> bool {
+        let nights = date::nights(&booking.check_in_date, &booking.check_out_date).len();
+        self.streaming_min_nights > 0 && nights >= self.streaming_min_nights as usize
+    }
+
+    // チェックインからチェックアウト日の開始時刻までの経過時間に応じて、まだ受け取っていない掲出料を返す
+    pub(crate) fn streamable(&self, booking: &Booking) -> Balance {
+        if booking.stream_started_at == 0 || booking.status != BookingStatus::CheckedIn {
+            return 0;
+        }
+        let started_at = booking.stream_started_at as i64;
+        let ends_at = self
+            .local_start_timestamp(&booking.check_out_date)
+            .max(started_at + 1);
+        let elapsed = (env::block_timestamp() as i64).clamp(started_at, ends_at) - started_at;
+        // 1か月以上の宿泊でも掛け算があふれないように、経過時間は秒単位で按分する
+        let elapsed_seconds = (elapsed as u64 / NANOS_PER_SECOND) as Balance;
+        let total_seconds = ((ends_at - started_at) as u64 / NANOS_PER_SECOND).max(1) as Balance;
+        let streamed = booking.amount.0 * elapsed_seconds / total_seconds;
+        streamed.saturating_sub(booking.streamed.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::NANOS_PER_DAY;
+    use crate::test_utils::*;
+
+    // 30泊以上をストリーミング払いにする部屋で、今日から30泊を予約してチェックインする
+    fn check_in_month_long_stay(contract: &mut Contract, room_id: &RoomId) -> BookingId {
+        set_caller(&owner_id(), 0);
+        contract.set_streaming_policy(room_id.clone(), 30);
+        let booking_id = book(contract, room_id, today(), 30, 30 * PRICE);
+        contract.check_in(booking_id);
+        booking_id
+    }
+
+    #[test]
+    fn streams_month_long_stay_by_elapsed_time() {
+        let (mut contract, room_id) = setup();
+        let booking_id = check_in_month_long_stay(&mut contract, &room_id);
+
+        set_caller_at(&owner_id(), 0, noon(&today()) + 10 * NANOS_PER_DAY);
+        let streamed = contract.withdraw_stream(booking_id).0;
+        // チェックインからチェックアウト日の開始時刻までの29.5日のうち10日分
+        assert_eq!(streamed, 30 * PRICE * 20 / 59);
+        assert_eq!(earnings_of(&contract, &owner_id()), streamed);
+    }
+
+    #[test]
+    fn refunds_unstreamed_amount_on_early_check_out() {
+        let (mut contract, room_id) = setup();
+        let booking_id = check_in_month_long_stay(&mut contract, &room_id);
+
+        set_caller_at(&guest_id(), 0, noon(&today()) + 10 * NANOS_PER_DAY);
+        let unstreamed = contract.check_out_early(booking_id).0;
+        assert_eq!(unstreamed, 30 * PRICE - 30 * PRICE * 20 / 59);
+        assert_eq!(transferred_to(&guest_id()), unstreamed);
+        assert_eq!(earnings_of(&contract, &owner_id()), 30 * PRICE - unstreamed);
+    }
+}
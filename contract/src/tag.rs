@@ -0,0 +1,89 @@
+// 部屋のタグ（「beachfront」「workspace」など）
+// タグごとに部屋のIDの索引を保存し、テーマ別の一覧を全ての部屋を調べずに取得できるようにする
+
+use crate::room_index::update_room_index;
+use crate::*;
+
+// 1つの部屋に登録できるタグの最大数
+const MAX_TAGS: usize = 10;
+// タグの最大の長さ
+const MAX_TAG_LENGTH: usize = 32;
+
+#[near_bindgen]
+impl Contract {
+    // 部屋のタグを変更し、タグの索引を更新する
+    // 部屋のデータと索引が増えた分のストレージの代金は、預けている代金と添付されたNEARから支払う
+    // 部屋のオーナーのみがコールできる
+    #[payable]
+    pub fn set_room_tags(&mut self, room_id: RoomId, tags: Vec<String>) {
+        let tags = normalize_tags(tags);
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        room.assert_owner();
+        let owner_id = room.owner_id.clone();
+        let old_tags = std::mem::replace(&mut room.tags, tags.clone());
+
+        // 部屋のデータはコールの終了時にまとめて保存されるため、タグをシリアライズしたサイズで数える
+        let initial_bytes = env::storage_usage() + old_tags.try_to_vec().unwrap().len() as u64;
+        self.internal_update_tag_index(&room_id, &old_tags, &tags);
+        let bytes = env::storage_usage() + tags.try_to_vec().unwrap().len() as u64;
+
+        let room = self.rooms_by_id.get_mut(&room_id).unwrap();
+        room.storage_bytes = (room.storage_bytes + bytes).saturating_sub(initial_bytes);
+        if bytes > initial_bytes {
+            self.internal_charge_storage(&owner_id, bytes - initial_bytes);
+        } else {
+            self.internal_release_storage(&owner_id, initial_bytes - bytes);
+        }
+    }
+
+    // 指定されたタグが付いた、掲載中の部屋を取得する
+    // `from_index`番目から最大`limit`件を返す
+    pub fn get_rooms_by_tag(
+        &self,
+        tag: String,
+        from_index: u64,
+        limit: u64,
+        lang: Option<String>,
+    ) -> Vec<AvailableRoom> {
+        self.internal_indexed_rooms(
+            &self.rooms_by_tag,
+            &tag.to_lowercase(),
+            from_index,
+            limit,
+            lang.as_deref(),
+        )
+    }
+}
+
+impl Contract {
+    // タグの変更に合わせて、タグの索引を更新する
+    pub(crate) fn internal_update_tag_index(
+        &mut self,
+        room_id: &RoomId,
+        old_tags: &[String],
+        new_tags: &[String],
+    ) {
+        update_room_index(&mut self.rooms_by_tag, b"d", room_id, old_tags, new_tags);
+    }
+}
+
+// タグを小文字にそろえ、数と長さを確認し、重複を除いて並べ替える
+// タグに使用できるのは、文字・数字と`-`のみ
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut tags: Vec<String> = tags.iter().map(|tag| tag.to_lowercase()).collect();
+    tags.sort();
+    tags.dedup();
+    assert!(tags.len() <= MAX_TAGS, "ERR_TOO_MANY_TAGS");
+    assert!(
+        tags.iter().all(|tag| {
+            !tag.is_empty()
+                && tag.chars().count() <= MAX_TAG_LENGTH
+                && tag.chars().all(|c| c.is_alphanumeric() || c == '-')
+        }),
+        "ERR_INVALID_TAG"
+    );
+    tags
+}
@@ -0,0 +1,108 @@
+// 施設の場所ごとの税金
+// 税率は管理者が地域ごとに設定し、オーナーへの支払いから徴収した税金は地域の税金の受取人の売上として記録する
+
+use crate::treasury::MAX_PLATFORM_FEE_BPS;
+use crate::*;
+
+// 税率の上限（ベーシスポイント）
+const MAX_TAX_BPS: u16 = 10_000;
+
+// 地域ごとの税率と、徴収した税金の受取人
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TaxRate {
+    tax_bps: u16,              // 税率（ベーシスポイント）
+    tax_account_id: AccountId, // 税金の受取人
+}
+
+#[near_bindgen]
+impl Contract {
+    // 地域の税率と税金の受取人を設定する
+    // 地域は部屋の所在地の`city`と一致するもので、変更後の税率はこれから作成される予約にのみ適用される
+    // 手数料率との合計が、手数料率の上限を超える税率は設定できない
+    // 管理者のみがコールできる
+    pub fn set_tax_rate(&mut self, region: String, tax_bps: u16, tax_account_id: AccountId) {
+        self.assert_admin();
+        assert!(
+            tax_bps <= MAX_TAX_BPS
+                && tax_bps as u32 + self.platform_fee_bps as u32 <= MAX_PLATFORM_FEE_BPS as u32,
+            "ERR_INVALID_TAX_RATE"
+        );
+        self.tax_rates.insert(
+            region,
+            TaxRate {
+                tax_bps,
+                tax_account_id,
+            },
+        );
+    }
+
+    // 地域の税率を削除する
+    // 管理者のみがコールできる
+    pub fn remove_tax_rate(&mut self, region: String) {
+        self.assert_admin();
+        self.tax_rates
+            .remove(&region)
+            .expect("ERR_NOT_FOUND_TAX_RATE");
+    }
+
+    // 地域の税率を取得する
+    pub fn get_tax_rate(&self, region: String) -> Option<TaxRate> {
+        self.tax_rates.get(&region).cloned()
+    }
+
+    // 全ての地域の税率を取得する
+    pub fn get_tax_rates(&self) -> HashMap<String, TaxRate> {
+        self.tax_rates.clone()
+    }
+
+    // 地域でこれまでに徴収した税金の合計を取得する
+    // `token_id`が`None`の場合はNEARの額を返す
+    pub fn get_tax_collected(&self, region: String, token_id: Option<AccountId>) -> U128 {
+        U128(
+            self.tax_collected
+                .get(&region)
+                .and_then(|collected| collected.get(&token_id).copied())
+                .unwrap_or(0),
+        )
+    }
+}
+
+impl Contract {
+    // 予約する部屋の地域の税率を返す
+    pub(crate) fn tax_bps_for(&self, region: &str) -> u16 {
+        self.tax_rates
+            .get(region)
+            .map_or(0, |tax_rate| tax_rate.tax_bps)
+    }
+
+    // 全ての地域の中で最も高い税率を返す
+    pub(crate) fn max_tax_bps(&self) -> u16 {
+        self.tax_rates
+            .values()
+            .map(|tax_rate| tax_rate.tax_bps)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // オーナーへの支払い（手数料を除いた額）から予約時の税率で税金を徴収し、徴収した額を返す
+    // 地域の税率が削除されている場合は徴収しない
+    pub(crate) fn internal_collect_tax(&mut self, booking: &Booking, amount: Balance) -> Balance {
+        let tax_account_id = match self.tax_rates.get(&booking.tax_region) {
+            Some(tax_rate) => tax_rate.tax_account_id.clone(),
+            None => return 0,
+        };
+        let tax = amount * booking.tax_bps as Balance / MAX_TAX_BPS as Balance;
+        if tax == 0 {
+            return 0;
+        }
+        *self
+            .tax_collected
+            .entry(booking.tax_region.clone())
+            .or_default()
+            .entry(booking.payment_token.clone())
+            .or_default() += tax;
+        self.internal_credit_earnings(&tax_account_id, &booking.payment_token, tax);
+        tax
+    }
+}
@@ -0,0 +1,161 @@
+// ユニットテストで使用する共通の処理
+// テスト用のコンテキストを設定し、テストで使用する部屋や予約を作成する
+
+use near_sdk::mock::VmAction;
+use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+use near_sdk::{testing_env, ONE_NEAR};
+
+use crate::date::NANOS_PER_HOUR;
+use crate::*;
+
+// テストの部屋の1泊あたりの掲出料
+pub(crate) const PRICE: Balance = ONE_NEAR;
+
+pub(crate) fn contract_id() -> AccountId {
+    "booking.near".parse().unwrap()
+}
+
+pub(crate) fn owner_id() -> AccountId {
+    "owner.near".parse().unwrap()
+}
+
+pub(crate) fn guest_id() -> AccountId {
+    "guest.near".parse().unwrap()
+}
+
+// 同じ部屋を予約しようとする別の予約者
+pub(crate) fn other_guest_id() -> AccountId {
+    "other.near".parse().unwrap()
+}
+
+// テストを実行する日付（2030-01-01）
+pub(crate) fn today() -> Date {
+    Date::parse("2030-01-01").unwrap()
+}
+
+// 指定された日付のUTCの正午のタイムスタンプを返す
+pub(crate) fn noon(date: &Date) -> u64 {
+    date.start_timestamp() + 12 * NANOS_PER_HOUR
+}
+
+// 指定されたアカウントが、指定されたNEARを添付して今日の正午にコールするコンテキストを設定する
+pub(crate) fn set_caller(account_id: &AccountId, deposit: Balance) {
+    set_caller_at(account_id, deposit, noon(&today()));
+}
+
+// 指定されたアカウントが、指定されたNEARを添付して指定された時刻にコールするコンテキストを設定する
+pub(crate) fn set_caller_at(account_id: &AccountId, deposit: Balance, timestamp: u64) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(contract_id())
+        .signer_account_id(account_id.clone())
+        .predecessor_account_id(account_id.clone())
+        .attached_deposit(deposit)
+        .block_timestamp(timestamp)
+        .build());
+}
+
+// コントラクトを初期化し、オーナーの部屋を1つ登録する
+// 部屋は即時予約で、追加料金と保証金のない1泊`PRICE`の部屋
+pub(crate) fn setup() -> (Contract, RoomId) {
+    set_caller(&owner_id(), 10 * ONE_NEAR);
+    let mut contract = Contract::default();
+    let room_id = contract.add_room_to_owner(
+        "room".to_string(),
+        vec!["https://example.com/room.png".to_string()],
+        0,
+        1,
+        false,
+        1,
+        "test room".to_string(),
+        Location {
+            country: "JP".to_string(),
+            city: "Tokyo".to_string(),
+            ..Default::default()
+        },
+        U128(PRICE),
+        None,
+        false,
+        true,
+        24,
+        1,
+        30,
+        3650,
+        0,
+        24,
+        0,
+        U128(0),
+        0,
+        0,
+        U128(0),
+        0,
+        U128(0),
+        CancellationPolicy::Flexible,
+        U128(0),
+        U128(0),
+        1,
+        0,
+        RoomType::PrivateRoom,
+        vec![],
+    );
+    (contract, room_id)
+}
+
+// 予約者として指定された日付から`nights`泊を予約し、予約IDを返す
+pub(crate) fn book(
+    contract: &mut Contract,
+    room_id: &RoomId,
+    check_in_date: Date,
+    nights: u64,
+    deposit: Balance,
+) -> BookingId {
+    book_as(
+        contract,
+        &guest_id(),
+        room_id,
+        check_in_date,
+        nights,
+        deposit,
+    )
+}
+
+// 指定されたアカウントとして指定された日付から`nights`泊を予約し、予約IDを返す
+pub(crate) fn book_as(
+    contract: &mut Contract,
+    guest_id: &AccountId,
+    room_id: &RoomId,
+    check_in_date: Date,
+    nights: u64,
+    deposit: Balance,
+) -> BookingId {
+    set_caller(guest_id, deposit);
+    contract.book_stay(
+        room_id.clone(),
+        check_in_date,
+        check_in_date.add_days(nights),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+}
+
+// オーナーの売上の額を返す
+pub(crate) fn earnings_of(contract: &Contract, account_id: &AccountId) -> Balance {
+    contract.get_earnings(account_id.clone(), None).0
+}
+
+// 直前のコールで指定されたアカウントに送金されたNEARの合計を返す
+pub(crate) fn transferred_to(account_id: &AccountId) -> Balance {
+    get_created_receipts()
+        .iter()
+        .filter(|receipt| &receipt.receiver_id == account_id)
+        .flat_map(|receipt| receipt.actions.iter())
+        .map(|action| match action {
+            VmAction::Transfer { deposit } => *deposit,
+            _ => 0,
+        })
+        .sum()
+}
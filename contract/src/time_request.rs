@@ -0,0 +1,196 @@
+use crate::*;
+
+// アーリーチェックイン・レイトチェックアウトの種類
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum TimeRequestKind {
+    EarlyCheckIn, // チェックイン日に早めに到着する
+    LateCheckOut, // チェックアウト日に遅めに出発する
+}
+
+// リクエストの進行状況
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum TimeRequestStatus {
+    Pending,  // オーナーの承認待ち
+    Approved, // 承認済み・料金の支払い待ち
+    Accepted, // 確定
+    Rejected, // 却下
+}
+
+// 予約者からのアーリーチェックイン・レイトチェックアウトのリクエスト
+#[derive(Serialize, Deserialize, Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TimeRequest {
+    kind: TimeRequestKind,     // リクエストの種類
+    hour: u8,                  // 希望する時刻（現地時間）
+    fee: U128,                 // オーナーが設定した追加料金
+    status: TimeRequestStatus, // リクエストの進行状況
+}
+
+#[near_bindgen]
+impl Contract {
+    // 確定した予約に対して、アーリーチェックイン・レイトチェックアウトをリクエストする
+    // 同じ種類のリクエストを再度送ると、以前のリクエストは置き換えられる
+    pub fn request_time_change(&mut self, booking_id: BookingId, kind: TimeRequestKind, hour: u8) {
+        let mut booking = self.internal_booking(booking_id);
+        assert_eq!(
+            env::signer_account_id(),
+            booking.guest_id,
+            "ERR_NOT_BOOKING_GUEST"
+        );
+        assert!(
+            matches!(
+                booking.status,
+                BookingStatus::Confirmed | BookingStatus::CheckedIn
+            ),
+            "ERR_BOOKING_NOT_CONFIRMED"
+        );
+        assert!(hour < 24, "ERR_INVALID_HOUR");
+
+        // 支払い済みのリクエストは置き換えられない
+        assert!(
+            booking.time_request(&kind).map(|request| &request.status)
+                != Some(&TimeRequestStatus::Accepted),
+            "ERR_TIME_REQUEST_ACCEPTED"
+        );
+        booking.time_requests.retain(|request| request.kind != kind);
+        booking.time_requests.push(TimeRequest {
+            kind,
+            hour,
+            fee: U128(0),
+            status: TimeRequestStatus::Pending,
+        });
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+    }
+
+    // リクエストを承認し、必要に応じて追加料金を設定する
+    // 追加料金が0の場合は、そのままリクエストが確定する
+    // 部屋のオーナーのみがコールできる
+    pub fn approve_time_request(
+        &mut self,
+        booking_id: BookingId,
+        kind: TimeRequestKind,
+        fee: U128,
+    ) {
+        let mut booking = self.internal_booking(booking_id);
+        self.rooms_by_id
+            .get(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM")
+            .assert_owner();
+
+        let request = booking
+            .pending_time_request(&kind)
+            .expect("ERR_NOT_FOUND_TIME_REQUEST");
+        request.fee = fee;
+        request.status = if fee.0 == 0 {
+            TimeRequestStatus::Accepted
+        } else {
+            TimeRequestStatus::Approved
+        };
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+    }
+
+    // リクエストを却下する
+    // 部屋のオーナーのみがコールできる
+    pub fn reject_time_request(&mut self, booking_id: BookingId, kind: TimeRequestKind) {
+        let mut booking = self.internal_booking(booking_id);
+        self.rooms_by_id
+            .get(&booking.room_id)
+            .expect("ERR_NOT_FOUND_ROOM")
+            .assert_owner();
+
+        let request = booking
+            .pending_time_request(&kind)
+            .expect("ERR_NOT_FOUND_TIME_REQUEST");
+        request.status = TimeRequestStatus::Rejected;
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+    }
+
+    // 承認されたリクエストの追加料金を支払い、リクエストを確定する
+    // 追加料金はチェックアウト時に掲出料と合わせてオーナーの売上に記録される
+    // チェックイン時に支払いを確定した予約では、支払った時点でオーナーの売上に記録される
+    #[payable]
+    pub fn pay_time_request(&mut self, booking_id: BookingId, kind: TimeRequestKind) {
+        let mut booking = self.internal_booking(booking_id);
+        assert_eq!(
+            env::signer_account_id(),
+            booking.guest_id,
+            "ERR_NOT_BOOKING_GUEST"
+        );
+        // 終了した予約の追加料金は、オーナーに支払われずに預かり金に残ってしまう
+        assert!(
+            matches!(
+                booking.status,
+                BookingStatus::Confirmed | BookingStatus::CheckedIn
+            ),
+            "ERR_BOOKING_NOT_CONFIRMED"
+        );
+
+        let request = booking
+            .time_requests
+            .iter_mut()
+            .find(|request| request.kind == kind && request.status == TimeRequestStatus::Approved)
+            .expect("ERR_NOT_FOUND_TIME_REQUEST");
+        assert!(booking.payment_token.is_none(), "ERR_PAY_WITH_TOKEN");
+        assert_eq!(
+            env::attached_deposit(),
+            request.fee.0,
+            "ERR_DEPOSIT_IS_INCORRECT"
+        );
+        request.status = TimeRequestStatus::Accepted;
+        let fee = request.fee.0;
+        booking.fees = U128(booking.fees.0 + fee);
+        booking.updated_at = env::block_timestamp();
+        self.bookings_by_id.insert(&booking_id, &booking);
+        self.internal_hold_escrow(booking_id, fee);
+        self.internal_capture_added_fee(booking_id, fee);
+    }
+}
+
+impl Booking {
+    // 指定された種類のリクエストを取得する
+    pub(crate) fn time_request(&self, kind: &TimeRequestKind) -> Option<&TimeRequest> {
+        self.time_requests
+            .iter()
+            .find(|request| &request.kind == kind)
+    }
+
+    // 指定された種類の承認待ちのリクエストを取得する
+    fn pending_time_request(&mut self, kind: &TimeRequestKind) -> Option<&mut TimeRequest> {
+        self.time_requests
+            .iter_mut()
+            .find(|request| &request.kind == kind && request.status == TimeRequestStatus::Pending)
+    }
+
+    // 確定したレイトチェックアウトの時刻を返す
+    pub(crate) fn late_check_out_hour(&self) -> Option<u8> {
+        self.time_request(&TimeRequestKind::LateCheckOut)
+            .filter(|request| request.status == TimeRequestStatus::Accepted)
+            .map(|request| request.hour)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    #[should_panic(expected = "ERR_BOOKING_NOT_CONFIRMED")]
+    fn rejects_payment_for_cancelled_booking() {
+        let (mut contract, room_id) = setup();
+        let booking_id = book(&mut contract, &room_id, today().add_days(10), 1, PRICE);
+        contract.request_time_change(booking_id, TimeRequestKind::LateCheckOut, 14);
+        set_caller(&owner_id(), 0);
+        contract.approve_time_request(booking_id, TimeRequestKind::LateCheckOut, U128(PRICE));
+
+        set_caller(&guest_id(), 0);
+        contract.cancel_booking(booking_id);
+        set_caller(&guest_id(), PRICE);
+        contract.pay_time_request(booking_id, TimeRequestKind::LateCheckOut);
+    }
+}
@@ -0,0 +1,45 @@
+// 宿泊後に予約者からオーナーへ送るチップ
+
+use near_sdk::serde_json::json;
+
+use crate::event::emit_event;
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // チェックアウト後の予約のオーナーにチップを送る
+    // 添付したNEARは手数料を差し引かずに全額がオーナーの売上として記録される
+    // 予約したアカウントのみがコールできる
+    #[payable]
+    pub fn tip_host(&mut self, booking_id: BookingId) -> U128 {
+        let mut booking = self.internal_booking(booking_id);
+        assert_eq!(
+            env::signer_account_id(),
+            booking.guest_id,
+            "ERR_NOT_BOOKING_GUEST"
+        );
+        assert_eq!(
+            booking.status,
+            BookingStatus::CheckedOut,
+            "ERR_NOT_CHECKED_OUT"
+        );
+        let tip = env::attached_deposit();
+        assert!(tip > 0, "ERR_NO_TIP_ATTACHED");
+
+        let owner_id = self.rooms_by_id[&booking.room_id].owner_id.clone();
+        self.internal_credit_earnings(&owner_id, &None, tip);
+        booking.tips = U128(booking.tips.0 + tip);
+        self.bookings_by_id.insert(&booking_id, &booking);
+
+        emit_event(
+            "tip",
+            json!({
+                "booking_id": booking_id,
+                "owner_id": owner_id,
+                "guest_id": booking.guest_id,
+                "amount": U128(tip),
+            }),
+        );
+        booking.tips
+    }
+}
@@ -0,0 +1,212 @@
+// マーケットプレイスの手数料と、その受け取り先のトレジャリー
+
+use near_sdk::serde_json::json;
+use near_sdk::{Gas, PromiseResult};
+
+use crate::ft_payment::transfer_to;
+use crate::*;
+
+// 手数料率の上限（ベーシスポイント）
+// 手数料率と地域の税率の合計も、この上限を超えられない
+pub(crate) const MAX_PLATFORM_FEE_BPS: u16 = 10_000;
+
+// 送金の結果を確認するコールバックに割り当てるガス
+const GAS_FOR_ON_TREASURY_WITHDRAWN: Gas = Gas(10_000_000_000_000);
+
+#[near_bindgen]
+impl Contract {
+    // オーナーへの支払いから差し引く手数料率（ベーシスポイント）を変更する
+    // 変更後の手数料率は、これから作成される予約にのみ適用される
+    // 最も高い地域の税率との合計が、上限を超える手数料率は設定できない
+    // 管理者のみがコールできる
+    pub fn set_platform_fee(&mut self, platform_fee_bps: u16) {
+        self.assert_admin();
+        assert!(
+            platform_fee_bps as u32 + self.max_tax_bps() as u32 <= MAX_PLATFORM_FEE_BPS as u32,
+            "ERR_INVALID_PLATFORM_FEE"
+        );
+        self.platform_fee_bps = platform_fee_bps;
+    }
+
+    // 手数料を受け取るトレジャリーのアカウントを変更する
+    // 管理者のみがコールできる
+    pub fn set_treasury(&mut self, treasury_id: AccountId) {
+        self.assert_admin();
+        self.treasury_id = treasury_id;
+    }
+
+    // 現在の手数料率を取得する
+    pub fn get_platform_fee(&self) -> u16 {
+        self.platform_fee_bps
+    }
+
+    // トレジャリーのアカウントを取得する
+    pub fn get_treasury(&self) -> AccountId {
+        self.treasury_id.clone()
+    }
+
+    // 貯まっている手数料の額を取得する
+    // `token_id`が`None`の場合はNEARの額を返す
+    pub fn get_treasury_balance(&self, token_id: Option<AccountId>) -> U128 {
+        U128(self.treasury_balances.get(&token_id).copied().unwrap_or(0))
+    }
+
+    // 貯まっている手数料をトレジャリーのアカウントに送金する
+    // 送金に失敗した場合は、手数料が台帳に戻される
+    // 管理者のみがコールできる
+    pub fn withdraw_treasury(&mut self, token_id: Option<AccountId>) -> Promise {
+        self.assert_admin();
+        let balance = self
+            .treasury_balances
+            .remove(&token_id)
+            .expect("ERR_NO_TREASURY_BALANCE");
+
+        transfer_to(self.treasury_id.clone(), &token_id, balance).then(
+            Promise::new(env::current_account_id()).function_call(
+                "on_treasury_withdrawn".to_string(),
+                json!({ "token_id": token_id, "amount": U128(balance) })
+                    .to_string()
+                    .into_bytes(),
+                0,
+                GAS_FOR_ON_TREASURY_WITHDRAWN,
+            ),
+        )
+    }
+
+    // 手数料の送金結果を確認し、失敗していた場合は台帳に戻す
+    #[private]
+    pub fn on_treasury_withdrawn(&mut self, token_id: Option<AccountId>, amount: U128) -> bool {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return true;
+        }
+        *self.treasury_balances.entry(token_id).or_default() += amount.0;
+        false
+    }
+}
+
+impl Contract {
+    // トレジャリーに貯まっている手数料から報酬などを支払う
+    // 手数料が足りない場合は何もせずに`false`を返す
+    pub(crate) fn internal_spend_treasury(
+        &mut self,
+        token_id: &Option<AccountId>,
+        amount: Balance,
+    ) -> bool {
+        let balance = self.treasury_balances.get(token_id).copied().unwrap_or(0);
+        if balance < amount {
+            return false;
+        }
+        if balance == amount {
+            self.treasury_balances.remove(token_id);
+        } else {
+            self.treasury_balances
+                .insert(token_id.clone(), balance - amount);
+        }
+        true
+    }
+
+    // 予約の預かり金からオーナーに支払う
+    // 予約時の手数料率で計算した手数料からアフィリエイトの成果報酬と保険基金への積立を除いた分をトレジャリーに積み立て、地域の税金を徴収し、残りを部屋の受取人ごとの売上として記録する
+    // 税金は手数料を除いた額に対して徴収するため、手数料と税金の合計が支払う額を超えることはない
+    pub(crate) fn internal_pay_owner(&mut self, booking_id: BookingId, amount: Balance) {
+        let booking = self.internal_booking(booking_id);
+        let fee = amount * booking.platform_fee_bps as Balance / MAX_PLATFORM_FEE_BPS as Balance;
+        self.internal_debit_escrow(booking_id, amount);
+        let treasury_fee = self.internal_pay_affiliate(&booking, amount, fee);
+        let treasury_fee = self.internal_fund_insurance(&booking.payment_token, treasury_fee);
+        if treasury_fee > 0 {
+            *self
+                .treasury_balances
+                .entry(booking.payment_token.clone())
+                .or_default() += treasury_fee;
+        }
+        let tax = self.internal_collect_tax(&booking, amount - fee);
+        let payouts = self.rooms_by_id[&booking.room_id].split_payout(amount - fee - tax);
+        for (account_id, payout) in payouts {
+            self.internal_credit_earnings(&account_id, &booking.payment_token, payout);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    // 今日から1泊の予約を作成し、チェックインしてチェックアウトする
+    fn stay(contract: &mut Contract, room_id: &RoomId) -> BookingId {
+        let booking_id = book(contract, room_id, today(), 1, PRICE);
+        contract.check_in(booking_id);
+        contract.check_out(booking_id);
+        booking_id
+    }
+
+    #[test]
+    fn pays_whole_amount_as_fee_at_max_platform_fee() {
+        let (mut contract, room_id) = setup();
+        set_caller(&contract_id(), 0);
+        contract.set_platform_fee(MAX_PLATFORM_FEE_BPS);
+
+        let booking_id = stay(&mut contract, &room_id);
+
+        assert_eq!(earnings_of(&contract, &owner_id()), 0);
+        assert_eq!(contract.get_treasury_balance(None).0, PRICE);
+        assert_eq!(contract.get_escrow(booking_id).0, 0);
+    }
+
+    #[test]
+    fn collects_tax_on_amount_after_fee() {
+        let (mut contract, room_id) = setup();
+        let tax_account_id: AccountId = "tax.near".parse().unwrap();
+        set_caller(&contract_id(), 0);
+        contract.set_platform_fee(2_000);
+        contract.set_tax_rate("Tokyo".to_string(), 8_000, tax_account_id.clone());
+
+        stay(&mut contract, &room_id);
+
+        let fee = PRICE / 5;
+        let tax = (PRICE - fee) * 4 / 5;
+        assert_eq!(contract.get_treasury_balance(None).0, fee);
+        assert_eq!(earnings_of(&contract, &tax_account_id), tax);
+        assert_eq!(earnings_of(&contract, &owner_id()), PRICE - fee - tax);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_TAX_RATE")]
+    fn rejects_tax_rate_exceeding_limit_with_platform_fee() {
+        let (mut contract, _) = setup();
+        set_caller(&contract_id(), 0);
+        contract.set_platform_fee(2_000);
+        contract.set_tax_rate("Tokyo".to_string(), 8_001, "tax.near".parse().unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_PLATFORM_FEE")]
+    fn rejects_platform_fee_exceeding_limit_with_tax_rate() {
+        let (mut contract, _) = setup();
+        set_caller(&contract_id(), 0);
+        contract.set_tax_rate("Tokyo".to_string(), 8_000, "tax.near".parse().unwrap());
+        contract.set_platform_fee(2_001);
+    }
+
+    #[test]
+    fn pays_booking_whose_fee_and_tax_exceed_limit() {
+        let (mut contract, room_id) = setup();
+        let tax_account_id: AccountId = "tax.near".parse().unwrap();
+        set_caller(&contract_id(), 0);
+        contract.set_tax_rate("Tokyo".to_string(), 0, tax_account_id.clone());
+        let booking_id = book(&mut contract, &room_id, today(), 1, PRICE);
+
+        // 合計が上限を超える率で作成された予約でも、チェックアウトできる
+        let mut booking = contract.internal_booking(booking_id);
+        booking.platform_fee_bps = MAX_PLATFORM_FEE_BPS;
+        booking.tax_bps = 10_000;
+        contract.bookings_by_id.insert(&booking_id, &booking);
+        contract.check_in(booking_id);
+        contract.check_out(booking_id);
+
+        assert_eq!(contract.get_treasury_balance(None).0, PRICE);
+        assert_eq!(earnings_of(&contract, &tax_account_id), 0);
+        assert_eq!(earnings_of(&contract, &owner_id()), 0);
+    }
+}
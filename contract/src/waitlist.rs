@@ -0,0 +1,143 @@
+use crate::*;
+
+// キャンセル待ちの先頭のアカウントが予約できる期間（1時間）
+const WAITLIST_CLAIM_DURATION: u64 = 60 * 60 * 1_000_000_000;
+
+// 1つの宿泊日に登録できるキャンセル待ちの最大数
+const MAX_WAITLIST_LENGTH: usize = 20;
+
+#[near_bindgen]
+impl Contract {
+    // 満室の宿泊日のキャンセル待ちに登録する
+    // 予約がキャンセルされると、登録順に一定時間だけ優先的に予約できる
+    pub fn join_waitlist(&mut self, room_id: RoomId, check_in_date: CheckInDate) {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        let guest_id = env::signer_account_id();
+
+        // 予約可能な日付はキャンセル待ちの必要がない
+        assert!(
+            room.vacancies(&check_in_date, Some(&guest_id)) == 0,
+            "ERR_ROOM_IS_AVAILABLE"
+        );
+
+        let initial_size = room.serialized_size();
+        let waitlist = room.waitlists.entry(check_in_date).or_default();
+        assert!(!waitlist.contains(&guest_id), "ERR_ALREADY_ON_WAITLIST");
+        assert!(waitlist.len() < MAX_WAITLIST_LENGTH, "ERR_WAITLIST_IS_FULL");
+        waitlist.push(guest_id);
+        self.internal_track_room_storage(&room_id, initial_size);
+    }
+
+    // キャンセル待ちの登録を取り消す
+    pub fn leave_waitlist(&mut self, room_id: RoomId, check_in_date: CheckInDate) {
+        let room = self
+            .rooms_by_id
+            .get_mut(&room_id)
+            .expect("ERR_NOT_FOUND_ROOM");
+        let guest_id = env::signer_account_id();
+        let initial_size = room.serialized_size();
+
+        let waitlist = room
+            .waitlists
+            .get_mut(&check_in_date)
+            .expect("ERR_NOT_ON_WAITLIST");
+        let index = waitlist
+            .iter()
+            .position(|account_id| account_id == &guest_id)
+            .expect("ERR_NOT_ON_WAITLIST");
+        waitlist.remove(index);
+        if waitlist.is_empty() {
+            room.waitlists.remove(&check_in_date);
+        }
+        self.internal_track_room_storage(&room_id, initial_size);
+    }
+
+    // 宿泊日のキャンセル待ちを登録順に取得する
+    pub fn get_waitlist(&self, room_id: RoomId, check_in_date: CheckInDate) -> Vec<AccountId> {
+        let room = self.rooms_by_id.get(&room_id).expect("ERR_NOT_FOUND_ROOM");
+        room.waitlists
+            .get(&check_in_date)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+impl Room {
+    // キャンセルされた宿泊日を、キャンセル待ちの先頭のアカウントに仮押さえする
+    // 期間内に予約されなければ、誰でも予約できるようになる
+    pub(crate) fn offer_to_waitlist(&mut self, date: &CheckInDate) {
+        let waitlist = match self.waitlists.get_mut(date) {
+            Some(waitlist) => waitlist,
+            None => return,
+        };
+        let guest_id = waitlist.remove(0);
+        if waitlist.is_empty() {
+            self.waitlists.remove(date);
+        }
+        self.place_hold(*date, guest_id, WAITLIST_CLAIM_DURATION, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    #[test]
+    #[should_panic(expected = "ERR_ROOM_ON_HOLD")]
+    fn holds_cancelled_night_for_first_waitlisted_guest() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = today().add_days(10);
+        let booking_id = book(&mut contract, &room_id, check_in_date, 1, PRICE);
+        set_caller(&other_guest_id(), 0);
+        contract.join_waitlist(room_id.clone(), check_in_date);
+
+        set_caller(&guest_id(), 0);
+        contract.cancel_booking(booking_id);
+        assert!(contract
+            .get_waitlist(room_id.clone(), check_in_date)
+            .is_empty());
+        assert_eq!(contract.get_holds(room_id.clone(), check_in_date).len(), 1);
+
+        // キャンセル待ちの予約者が仮押さえしている間は、他のアカウントは予約できない
+        book_as(
+            &mut contract,
+            &"third.near".parse().unwrap(),
+            &room_id,
+            check_in_date,
+            1,
+            PRICE,
+        );
+    }
+
+    #[test]
+    fn waitlisted_guest_books_held_night() {
+        let (mut contract, room_id) = setup();
+        let check_in_date = today().add_days(10);
+        let booking_id = book(&mut contract, &room_id, check_in_date, 1, PRICE);
+        set_caller(&other_guest_id(), 0);
+        contract.join_waitlist(room_id.clone(), check_in_date);
+        set_caller(&guest_id(), 0);
+        contract.cancel_booking(booking_id);
+
+        book_as(
+            &mut contract,
+            &other_guest_id(),
+            &room_id,
+            check_in_date,
+            1,
+            PRICE,
+        );
+        assert!(contract.get_holds(room_id, check_in_date).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ROOM_IS_AVAILABLE")]
+    fn rejects_waitlist_for_available_night() {
+        let (mut contract, room_id) = setup();
+        set_caller(&guest_id(), 0);
+        contract.join_waitlist(room_id, today().add_days(10));
+    }
+}
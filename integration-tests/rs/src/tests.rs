@@ -1,5 +1,6 @@
 use near_units::parse_near;
 use serde_json::json;
+use std::collections::HashMap;
 use workspaces::prelude::*;
 use workspaces::{network::Sandbox, Account, Contract, Worker};
 
@@ -20,7 +21,24 @@ async fn main() -> anyhow::Result<()> {
         .await?
         .into_result()?;
 
+    let bob = owner
+        .create_subaccount(&worker, "bob")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    let carol = owner
+        .create_subaccount(&worker, "carol")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+
     // begin tests
+    let room_id = add_room(&alice, &contract, &worker).await?;
+    test_rejects_double_booking(&bob, &carol, &room_id, &contract, &worker).await?;
+    test_rejects_overlapping_stay(&bob, &carol, &room_id, &contract, &worker).await?;
+    test_concurrent_bookings(&bob, &carol, &room_id, &contract, &worker).await?;
     test_default_message(&alice, &contract, &worker).await?;
     test_changes_message(&alice, &contract, &worker).await?;
     Ok(())
@@ -63,4 +81,235 @@ async fn test_changes_message(
     assert_eq!(message, "Howdy".to_string());
     println!("      Passed ✅ changes message");
     Ok(())
-}
\ No newline at end of file
+}
+
+async fn add_room(
+    owner: &Account,
+    contract: &Contract,
+    worker: &Worker<Sandbox>,
+) -> anyhow::Result<String> {
+    let room_id: String = owner
+        .call(&worker, contract.id(), "add_room_to_owner")
+        .args_json(json!({
+            "name": "room",
+            "images": ["https://example.com/room.png"],
+            "cover_image_index": 0,
+            "beds": 1,
+            "bookable_by_bed": false,
+            "units": 1,
+            "description": "test room",
+            "location": {
+                "country": "JP",
+                "city": "Tokyo",
+                "address_line": "1-1 Marunouchi, Chiyoda-ku",
+                "postal_code": "100-0005",
+                "lat": 35681236,
+                "lng": 139767125,
+            },
+            "price": parse_near!("1 N").to_string(),
+            "payment_token": null,
+            "usd_pricing": false,
+            "instant_book": true,
+            "approval_window_hours": 24,
+            "min_nights": 1,
+            "max_nights": 30,
+            "booking_window_days": 3650,
+            "min_lead_time_hours": 0,
+            "same_day_cutoff_hour": 24,
+            "timezone_offset_minutes": 0,
+            "security_deposit": "0",
+            "deposit_release_days": 0,
+            "overstay_grace_hours": 0,
+            "overstay_penalty_per_day": "0",
+            "free_reschedule_hours": 0,
+            "reschedule_fee": "0",
+            "cancellation_policy": "Flexible",
+            "cleaning_fee": "0",
+            "extra_guest_fee": "0",
+            "included_guests": 1,
+            "max_guests": 2,
+            "room_type": "PrivateRoom",
+            "amenities": ["wifi"],
+        }))?
+        .deposit(parse_near!("1 N"))
+        .transact()
+        .await?
+        .json()?;
+
+    Ok(room_id)
+}
+
+async fn book_stay(
+    guest: &Account,
+    room_id: &str,
+    check_in_date: &str,
+    check_out_date: &str,
+    deposit: u128,
+    contract: &Contract,
+    worker: &Worker<Sandbox>,
+) -> bool {
+    let result = guest
+        .call(&worker, contract.id(), "book_stay")
+        .args_json(json!({
+            "room_id": room_id,
+            "check_in_date": check_in_date,
+            "check_out_date": check_out_date,
+        }))
+        .expect("invalid args")
+        .deposit(deposit)
+        .transact()
+        .await;
+
+    matches!(result, Ok(details) if details.is_success())
+}
+
+async fn get_availability(
+    room_id: &str,
+    from_date: &str,
+    to_date: &str,
+    contract: &Contract,
+    worker: &Worker<Sandbox>,
+) -> anyhow::Result<HashMap<String, bool>> {
+    let availability: HashMap<String, bool> = contract
+        .view(
+            &worker,
+            "get_room_availability",
+            json!({
+                "room_id": room_id,
+                "from_date": from_date,
+                "to_date": to_date,
+            })
+            .to_string()
+            .into_bytes(),
+        )
+        .await?
+        .json()?;
+
+    Ok(availability)
+}
+
+async fn test_rejects_double_booking(
+    first_guest: &Account,
+    second_guest: &Account,
+    room_id: &str,
+    contract: &Contract,
+    worker: &Worker<Sandbox>,
+) -> anyhow::Result<()> {
+    let deposit = parse_near!("1 N");
+    assert!(
+        book_stay(
+            first_guest,
+            room_id,
+            "2030-01-01",
+            "2030-01-02",
+            deposit,
+            contract,
+            worker
+        )
+        .await
+    );
+
+    // 同じ日付の予約は失敗し、添付したNEARは返金される
+    let balance_before = second_guest.view_account(&worker).await?.balance;
+    assert!(
+        !book_stay(
+            second_guest,
+            room_id,
+            "2030-01-01",
+            "2030-01-02",
+            deposit,
+            contract,
+            worker
+        )
+        .await
+    );
+    let balance_after = second_guest.view_account(&worker).await?.balance;
+    assert!(balance_before - balance_after < deposit);
+
+    println!("      Passed ✅ rejects double booking");
+    Ok(())
+}
+
+async fn test_rejects_overlapping_stay(
+    first_guest: &Account,
+    second_guest: &Account,
+    room_id: &str,
+    contract: &Contract,
+    worker: &Worker<Sandbox>,
+) -> anyhow::Result<()> {
+    let deposit = parse_near!("3 N");
+    assert!(
+        book_stay(
+            first_guest,
+            room_id,
+            "2030-02-01",
+            "2030-02-04",
+            deposit,
+            contract,
+            worker
+        )
+        .await
+    );
+
+    // 一部の日付が重なる予約は全体が失敗し、重ならない日付も予約されない
+    assert!(
+        !book_stay(
+            second_guest,
+            room_id,
+            "2030-02-03",
+            "2030-02-06",
+            deposit,
+            contract,
+            worker
+        )
+        .await
+    );
+    let availability =
+        get_availability(room_id, "2030-02-01", "2030-02-06", contract, worker).await?;
+    assert!(!availability["2030-02-03"]);
+    assert!(availability["2030-02-04"]);
+    assert!(availability["2030-02-05"]);
+
+    println!("      Passed ✅ rejects overlapping stay");
+    Ok(())
+}
+
+async fn test_concurrent_bookings(
+    first_guest: &Account,
+    second_guest: &Account,
+    room_id: &str,
+    contract: &Contract,
+    worker: &Worker<Sandbox>,
+) -> anyhow::Result<()> {
+    let deposit = parse_near!("1 N");
+
+    // 同じ日付への予約を同時に送信しても、成功するのは一方のみ
+    let (first, second) = tokio::join!(
+        book_stay(
+            first_guest,
+            room_id,
+            "2030-03-01",
+            "2030-03-02",
+            deposit,
+            contract,
+            worker
+        ),
+        book_stay(
+            second_guest,
+            room_id,
+            "2030-03-01",
+            "2030-03-02",
+            deposit,
+            contract,
+            worker
+        ),
+    );
+    assert!(first ^ second);
+
+    let availability =
+        get_availability(room_id, "2030-03-01", "2030-03-02", contract, worker).await?;
+    assert!(!availability["2030-03-01"]);
+
+    println!("      Passed ✅ accepts only one of concurrent bookings");
+    Ok(())
+}